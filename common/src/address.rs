@@ -0,0 +1,232 @@
+//! Address parsing with EIP-55 checksum verification
+//!
+//! Mixed-case input must match its checksum exactly. All-lowercase and
+//! all-uppercase input carries no checksum, so it is accepted with a warning.
+//! Addresses that are almost never a valid recipient (the zero address and
+//! precompiles) are flagged as well.
+
+use ethers::types::H160;
+use ethers::utils::to_checksum;
+use std::fmt;
+
+/// Something worth telling the user about a parsed address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressWarning {
+    /// Input was all-lowercase or all-uppercase, so typos cannot be detected
+    NoChecksum,
+    /// The zero address; funds sent there are lost
+    ZeroAddress,
+    /// A precompile or system contract, with its name
+    Precompile(&'static str),
+}
+
+impl fmt::Display for AddressWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressWarning::NoChecksum => {
+                write!(f, "address has no EIP-55 checksum (all one case), typos cannot be detected")
+            }
+            AddressWarning::ZeroAddress => write!(f, "this is the zero address, funds sent to it are lost"),
+            AddressWarning::Precompile(name) => write!(f, "this is the {} precompile, not a regular account", name),
+        }
+    }
+}
+
+/// A successfully parsed address and any warnings about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedAddress {
+    pub address: H160,
+    pub warnings: Vec<AddressWarning>,
+}
+
+impl ParsedAddress {
+    /// A parsed address with only the zero/precompile warnings
+    pub fn from_address(address: H160) -> Self {
+        ParsedAddress {
+            address,
+            warnings: special_address_warning(address).into_iter().collect(),
+        }
+    }
+
+    /// Whether the address should not receive funds
+    pub fn is_unusable_recipient(&self) -> bool {
+        self.warnings
+            .iter()
+            .any(|w| matches!(w, AddressWarning::ZeroAddress | AddressWarning::Precompile(_)))
+    }
+}
+
+/// Ethereum precompiles (0x01 - 0x0a) plus the P-256 verifier (0x0100)
+const ETHEREUM_PRECOMPILES: &[(u64, &str)] = &[
+    (0x01, "ecRecover"),
+    (0x02, "SHA2-256"),
+    (0x03, "RIPEMD-160"),
+    (0x04, "identity"),
+    (0x05, "modexp"),
+    (0x06, "ecAdd"),
+    (0x07, "ecMul"),
+    (0x08, "ecPairing"),
+    (0x09, "blake2f"),
+    (0x0a, "point evaluation"),
+    (0x0100, "P256VERIFY"),
+];
+
+/// Arbitrum system contracts
+const ARBITRUM_PRECOMPILES: &[(u64, &str)] = &[
+    (0x64, "ArbSys"),
+    (0x65, "ArbInfo"),
+    (0x66, "ArbAddressTable"),
+    (0x67, "ArbBLS"),
+    (0x68, "ArbFunctionTable"),
+    (0x69, "ArbosTest"),
+    (0x6b, "ArbOwnerPublic"),
+    (0x6c, "ArbGasInfo"),
+    (0x6d, "ArbAggregator"),
+    (0x6e, "ArbRetryableTx"),
+    (0x6f, "ArbStatistics"),
+    (0x70, "ArbOwner"),
+    (0x71, "ArbWasm"),
+    (0x72, "ArbWasmCache"),
+    (0xc8, "NodeInterface"),
+    (0xc9, "NodeInterfaceDebug"),
+];
+
+/// Name of the precompile at an address, if it is one
+pub fn precompile_name(address: H160) -> Option<&'static str> {
+    // Precompiles live in the low 8 bytes with everything above zero
+    if address.as_bytes()[..12].iter().any(|b| *b != 0) {
+        return None;
+    }
+    let mut low = [0u8; 8];
+    low.copy_from_slice(&address.as_bytes()[12..]);
+    let value = u64::from_be_bytes(low);
+    ETHEREUM_PRECOMPILES
+        .iter()
+        .chain(ARBITRUM_PRECOMPILES)
+        .find(|(a, _)| *a == value)
+        .map(|(_, name)| *name)
+}
+
+/// Zero address or precompile warning for an address
+fn special_address_warning(address: H160) -> Option<AddressWarning> {
+    if address.is_zero() {
+        return Some(AddressWarning::ZeroAddress);
+    }
+    precompile_name(address).map(AddressWarning::Precompile)
+}
+
+/// Render an address in EIP-55 checksummed form
+pub fn checksummed(address: H160) -> String {
+    to_checksum(&address, None)
+}
+
+/// Parse an address, verifying its EIP-55 checksum
+///
+/// # Arguments
+/// * `input` - Address with or without the 0x prefix
+///
+/// # Returns
+/// * `Result<ParsedAddress, String>` - Address and warnings, or a diagnostic
+///   naming what is wrong with the input
+pub fn parse_address(input: &str) -> Result<ParsedAddress, String> {
+    let input = input.trim();
+    let hex = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+
+    if let Some((i, c)) = hex.chars().enumerate().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "Invalid address {}: non-hex character '{}' at position {}",
+            input,
+            c,
+            i + 1
+        ));
+    }
+    if hex.len() != 40 {
+        return Err(format!(
+            "Invalid address length: {} has {} hex characters, expected 40",
+            input,
+            hex.len()
+        ));
+    }
+
+    let mut bytes = [0u8; 20];
+    ethers::utils::hex::decode_to_slice(hex, &mut bytes).map_err(|e| format!("Invalid address {}: {}", input, e))?;
+    let address = H160::from(bytes);
+
+    let mut warnings = Vec::new();
+    let has_lower = hex.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = hex.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        let expected = checksummed(address);
+        if expected[2..] != *hex {
+            return Err(format!(
+                "Checksum mismatch for {}: expected {}. The address is probably mistyped",
+                input, expected
+            ));
+        }
+    } else if has_lower || has_upper {
+        // Digit-only addresses have nothing to checksum
+        warnings.push(AddressWarning::NoChecksum);
+    }
+    warnings.extend(special_address_warning(address));
+
+    Ok(ParsedAddress { address, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const CHECKSUMMED: &str = "0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439";
+
+    #[test]
+    fn test_checksum_verification() {
+        let parsed = parse_address(CHECKSUMMED).unwrap();
+        assert!(parsed.warnings.is_empty());
+        assert_eq!(checksummed(parsed.address), CHECKSUMMED);
+
+        // One letter with the wrong case
+        let err = parse_address("0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439".replace('E', "e").as_str()).unwrap_err();
+        assert!(err.contains("Checksum mismatch"), "{}", err);
+        assert!(err.contains(CHECKSUMMED));
+
+        let lower = parse_address(&CHECKSUMMED.to_lowercase()).unwrap();
+        assert_eq!(lower.address, parsed.address);
+        assert_eq!(lower.warnings, vec![AddressWarning::NoChecksum]);
+        let upper = parse_address(&format!("0x{}", CHECKSUMMED[2..].to_uppercase())).unwrap();
+        assert_eq!(upper.warnings, vec![AddressWarning::NoChecksum]);
+        assert!(parse_address(&CHECKSUMMED[2..]).is_ok());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let err = parse_address("0x123").unwrap_err();
+        assert!(err.contains("has 3 hex characters"), "{}", err);
+        let err = parse_address("0xd78677EFed3b87f8f421E68dA3F984ad8Ef7643g").unwrap_err();
+        assert!(err.contains("non-hex character 'g' at position 40"), "{}", err);
+        assert!(parse_address("not_an_address").is_err());
+    }
+
+    #[test]
+    fn test_special_addresses() {
+        let zero = parse_address("0x0000000000000000000000000000000000000000").unwrap();
+        assert_eq!(zero.warnings, vec![AddressWarning::ZeroAddress]);
+        assert!(zero.is_unusable_recipient());
+
+        let ecrecover = parse_address("0x0000000000000000000000000000000000000001").unwrap();
+        assert_eq!(ecrecover.warnings, vec![AddressWarning::Precompile("ecRecover")]);
+        assert_eq!(
+            precompile_name(H160::from_str("0x0000000000000000000000000000000000000064").unwrap()),
+            Some("ArbSys")
+        );
+        assert_eq!(
+            precompile_name(H160::from_str("0x00000000000000000000000000000000000000C8").unwrap()),
+            Some("NodeInterface")
+        );
+        assert_eq!(precompile_name(H160::from_str("0x0000000000000000000000000000000000000063").unwrap()), None);
+        assert!(!parse_address(CHECKSUMMED).unwrap().is_unusable_recipient());
+    }
+}
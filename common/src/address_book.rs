@@ -0,0 +1,293 @@
+//! Local address book of labelled recipients
+//!
+//! Entries live in a JSON or TOML file (picked by extension) and can be used
+//! anywhere an address is accepted. Addresses in the book must be written in
+//! their EIP-55 checksummed form so a mistyped entry is caught on load.
+
+use crate::address::{checksummed, parse_address, AddressWarning, ParsedAddress};
+use ethers::types::H160;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A labelled recipient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressEntry {
+    pub label: String,
+    #[serde(serialize_with = "serialize_checksummed", deserialize_with = "deserialize_checksummed")]
+    pub address: H160,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Chain IDs the entry may be used on; empty means any chain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chains: Vec<u64>,
+}
+
+impl AddressEntry {
+    /// Whether the entry may be used on the given chain
+    pub fn allows_chain(&self, chain_id: u64) -> bool {
+        self.chains.is_empty() || self.chains.contains(&chain_id)
+    }
+}
+
+/// The address book file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AddressBook {
+    #[serde(default)]
+    pub entries: Vec<AddressEntry>,
+}
+
+fn serialize_checksummed<S: Serializer>(address: &H160, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&checksummed(*address))
+}
+
+fn deserialize_checksummed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<H160, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_checksummed(&value).map_err(serde::de::Error::custom)
+}
+
+/// Parse an address that must be in its exact EIP-55 checksummed form
+///
+/// # Arguments
+/// * `value` - Address string with 0x prefix
+///
+/// # Returns
+/// * `Result<H160, String>` - Parsed address
+pub fn parse_checksummed(value: &str) -> Result<H160, String> {
+    let parsed = parse_address(value)?;
+    if parsed.warnings.contains(&AddressWarning::NoChecksum) || !value.trim().starts_with("0x") {
+        return Err(format!(
+            "Address {} is not checksummed (expected {})",
+            value,
+            checksummed(parsed.address)
+        ));
+    }
+    Ok(parsed.address)
+}
+
+/// Path of the address book: `ADDRESS_BOOK` or `address_book.json`
+pub fn address_book_path() -> PathBuf {
+    PathBuf::from(env::var("ADDRESS_BOOK").unwrap_or_else(|_| "address_book.json".to_string()))
+}
+
+/// Whether a file should be read and written as TOML
+fn is_toml(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some("toml")
+}
+
+impl AddressBook {
+    /// Load an address book, returning an empty one if the file does not exist
+    ///
+    /// # Arguments
+    /// * `path` - JSON or TOML file
+    ///
+    /// # Returns
+    /// * `Result<AddressBook, Box<dyn std::error::Error>>` - Parsed and validated book
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(AddressBook::default());
+        }
+        let text = fs::read_to_string(path)
+            .map_err(|_| format!("Failed to read address book: {}", path.display()))?;
+        let book: AddressBook = if is_toml(path) {
+            toml::from_str(&text).map_err(|e| format!("Invalid address book {}: {}", path.display(), e))?
+        } else {
+            serde_json::from_str(&text).map_err(|e| format!("Invalid address book {}: {}", path.display(), e))?
+        };
+        book.validate()?;
+        Ok(book)
+    }
+
+    /// Load the book at `address_book_path()`
+    pub fn load_default() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load(&address_book_path())
+    }
+
+    /// Write the book in the format given by the file extension
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        self.validate()?;
+        let text = if is_toml(path) {
+            toml::to_string_pretty(self)?
+        } else {
+            serde_json::to_string_pretty(self)? + "\n"
+        };
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Reject empty or duplicate labels and labels that look like addresses
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if entry.label.trim().is_empty() {
+                return Err("Address book label must not be empty".into());
+            }
+            if looks_like_address(&entry.label) {
+                return Err(format!("Address book label must not be an address: {}", entry.label).into());
+            }
+            if self.entries[..i].iter().any(|e| e.label.eq_ignore_ascii_case(&entry.label)) {
+                return Err(format!("Duplicate address book label: {}", entry.label).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a new entry
+    pub fn add(&mut self, entry: AddressEntry) -> Result<(), Box<dyn std::error::Error>> {
+        self.entries.push(entry);
+        if let Err(e) = self.validate() {
+            self.entries.pop();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Find an entry by label (case-insensitive)
+    pub fn get(&self, label: &str) -> Option<&AddressEntry> {
+        self.entries.iter().find(|e| e.label.eq_ignore_ascii_case(label.trim()))
+    }
+
+    /// Find the entry for an address
+    pub fn entry_for(&self, address: H160) -> Option<&AddressEntry> {
+        self.entries.iter().find(|e| e.address == address)
+    }
+
+    /// Label of an address, if it is in the book
+    pub fn label_for(&self, address: H160) -> Option<&str> {
+        self.entry_for(address).map(|e| e.label.as_str())
+    }
+
+    /// Turn a label or a raw address into an address
+    ///
+    /// Raw addresses go through EIP-55 verification. An address that is in
+    /// the book must also pass the entry's chain restriction, however it was given.
+    ///
+    /// # Arguments
+    /// * `input` - Label or hex address
+    /// * `chain_id` - Chain the address will be used on
+    ///
+    /// # Returns
+    /// * `Result<ParsedAddress, Box<dyn std::error::Error>>` - Resolved address and any warnings
+    pub fn resolve(&self, input: &str, chain_id: u64) -> Result<ParsedAddress, Box<dyn std::error::Error>> {
+        let input = input.trim();
+        let (entry, parsed) = if looks_like_address(input) {
+            let parsed = parse_address(input)?;
+            match self.entry_for(parsed.address) {
+                Some(entry) => (entry, parsed),
+                None => return Ok(parsed),
+            }
+        } else {
+            let entry = self
+                .get(input)
+                .ok_or_else(|| format!("Unknown address book label: {}", input))?;
+            (entry, ParsedAddress::from_address(entry.address))
+        };
+
+        if !entry.allows_chain(chain_id) {
+            return Err(format!(
+                "'{}' is restricted to chain(s) {:?}, not chain {}",
+                entry.label, entry.chains, chain_id
+            )
+            .into());
+        }
+        Ok(parsed)
+    }
+
+    /// Checksummed address followed by its label, e.g. "0xAbC… (treasury)"
+    pub fn display(&self, address: H160) -> String {
+        match self.label_for(address) {
+            Some(label) => format!("{} ({})", checksummed(address), label),
+            None => checksummed(address),
+        }
+    }
+}
+
+/// Whether input is meant as a hex address rather than a label
+fn looks_like_address(input: &str) -> bool {
+    let hex = input.strip_prefix("0x").unwrap_or(input);
+    input.starts_with("0x") || (hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const TREASURY: &str = "0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439";
+    const RECEIVER: &str = "0x7292dD72151DaCFBbE76305db1C8Ab1928E922E4";
+
+    fn sample_book() -> AddressBook {
+        let json = format!(
+            r#"{{ "entries": [
+                {{ "label": "treasury", "address": "{}", "notes": "Team multisig" }},
+                {{ "label": "receiver", "address": "{}", "chains": [421614] }}
+            ] }}"#,
+            TREASURY, RECEIVER
+        );
+        let book: AddressBook = serde_json::from_str(&json).unwrap();
+        book.validate().unwrap();
+        book
+    }
+
+    #[test]
+    fn test_resolve_labels_and_addresses() {
+        let book = sample_book();
+        let treasury = H160::from_str(TREASURY).unwrap();
+        assert_eq!(book.resolve("treasury", 1).unwrap().address, treasury);
+        assert_eq!(book.resolve("Treasury", 1).unwrap().address, treasury);
+        let lower = book.resolve(&TREASURY.to_lowercase(), 1).unwrap();
+        assert_eq!(lower.address, treasury);
+        assert_eq!(lower.warnings, vec![AddressWarning::NoChecksum]);
+        assert!(book.resolve("nobody", 1).is_err());
+
+        // Unknown raw addresses pass through, but must have a valid checksum
+        let other = H160::repeat_byte(0x11);
+        assert_eq!(book.resolve(&format!("{:#x}", other), 1).unwrap().address, other);
+        assert!(book.resolve("0xD78677EFed3b87f8f421E68dA3F984ad8Ef76439", 1).is_err());
+
+        assert_eq!(book.display(treasury), format!("{} (treasury)", TREASURY));
+        assert_eq!(book.display(other), checksummed(other));
+    }
+
+    #[test]
+    fn test_chain_restrictions() {
+        let book = sample_book();
+        assert!(book.resolve("receiver", 421614).is_ok());
+        assert!(book.resolve("receiver", 42161).is_err());
+        // Restriction also applies when the address is typed in directly
+        assert!(book.resolve(RECEIVER, 42161).is_err());
+    }
+
+    #[test]
+    fn test_checksum_enforced_on_load() {
+        let json = format!(r#"{{ "entries": [ {{ "label": "x", "address": "{}" }} ] }}"#, TREASURY.to_lowercase());
+        assert!(serde_json::from_str::<AddressBook>(&json).is_err());
+        assert!(parse_checksummed(TREASURY).is_ok());
+        assert!(parse_checksummed("0xD78677EFed3b87f8f421E68dA3F984ad8Ef76439").is_err());
+    }
+
+    #[test]
+    fn test_validation_and_toml_roundtrip() {
+        let mut book = sample_book();
+        let duplicate = AddressEntry {
+            label: "TREASURY".to_string(),
+            address: H160::repeat_byte(0x22),
+            notes: None,
+            chains: vec![],
+        };
+        assert!(book.add(duplicate).is_err());
+        let address_label = AddressEntry {
+            label: RECEIVER.to_string(),
+            address: H160::repeat_byte(0x22),
+            notes: None,
+            chains: vec![],
+        };
+        assert!(book.add(address_label).is_err());
+        assert_eq!(book.entries.len(), 2);
+
+        let toml_text = toml::to_string_pretty(&book).unwrap();
+        assert!(toml_text.contains(TREASURY));
+        let parsed: AddressBook = toml::from_str(&toml_text).unwrap();
+        assert_eq!(parsed, book);
+    }
+}
@@ -0,0 +1,420 @@
+//! Exact fixed-point token amounts
+//!
+//! An [`Amount`] is a raw integer in the token's smallest unit plus the
+//! number of decimals it is scaled by, so wei, gwei, ether and any ERC20
+//! token share one type. Parsing and formatting never go through floats.
+
+use ethers::types::U256;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Decimals of one ether expressed in wei
+pub const ETHER_DECIMALS: u8 = 18;
+
+/// Decimals of one gwei expressed in wei
+pub const GWEI_DECIMALS: u8 = 9;
+
+/// How to drop digits that do not fit the target precision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// Towards zero (truncate)
+    #[default]
+    Down,
+    /// Away from zero
+    Up,
+    /// To nearest, ties away from zero
+    HalfUp,
+    /// To nearest, ties to the even digit (banker's rounding)
+    HalfEven,
+}
+
+/// Display options for [`Amount::format`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Round to at most this many decimal places
+    pub max_decimals: Option<u8>,
+    pub rounding: Rounding,
+    /// Insert thousands separators into the integer part
+    pub group: bool,
+    /// Remove trailing zeros (and a trailing decimal point)
+    pub trim: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            max_decimals: None,
+            rounding: Rounding::HalfEven,
+            group: false,
+            trim: true,
+        }
+    }
+}
+
+/// A non-negative fixed-point amount: `raw / 10^decimals`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Amount {
+    raw: U256,
+    decimals: u8,
+}
+
+impl Amount {
+    /// Create an amount from raw units
+    pub fn new(raw: U256, decimals: u8) -> Self {
+        Amount { raw, decimals }
+    }
+
+    /// Zero with the given decimals
+    pub fn zero(decimals: u8) -> Self {
+        Amount::new(U256::zero(), decimals)
+    }
+
+    /// An ETH amount from wei
+    pub fn from_wei(wei: U256) -> Self {
+        Amount::new(wei, ETHER_DECIMALS)
+    }
+
+    /// A wei value expressed in gwei (e.g. gas prices)
+    pub fn wei_as_gwei(wei: U256) -> Self {
+        Amount::new(wei, GWEI_DECIMALS)
+    }
+
+    /// Raw value in the smallest unit
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    /// Number of decimals the raw value is scaled by
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw.is_zero()
+    }
+
+    /// Parse a decimal string exactly
+    ///
+    /// Fails if the string has more significant decimal places than
+    /// `decimals` allows; trailing zeros beyond the precision are accepted.
+    ///
+    /// # Arguments
+    /// * `input` - Decimal string, e.g. "0.0001" or "1_000.5"
+    /// * `decimals` - Token decimals
+    ///
+    /// # Returns
+    /// * `Result<Amount, Box<dyn std::error::Error>>` - Parsed amount
+    pub fn parse(input: &str, decimals: u8) -> Result<Amount, Box<dyn std::error::Error>> {
+        let (int_digits, frac_digits) = split_decimal(input)?;
+        let significant = frac_digits.trim_end_matches('0');
+        if significant.len() > decimals as usize {
+            return Err(format!(
+                "Amount {} has more than {} decimal places",
+                input.trim(),
+                decimals
+            )
+            .into());
+        }
+        from_digits(&int_digits, significant, decimals)
+            .ok_or_else(|| format!("Amount {} is too large", input.trim()).into())
+    }
+
+    /// Parse a decimal string, rounding extra decimal places
+    ///
+    /// # Arguments
+    /// * `input` - Decimal string
+    /// * `decimals` - Token decimals
+    /// * `rounding` - How to round digits beyond `decimals`
+    ///
+    /// # Returns
+    /// * `Result<Amount, Box<dyn std::error::Error>>` - Parsed amount
+    pub fn parse_rounded(input: &str, decimals: u8, rounding: Rounding) -> Result<Amount, Box<dyn std::error::Error>> {
+        let (int_digits, frac_digits) = split_decimal(input)?;
+        let keep = frac_digits.len().min(decimals as usize);
+        let exact = from_digits(&int_digits, &frac_digits[..keep], decimals)
+            .ok_or_else(|| format!("Amount {} is too large", input.trim()))?;
+
+        let dropped = &frac_digits[keep..];
+        let round_up = match rounding {
+            Rounding::Down => false,
+            Rounding::Up => dropped.bytes().any(|b| b != b'0'),
+            Rounding::HalfUp => dropped.as_bytes().first().is_some_and(|&b| b >= b'5'),
+            Rounding::HalfEven => {
+                let first = dropped.as_bytes().first().copied().unwrap_or(b'0');
+                let rest_nonzero = dropped.bytes().skip(1).any(|b| b != b'0');
+                first > b'5' || (first == b'5' && (rest_nonzero || exact.raw.bit(0)))
+            }
+        };
+
+        if round_up {
+            let raw = exact.raw.checked_add(U256::one())
+                .ok_or_else(|| format!("Amount {} is too large", input.trim()))?;
+            Ok(Amount::new(raw, decimals))
+        } else {
+            Ok(exact)
+        }
+    }
+
+    /// Parse an ETH amount into wei
+    pub fn parse_ether(input: &str) -> Result<Amount, Box<dyn std::error::Error>> {
+        Amount::parse(input, ETHER_DECIMALS)
+    }
+
+    /// Change the number of decimals, rounding when precision is lost
+    ///
+    /// # Arguments
+    /// * `decimals` - Target decimals
+    /// * `rounding` - How to round when `decimals` is lower than the current precision
+    ///
+    /// # Returns
+    /// * `Result<Amount, Box<dyn std::error::Error>>` - Rescaled amount; fails on overflow
+    pub fn rescale(&self, decimals: u8, rounding: Rounding) -> Result<Amount, Box<dyn std::error::Error>> {
+        match decimals.cmp(&self.decimals) {
+            Ordering::Equal => Ok(*self),
+            Ordering::Greater => {
+                let shift = (decimals - self.decimals) as usize;
+                let raw = pow10(shift)
+                    .and_then(|factor| self.raw.checked_mul(factor))
+                    .ok_or("Amount overflows when adding decimals")?;
+                Ok(Amount::new(raw, decimals))
+            }
+            Ordering::Less => {
+                let (int_digits, frac_digits) = self.digits();
+                let input = format!("{}.{}", int_digits, frac_digits);
+                Amount::parse_rounded(&input, decimals, rounding)
+            }
+        }
+    }
+
+    /// Add two amounts with the same decimals
+    pub fn checked_add(&self, other: Amount) -> Option<Amount> {
+        if self.decimals != other.decimals {
+            return None;
+        }
+        self.raw.checked_add(other.raw).map(|raw| Amount::new(raw, self.decimals))
+    }
+
+    /// Subtract two amounts with the same decimals
+    pub fn checked_sub(&self, other: Amount) -> Option<Amount> {
+        if self.decimals != other.decimals {
+            return None;
+        }
+        self.raw.checked_sub(other.raw).map(|raw| Amount::new(raw, self.decimals))
+    }
+
+    /// Integer and fractional digit strings (fractional part has exactly `decimals` digits)
+    fn digits(&self) -> (String, String) {
+        let digits = self.raw.to_string();
+        let decimals = self.decimals as usize;
+        if digits.len() > decimals {
+            let (int_part, frac_part) = digits.split_at(digits.len() - decimals);
+            (int_part.to_string(), frac_part.to_string())
+        } else {
+            ("0".to_string(), format!("{:0>width$}", digits, width = decimals))
+        }
+    }
+
+    /// Format with rounding, grouping and trimming options
+    ///
+    /// # Arguments
+    /// * `options` - Display options
+    ///
+    /// # Returns
+    /// * `String` - Formatted amount
+    pub fn format(&self, options: &FormatOptions) -> String {
+        let amount = match options.max_decimals {
+            Some(max) if max < self.decimals => self.rescale(max, options.rounding).unwrap_or(*self),
+            _ => *self,
+        };
+
+        let (int_digits, frac_digits) = amount.digits();
+        let int_part = if options.group { group_thousands(&int_digits) } else { int_digits };
+        let frac_part = if options.trim { frac_digits.trim_end_matches('0') } else { frac_digits.as_str() };
+
+        if frac_part.is_empty() {
+            int_part
+        } else {
+            format!("{}.{}", int_part, frac_part)
+        }
+    }
+
+    /// All decimal places, e.g. "1.500000" for six decimals
+    pub fn to_fixed_string(&self) -> String {
+        self.format(&FormatOptions { trim: false, ..Default::default() })
+    }
+
+    /// Trailing zeros trimmed and thousands grouped, e.g. "1,234.5"
+    pub fn to_grouped_string(&self) -> String {
+        self.format(&FormatOptions { group: true, ..Default::default() })
+    }
+}
+
+/// Exact decimal representation with trailing zeros trimmed
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(&FormatOptions::default()))
+    }
+}
+
+/// Amounts compare by value, so `1.5` (6 decimals) equals `1.50` (18 decimals)
+impl Ord for Amount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a_int, a_frac) = self.digits();
+        let (b_int, b_frac) = other.digits();
+        let a_int = a_int.trim_start_matches('0');
+        let b_int = b_int.trim_start_matches('0');
+
+        a_int
+            .len()
+            .cmp(&b_int.len())
+            .then_with(|| a_int.cmp(b_int))
+            .then_with(|| {
+                let width = a_frac.len().max(b_frac.len());
+                let a = format!("{:0<width$}", a_frac, width = width);
+                let b = format!("{:0<width$}", b_frac, width = width);
+                a.cmp(&b)
+            })
+    }
+}
+
+impl PartialOrd for Amount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Amount {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Amount {}
+
+/// `10^exp` if it fits in 256 bits
+fn pow10(exp: usize) -> Option<U256> {
+    if exp > 77 {
+        None
+    } else {
+        Some(U256::exp10(exp))
+    }
+}
+
+/// Validate a decimal string and split it into integer and fractional digits
+fn split_decimal(input: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
+    let cleaned: String = input.trim().chars().filter(|&c| c != '_').collect();
+    if cleaned.is_empty() {
+        return Err("Amount is empty".into());
+    }
+    if cleaned.starts_with('-') {
+        return Err(format!("Amount must not be negative: {}", input.trim()).into());
+    }
+
+    let (int_part, frac_part) = match cleaned.split_once('.') {
+        Some((i, f)) => (i, f),
+        None => (cleaned.as_str(), ""),
+    };
+    if int_part.is_empty() && frac_part.is_empty() {
+        return Err(format!("Invalid amount: {}", input.trim()).into());
+    }
+    if !int_part.chars().chain(frac_part.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid amount: {}", input.trim()).into());
+    }
+
+    let int_part = if int_part.is_empty() { "0" } else { int_part };
+    Ok((int_part.to_string(), frac_part.to_string()))
+}
+
+/// Build an amount from digit strings; `frac` must not exceed `decimals` digits
+fn from_digits(int: &str, frac: &str, decimals: u8) -> Option<Amount> {
+    let padded = format!("{:0<width$}", frac, width = decimals as usize);
+    let digits = format!("{}{}", int, padded);
+    let digits = digits.trim_start_matches('0');
+    let raw = if digits.is_empty() {
+        U256::zero()
+    } else {
+        U256::from_dec_str(digits).ok()?
+    };
+    Some(Amount::new(raw, decimals))
+}
+
+/// Insert a comma every three digits from the right
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exact() {
+        assert_eq!(Amount::parse_ether("0.0001").unwrap().raw(), U256::exp10(14));
+        assert_eq!(Amount::parse("1_000.5", 6).unwrap().raw(), U256::from(1_000_500_000u64));
+        assert_eq!(Amount::parse(".5", 1).unwrap().raw(), U256::from(5));
+        assert_eq!(Amount::parse("2.500", 1).unwrap().raw(), U256::from(25));
+
+        assert!(Amount::parse("0.0000001", 6).is_err());
+        assert!(Amount::parse("-1", 18).is_err());
+        assert!(Amount::parse("1.2.3", 18).is_err());
+        assert!(Amount::parse("1e18", 18).is_err());
+        assert!(Amount::parse(".", 18).is_err());
+        assert!(Amount::parse("", 18).is_err());
+    }
+
+    #[test]
+    fn test_parse_rounded() {
+        let round = |s, mode| Amount::parse_rounded(s, 2, mode).unwrap().raw().as_u64();
+        assert_eq!(round("1.005", Rounding::Down), 100);
+        assert_eq!(round("1.001", Rounding::Up), 101);
+        assert_eq!(round("1.005", Rounding::HalfUp), 101);
+        assert_eq!(round("1.005", Rounding::HalfEven), 100);
+        assert_eq!(round("1.015", Rounding::HalfEven), 102);
+        assert_eq!(round("1.0051", Rounding::HalfEven), 101);
+    }
+
+    #[test]
+    fn test_format() {
+        let amount = Amount::parse("1234567.891000", 6).unwrap();
+        assert_eq!(amount.to_string(), "1234567.891");
+        assert_eq!(amount.to_fixed_string(), "1234567.891000");
+        assert_eq!(amount.to_grouped_string(), "1,234,567.891");
+        let two_places = FormatOptions { max_decimals: Some(2), rounding: Rounding::HalfUp, ..Default::default() };
+        assert_eq!(amount.format(&two_places), "1234567.89");
+
+        assert_eq!(Amount::zero(18).to_string(), "0");
+        assert_eq!(Amount::from_wei(U256::from(1)).to_string(), "0.000000000000000001");
+        assert_eq!(Amount::wei_as_gwei(U256::from(10_000_000u64)).to_string(), "0.01");
+    }
+
+    #[test]
+    fn test_large_decimals() {
+        // More than 38 decimals used to overflow u128 formatting
+        let amount = Amount::new(U256::MAX, 60);
+        assert!(amount.to_fixed_string().starts_with("115792089237316195."));
+        let tiny = Amount::new(U256::from(1), 80);
+        assert_eq!(tiny.to_fixed_string().len(), 82);
+        assert!(tiny > Amount::zero(0));
+    }
+
+    #[test]
+    fn test_compare_across_decimals() {
+        let a = Amount::parse("1.5", 6).unwrap();
+        let b = Amount::parse("1.50", 18).unwrap();
+        assert_eq!(a, b);
+        assert!(Amount::parse_ether("0.0009").unwrap() < Amount::parse("0.001", 6).unwrap());
+        assert!(Amount::parse("10", 0).unwrap() > Amount::parse("9.99", 2).unwrap());
+
+        let rescaled = b.rescale(6, Rounding::Down).unwrap();
+        assert_eq!(rescaled.raw(), U256::from(1_500_000));
+        assert_eq!(a.checked_add(rescaled).unwrap().to_string(), "3");
+        assert!(a.checked_add(b).is_none());
+    }
+}
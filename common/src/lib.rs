@@ -0,0 +1,10 @@
+//! Shared helpers for the Arbitrum Sepolia tools
+//!
+//! Code that more than one of the task crates needs lives here and is
+//! pulled in as a path dependency.
+
+pub mod address;
+pub mod address_book;
+pub mod amount;
+pub mod price;
+pub mod revert;
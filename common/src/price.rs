@@ -0,0 +1,472 @@
+//! Chainlink price feed helpers
+//!
+//! Reads `latestRoundData` and `decimals` from Chainlink aggregator
+//! contracts and converts token amounts into USD.
+
+use crate::amount::{Amount, FormatOptions};
+use ethers::abi::{parse_abi, Abi};
+use ethers::prelude::*;
+use std::env;
+use std::str::FromStr;
+
+/// Default maximum age of a price: the 24h feed heartbeat plus one hour
+pub const DEFAULT_MAX_PRICE_AGE_SECS: u64 = 90_000;
+
+/// ETH/USD aggregator on Arbitrum One
+const ARBITRUM_ONE_ETH_USD: &str = "0x639Fe6ab55C921f74e7fac1ee960C0B6293ba612";
+
+/// ETH/USD aggregator on Arbitrum Sepolia
+const ARBITRUM_SEPOLIA_ETH_USD: &str = "0xd30e2101a97dcbAeBCBC04F14C3f624E67A35165";
+
+/// WETH on Arbitrum One
+const ARBITRUM_ONE_WETH: &str = "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1";
+
+/// WETH on Arbitrum Sepolia
+const ARBITRUM_SEPOLIA_WETH: &str = "0x980B62Da83eFf3D4576C647993b0c1D7faf17c73";
+
+/// A price read from an aggregator
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceQuote {
+    pub feed: H160,
+    /// Price scaled by `10^decimals`
+    pub answer: U256,
+    pub decimals: u8,
+    /// Unix timestamp of the round's last update
+    pub updated_at: u64,
+    /// Seconds between `updated_at` and the latest block
+    pub age_secs: u64,
+}
+
+impl PriceQuote {
+    /// Price formatted as "$1,234.56"
+    pub fn display_price(&self) -> String {
+        let cents = to_usd_cents(U256::exp10(self.decimals as usize), self.decimals, self)
+            .unwrap_or_default();
+        format_usd(cents)
+    }
+}
+
+/// Minimal Chainlink AggregatorV3Interface
+fn aggregator_abi() -> Abi {
+    parse_abi(&[
+        "function decimals() external view returns (uint8)",
+        "function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)",
+    ])
+    .expect("aggregator ABI is valid")
+}
+
+/// Default ETH/USD feed for a chain, if one is known
+///
+/// # Arguments
+/// * `chain_id` - Chain ID of the connected network
+///
+/// # Returns
+/// * `Option<H160>` - Aggregator address
+pub fn default_eth_usd_feed(chain_id: u64) -> Option<H160> {
+    let address = match chain_id {
+        42161 => ARBITRUM_ONE_ETH_USD,
+        421614 => ARBITRUM_SEPOLIA_ETH_USD,
+        _ => return None,
+    };
+    H160::from_str(address).ok()
+}
+
+/// Default USD feed for an ERC20 token, keyed by its contract address
+///
+/// Only WETH is built in; it is priced with the chain's ETH/USD feed.
+///
+/// # Arguments
+/// * `chain_id` - Chain ID of the connected network
+/// * `token` - Token contract address
+///
+/// # Returns
+/// * `Option<H160>` - Aggregator address
+pub fn default_token_feed(chain_id: u64, token: H160) -> Option<H160> {
+    let weth = match chain_id {
+        42161 => ARBITRUM_ONE_WETH,
+        421614 => ARBITRUM_SEPOLIA_WETH,
+        _ => return None,
+    };
+    if H160::from_str(weth).ok()? == token {
+        default_eth_usd_feed(chain_id)
+    } else {
+        None
+    }
+}
+
+/// Name of the environment variable that overrides a token's feed
+///
+/// # Arguments
+/// * `token` - Token contract address
+///
+/// # Returns
+/// * `String` - e.g. "PRICE_FEED_82AF49447D8A07E3BD95BD0D56F35241523FBAB1"
+pub fn token_feed_var(token: H160) -> String {
+    format!("PRICE_FEED_{}", ethers::utils::hex::encode_upper(token.as_bytes()))
+}
+
+/// Parse a feed address taken from an environment variable
+fn parse_feed(key: &str, value: &str) -> Result<H160, Box<dyn std::error::Error>> {
+    H160::from_str(value.trim()).map_err(|_| format!("Invalid price feed address in {}: {}", key, value).into())
+}
+
+/// Find the USD feed for an ERC20 token
+///
+/// Feeds are keyed by contract address, never by the token's self-reported
+/// symbol, so a token cannot pick up another token's price by naming itself
+/// after it.
+///
+/// # Arguments
+/// * `chain_id` - Chain ID of the connected network
+/// * `token` - Token contract address
+/// * `override_feed` - Value of `PRICE_FEED_<ADDRESS>`, if set
+///
+/// # Returns
+/// * `Result<Option<H160>, Box<dyn std::error::Error>>` - Aggregator address, if configured
+pub fn feed_for_token(
+    chain_id: u64,
+    token: H160,
+    override_feed: Option<&str>,
+) -> Result<Option<H160>, Box<dyn std::error::Error>> {
+    match override_feed {
+        Some(value) => Ok(Some(parse_feed(&token_feed_var(token), value)?)),
+        None => Ok(default_token_feed(chain_id, token)),
+    }
+}
+
+/// Find the USD feed for native ETH
+///
+/// # Arguments
+/// * `chain_id` - Chain ID of the connected network
+/// * `override_feed` - Value of `PRICE_FEED_ETH`, if set
+///
+/// # Returns
+/// * `Result<Option<H160>, Box<dyn std::error::Error>>` - Aggregator address, if configured
+pub fn feed_for_eth(chain_id: u64, override_feed: Option<&str>) -> Result<Option<H160>, Box<dyn std::error::Error>> {
+    match override_feed {
+        Some(value) => Ok(Some(parse_feed("PRICE_FEED_ETH", value)?)),
+        None => Ok(default_eth_usd_feed(chain_id)),
+    }
+}
+
+/// Maximum accepted price age, from `PRICE_MAX_AGE_SECS` or the default
+pub fn max_price_age() -> u64 {
+    env::var("PRICE_MAX_AGE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_PRICE_AGE_SECS)
+}
+
+/// Convert a timestamp read from the chain, rejecting values beyond `u64`
+fn timestamp(value: U256, what: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    u64::try_from(value).map_err(|_| format!("{} {} is not a valid timestamp", what, value).into())
+}
+
+/// Reject a price whose `updatedAt` is too old or in the future
+///
+/// # Arguments
+/// * `updated_at` - Round update timestamp
+/// * `now` - Reference timestamp (latest block)
+/// * `max_age_secs` - Maximum accepted age
+///
+/// # Returns
+/// * `Result<u64, Box<dyn std::error::Error>>` - Age of the price in seconds
+pub fn check_staleness(updated_at: u64, now: u64, max_age_secs: u64) -> Result<u64, Box<dyn std::error::Error>> {
+    if updated_at == 0 {
+        return Err("Price feed round is not complete (updatedAt = 0)".into());
+    }
+    if updated_at > now {
+        return Err(format!("Price feed updatedAt {} is ahead of block time {}", updated_at, now).into());
+    }
+    let age = now - updated_at;
+    if age > max_age_secs {
+        return Err(format!("Stale price: last updated {}s ago (max {}s)", age, max_age_secs).into());
+    }
+    Ok(age)
+}
+
+/// Read the latest price from an aggregator and check its freshness
+///
+/// # Arguments
+/// * `client` - Provider or middleware connected to the network
+/// * `feed` - Aggregator contract address
+/// * `max_age_secs` - Maximum accepted age of the price
+///
+/// # Returns
+/// * `Result<PriceQuote, Box<dyn std::error::Error>>` - Validated price
+pub async fn get_price<M: Middleware + 'static>(
+    client: &M,
+    feed: H160,
+    max_age_secs: u64,
+) -> Result<PriceQuote, Box<dyn std::error::Error>> {
+    let aggregator = BaseContract::from(aggregator_abi());
+
+    let call = TransactionRequest::new()
+        .to(feed)
+        .data(aggregator.encode("decimals", ())?);
+    let output = client.call(&call.into(), None).await?;
+    let decimals: u8 = aggregator.decode_output("decimals", output)?;
+
+    let call = TransactionRequest::new()
+        .to(feed)
+        .data(aggregator.encode("latestRoundData", ())?);
+    let output = client.call(&call.into(), None).await?;
+    let (_round_id, answer, _started_at, updated_at, _answered_in_round): (U256, I256, U256, U256, U256) =
+        aggregator.decode_output("latestRoundData", output)?;
+
+    if answer <= I256::zero() {
+        return Err(format!("Price feed {:#x} returned a non-positive answer: {}", feed, answer).into());
+    }
+
+    let block = client
+        .get_block(BlockNumber::Latest)
+        .await?
+        .ok_or("Failed to get latest block")?;
+    let updated_at = timestamp(updated_at, &format!("Price feed {:#x} updatedAt", feed))?;
+    let age_secs = check_staleness(updated_at, timestamp(block.timestamp, "Block timestamp")?, max_age_secs)?;
+
+    Ok(PriceQuote {
+        feed,
+        answer: answer.into_raw(),
+        decimals,
+        updated_at,
+        age_secs,
+    })
+}
+
+/// Read the price from a feed, passing `None` through
+async fn price_from<M: Middleware + 'static>(
+    client: &M,
+    feed: Option<H160>,
+) -> Result<Option<PriceQuote>, Box<dyn std::error::Error>> {
+    match feed {
+        Some(feed) => Ok(Some(get_price(client, feed, max_price_age()).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Look up and read the USD price of an ERC20 token on the connected chain
+///
+/// # Arguments
+/// * `client` - Provider or middleware connected to the network
+/// * `token` - Token contract address
+///
+/// # Returns
+/// * `Result<Option<PriceQuote>, Box<dyn std::error::Error>>` - `None` when no feed is configured
+pub async fn get_token_usd_price<M: Middleware + 'static>(
+    client: &M,
+    token: H160,
+) -> Result<Option<PriceQuote>, Box<dyn std::error::Error>> {
+    let chain_id = client.get_chainid().await?.as_u64();
+    let override_feed = env::var(token_feed_var(token)).ok();
+    price_from(client, feed_for_token(chain_id, token, override_feed.as_deref())?).await
+}
+
+/// Look up and read the USD price of native ETH on the connected chain
+///
+/// # Arguments
+/// * `client` - Provider or middleware connected to the network
+///
+/// # Returns
+/// * `Result<Option<PriceQuote>, Box<dyn std::error::Error>>` - `None` when no feed is configured
+pub async fn get_eth_usd_price<M: Middleware + 'static>(
+    client: &M,
+) -> Result<Option<PriceQuote>, Box<dyn std::error::Error>> {
+    let chain_id = client.get_chainid().await?.as_u64();
+    let override_feed = env::var("PRICE_FEED_ETH").ok();
+    price_from(client, feed_for_eth(chain_id, override_feed.as_deref())?).await
+}
+
+/// Value an amount of ETH in USD with the connected chain's ETH/USD feed
+///
+/// # Arguments
+/// * `client` - Provider or middleware connected to the network
+/// * `wei` - Amount in wei
+///
+/// # Returns
+/// * `Result<Option<String>, Box<dyn std::error::Error>>` - Value as described by
+///   `describe_usd_value`, or `None` when no price feed is configured
+pub async fn eth_usd_value<M: Middleware + 'static>(
+    client: &M,
+    wei: U256,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    describe_eth_value(wei, get_eth_usd_price(client).await?)
+}
+
+/// Describe an ETH amount with an optional ETH/USD quote
+fn describe_eth_value(wei: U256, quote: Option<PriceQuote>) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    match quote {
+        Some(quote) => Ok(Some(describe_usd_value(wei, 18, "ETH", &quote)?)),
+        None => Ok(None),
+    }
+}
+
+/// Convert a raw token amount into USD cents (rounded down)
+///
+/// # Arguments
+/// * `raw` - Amount in the token's smallest unit
+/// * `decimals` - Token decimals
+/// * `quote` - USD price of one whole token
+///
+/// # Returns
+/// * `Result<U256, Box<dyn std::error::Error>>` - Value in cents
+pub fn to_usd_cents(raw: U256, decimals: u8, quote: &PriceQuote) -> Result<U256, Box<dyn std::error::Error>> {
+    let scale = decimals as usize + quote.decimals as usize;
+    if scale > 150 {
+        return Err(format!("Too many decimals to convert: {}", scale).into());
+    }
+    let numerator = raw.full_mul(quote.answer) * U512::from(100);
+    let cents = numerator / U512::exp10(scale);
+    U256::try_from(cents).map_err(|_| "USD value overflows 256 bits".into())
+}
+
+/// Describe the USD value of an amount together with the price it was derived from
+///
+/// # Arguments
+/// * `raw` - Amount in the token's smallest unit
+/// * `decimals` - Token decimals
+/// * `symbol` - Token symbol
+/// * `quote` - USD price of one whole token
+///
+/// # Returns
+/// * `Result<String, Box<dyn std::error::Error>>` - e.g. "$150.00 (1 ETH = $3,000.00, updated 100s ago)"
+pub fn describe_usd_value(
+    raw: U256,
+    decimals: u8,
+    symbol: &str,
+    quote: &PriceQuote,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(format!(
+        "{} (1 {} = {}, updated {}s ago)",
+        format_usd(to_usd_cents(raw, decimals, quote)?),
+        symbol,
+        quote.display_price(),
+        quote.age_secs
+    ))
+}
+
+/// Format cents as "$1,234.56"
+pub fn format_usd(cents: U256) -> String {
+    let options = FormatOptions {
+        group: true,
+        trim: false,
+        ..Default::default()
+    };
+    format!("${}", Amount::new(cents, 2).format(&options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::Token;
+
+    fn quote(answer: u64, decimals: u8) -> PriceQuote {
+        PriceQuote {
+            feed: H160::zero(),
+            answer: U256::from(answer),
+            decimals,
+            updated_at: 0,
+            age_secs: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_staleness() {
+        assert_eq!(check_staleness(1_000, 1_060, 3_600).unwrap(), 60);
+        assert!(check_staleness(1_000, 10_000, 3_600).is_err());
+        assert!(check_staleness(2_000, 1_000, 3_600).is_err());
+        assert!(check_staleness(0, 1_000, 3_600).is_err());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(U256::from(1_700_000_000u64), "updatedAt").unwrap(), 1_700_000_000);
+        assert!(timestamp(U256::from(u64::MAX) + 1, "updatedAt").is_err());
+    }
+
+    #[test]
+    fn test_to_usd_cents() {
+        // $3,000.12345678 with 8 feed decimals
+        let eth = quote(300_012_345_678, 8);
+        let half_eth = U256::exp10(17) * 5;
+        assert_eq!(to_usd_cents(half_eth, 18, &eth).unwrap(), U256::from(150_006));
+
+        let usdc = quote(100_000_000, 8);
+        assert_eq!(to_usd_cents(U256::from(2_500_000), 6, &usdc).unwrap(), U256::from(250));
+    }
+
+    #[test]
+    fn test_format_usd() {
+        assert_eq!(format_usd(U256::from(5)), "$0.05");
+        assert_eq!(format_usd(U256::from(123_456_789)), "$1,234,567.89");
+        assert_eq!(quote(300_012_345_678, 8).display_price(), "$3,000.12");
+    }
+
+    /// Queue the three responses `get_price` consumes (served last-in first-out)
+    fn push_round(mock: &MockProvider, price: u64, updated_at: u64, block_time: u64) {
+        let block = Block::<TxHash> {
+            timestamp: U256::from(block_time),
+            ..Default::default()
+        };
+        mock.push::<Block<TxHash>, _>(block).unwrap();
+        let round = ethers::abi::encode(&[
+            Token::Uint(U256::from(1)),
+            Token::Int(U256::from(price)),
+            Token::Uint(U256::from(updated_at)),
+            Token::Uint(U256::from(updated_at)),
+            Token::Uint(U256::from(1)),
+        ]);
+        mock.push::<Bytes, _>(Bytes::from(round)).unwrap();
+        mock.push::<Bytes, _>(Bytes::from(ethers::abi::encode(&[Token::Uint(U256::from(8))]))).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_price_from_mock_aggregator() {
+        let (provider, mock) = Provider::mocked();
+        let feed = H160::repeat_byte(0x11);
+
+        push_round(&mock, 300_000_000_000, 1_700_000_000, 1_700_000_100);
+        let price = get_price(&provider, feed, 3_600).await.unwrap();
+        assert_eq!(price.decimals, 8);
+        assert_eq!(price.age_secs, 100);
+        assert_eq!(price.display_price(), "$3,000.00");
+
+        push_round(&mock, 300_000_000_000, 1_700_000_000, 1_700_010_000);
+        assert!(get_price(&provider, feed, 3_600).await.is_err());
+    }
+
+    #[test]
+    fn test_feed_lookup() {
+        let weth = H160::from_str(ARBITRUM_ONE_WETH).unwrap();
+        let eth_feed = default_eth_usd_feed(42161).unwrap();
+        assert_eq!(feed_for_token(42161, weth, None).unwrap(), Some(eth_feed));
+        // The Sepolia WETH address is not WETH on Arbitrum One
+        let sepolia_weth = H160::from_str(ARBITRUM_SEPOLIA_WETH).unwrap();
+        assert_eq!(feed_for_token(42161, sepolia_weth, None).unwrap(), None);
+        assert_eq!(feed_for_token(1, weth, None).unwrap(), None);
+
+        let feed = H160::repeat_byte(0x22);
+        let other = H160::repeat_byte(0x33);
+        assert_eq!(feed_for_token(42161, other, Some(&format!("{:#x}", feed))).unwrap(), Some(feed));
+        assert!(feed_for_token(42161, other, Some("0x123")).is_err());
+        assert_eq!(
+            token_feed_var(weth),
+            "PRICE_FEED_82AF49447D8A07E3BD95BD0D56F35241523FBAB1"
+        );
+
+        assert_eq!(feed_for_eth(421614, None).unwrap(), default_eth_usd_feed(421614));
+        assert_eq!(feed_for_eth(1, None).unwrap(), None);
+        assert_eq!(feed_for_eth(1, Some(&format!("{:#x}", feed))).unwrap(), Some(feed));
+    }
+
+    #[tokio::test]
+    async fn test_eth_usd_value() {
+        let (provider, mock) = Provider::mocked();
+
+        push_round(&mock, 300_000_000_000, 1_700_000_000, 1_700_000_100);
+        let quote = get_price(&provider, H160::repeat_byte(0x11), 3_600).await.unwrap();
+        let value = describe_eth_value(U256::exp10(17) * 5, Some(quote)).unwrap();
+        assert_eq!(value.as_deref(), Some("$1,500.00 (1 ETH = $3,000.00, updated 100s ago)"));
+        assert_eq!(describe_eth_value(U256::one(), None).unwrap(), None);
+    }
+}
@@ -0,0 +1,209 @@
+//! Pre-send simulation and revert reason decoding
+//!
+//! Runs a transaction through `eth_call` against pending state before it is
+//! broadcast, and turns revert data into something readable: the standard
+//! `Error(string)` and `Panic(uint256)` payloads, or custom errors looked up
+//! in a contract ABI.
+
+use ethers::abi::{decode, Abi, ParamType, Token};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use std::fmt;
+
+/// Selector of `Error(string)`
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Selector of `Panic(uint256)`
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Why a call reverted
+#[derive(Debug, Clone, PartialEq)]
+pub enum RevertReason {
+    /// Reverted without data (`revert()` or a failed `require` without message)
+    Empty,
+    /// `Error(string)` from `require(cond, "message")` or `revert("message")`
+    Error(String),
+    /// `Panic(uint256)` from a failed assert, overflow, division by zero, ...
+    Panic { code: U256, description: &'static str },
+    /// Custom error found in the ABI
+    Custom { name: String, args: Vec<Token> },
+    /// Data that matches nothing known
+    Unknown(Bytes),
+}
+
+impl fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevertReason::Empty => write!(f, "reverted without a reason"),
+            RevertReason::Error(message) => write!(f, "{}", message),
+            RevertReason::Panic { code, description } => write!(f, "panic 0x{:02x}: {}", code, description),
+            RevertReason::Custom { name, args } => {
+                let args: Vec<String> = args.iter().map(format_token).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+            RevertReason::Unknown(data) => write!(f, "unknown revert data {}", data),
+        }
+    }
+}
+
+/// Render a decoded argument; addresses and numbers read better than `Debug`
+fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(a) => ethers::utils::to_checksum(a, None),
+        Token::Uint(v) | Token::Int(v) => v.to_string(),
+        Token::Bool(b) => b.to_string(),
+        Token::String(s) => format!("{:?}", s),
+        Token::Bytes(b) | Token::FixedBytes(b) => format!("0x{}", ethers::utils::hex::encode(b)),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Meaning of a Solidity panic code
+pub fn panic_description(code: U256) -> &'static str {
+    if code > U256::from(0xffu64) {
+        return "unknown panic code";
+    }
+    match code.as_u64() {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array encoding",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to uninitialized internal function",
+        _ => "unknown panic code",
+    }
+}
+
+/// Decode revert data
+///
+/// # Arguments
+/// * `data` - Revert data returned by the node
+/// * `abi` - ABI to resolve custom errors from, if available
+///
+/// # Returns
+/// * `RevertReason` - Decoded reason; `Unknown` if nothing matched
+pub fn decode_revert(data: &[u8], abi: Option<&Abi>) -> RevertReason {
+    if data.is_empty() {
+        return RevertReason::Empty;
+    }
+    if data.len() < 4 {
+        return RevertReason::Unknown(Bytes::from(data.to_vec()));
+    }
+    let (selector, payload) = data.split_at(4);
+
+    if selector == ERROR_SELECTOR {
+        if let Ok(tokens) = decode(&[ParamType::String], payload) {
+            if let Some(Token::String(message)) = tokens.into_iter().next() {
+                return RevertReason::Error(message);
+            }
+        }
+    }
+    if selector == PANIC_SELECTOR {
+        if let Ok(tokens) = decode(&[ParamType::Uint(256)], payload) {
+            if let Some(Token::Uint(code)) = tokens.into_iter().next() {
+                return RevertReason::Panic { code, description: panic_description(code) };
+            }
+        }
+    }
+    if let Some(abi) = abi {
+        for error in abi.errors() {
+            if error.signature()[..4] == *selector {
+                if let Ok(args) = error.decode(payload) {
+                    return RevertReason::Custom { name: error.name.clone(), args };
+                }
+            }
+        }
+    }
+
+    RevertReason::Unknown(Bytes::from(data.to_vec()))
+}
+
+/// Run a transaction through `eth_call` against pending state
+///
+/// # Arguments
+/// * `client` - Provider or middleware
+/// * `tx` - Transaction to simulate; `from` should be set
+/// * `abi` - ABI of the called contract, for custom errors
+///
+/// # Returns
+/// * `Result<Bytes, Box<dyn std::error::Error>>` - Return data, or an error
+///   naming the decoded revert reason
+pub async fn simulate_call<M: Middleware>(
+    client: &M,
+    tx: &TypedTransaction,
+    abi: Option<&Abi>,
+) -> Result<Bytes, Box<dyn std::error::Error>> {
+    match client.call(tx, Some(BlockNumber::Pending.into())).await {
+        Ok(output) => Ok(output),
+        Err(e) => match e.as_error_response().and_then(|r| r.as_revert_data()) {
+            Some(data) => Err(format!("Transaction would revert: {}", decode_revert(&data, abi)).into()),
+            None => Err(format!("Simulation failed: {}", e).into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, parse_abi};
+    use ethers::providers::{JsonRpcError, MockResponse};
+
+    fn with_selector(selector: &[u8], tokens: &[Token]) -> Vec<u8> {
+        let mut data = selector.to_vec();
+        data.extend(encode(tokens));
+        data
+    }
+
+    #[test]
+    fn test_decode_standard_reverts() {
+        let data = with_selector(&ERROR_SELECTOR, &[Token::String("ERC20: insufficient allowance".into())]);
+        assert_eq!(decode_revert(&data, None), RevertReason::Error("ERC20: insufficient allowance".into()));
+
+        let data = with_selector(&PANIC_SELECTOR, &[Token::Uint(U256::from(0x11))]);
+        let reason = decode_revert(&data, None);
+        assert_eq!(reason.to_string(), "panic 0x11: arithmetic overflow or underflow");
+
+        assert_eq!(decode_revert(&[], None), RevertReason::Empty);
+        assert!(matches!(decode_revert(&[0xde, 0xad], None), RevertReason::Unknown(_)));
+    }
+
+    #[test]
+    fn test_decode_custom_error() {
+        let abi = parse_abi(&["error ERC20InsufficientBalance(address sender, uint256 balance, uint256 needed)"]).unwrap();
+        let error = abi.error("ERC20InsufficientBalance").unwrap();
+        let sender = H160::repeat_byte(0x11);
+        let data = error
+            .encode(&[Token::Address(sender), Token::Uint(U256::from(5)), Token::Uint(U256::from(7))])
+            .unwrap();
+
+        let reason = decode_revert(&data, Some(&abi));
+        assert_eq!(
+            reason.to_string(),
+            format!("ERC20InsufficientBalance({}, 5, 7)", ethers::utils::to_checksum(&sender, None))
+        );
+        // Without the ABI the data is opaque
+        assert!(matches!(decode_revert(&data, None), RevertReason::Unknown(_)));
+    }
+
+    #[tokio::test]
+    async fn test_simulate_call_reports_revert() {
+        let (provider, mock) = Provider::mocked();
+        let tx: TypedTransaction = TransactionRequest::new().to(H160::repeat_byte(0x22)).into();
+
+        let data = with_selector(&ERROR_SELECTOR, &[Token::String("paused".into())]);
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: 3,
+            message: "execution reverted: paused".to_string(),
+            data: Some(serde_json::Value::String(format!("0x{}", ethers::utils::hex::encode(&data)))),
+        }));
+        let err = simulate_call(&provider, &tx, None).await.unwrap_err();
+        assert_eq!(err.to_string(), "Transaction would revert: paused");
+
+        mock.push::<Bytes, _>(Bytes::from(vec![1u8])).unwrap();
+        assert_eq!(simulate_call(&provider, &tx, None).await.unwrap(), Bytes::from(vec![1u8]));
+    }
+}
@@ -0,0 +1,211 @@
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use std::str::FromStr;
+
+/// EIP-7702 delegation designator prefix: `0xef0100 || address`
+const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// EIP-1967 implementation slot: `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+const EIP1967_IMPLEMENTATION_SLOT: &str = "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// EIP-1967 admin slot: `bytes32(uint256(keccak256("eip1967.proxy.admin")) - 1)`
+const EIP1967_ADMIN_SLOT: &str = "0xb53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103";
+
+/// EIP-1967 beacon slot: `bytes32(uint256(keccak256("eip1967.proxy.beacon")) - 1)`
+const EIP1967_BEACON_SLOT: &str = "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+/// What kind of account lives at an address
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountKind {
+    /// Externally owned account without code
+    Eoa,
+    /// EOA whose code is an EIP-7702 delegation designator
+    DelegatedEoa { delegate: H160 },
+    /// Contract account
+    Contract,
+}
+
+/// EIP-1967 proxy slots that are set on an account
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProxyInfo {
+    pub implementation: Option<H160>,
+    pub admin: Option<H160>,
+    pub beacon: Option<H160>,
+}
+
+/// Result of inspecting an address
+#[derive(Debug, Clone)]
+pub struct AccountInfo {
+    pub address: H160,
+    /// Block every field was read at
+    pub block_number: U64,
+    pub kind: AccountKind,
+    pub nonce: U256,
+    pub balance: U256,
+    pub code_size: usize,
+    pub code_hash: H256,
+    /// Set when any EIP-1967 slot is populated
+    pub proxy: Option<ProxyInfo>,
+}
+
+/// Extract the delegate address from an EIP-7702 delegation designator
+///
+/// # Arguments
+/// * `code` - Account code
+///
+/// # Returns
+/// * `Option<H160>` - Delegate address if the code is exactly `0xef0100 || address`
+pub fn parse_delegation(code: &[u8]) -> Option<H160> {
+    if code.len() == 23 && code[..3] == DELEGATION_PREFIX {
+        Some(H160::from_slice(&code[3..]))
+    } else {
+        None
+    }
+}
+
+/// Classify an account from its code
+///
+/// # Arguments
+/// * `code` - Account code as returned by `eth_getCode`
+///
+/// # Returns
+/// * `AccountKind` - EOA, delegated EOA or contract
+pub fn classify_code(code: &[u8]) -> AccountKind {
+    if code.is_empty() {
+        AccountKind::Eoa
+    } else if let Some(delegate) = parse_delegation(code) {
+        AccountKind::DelegatedEoa { delegate }
+    } else {
+        AccountKind::Contract
+    }
+}
+
+/// Interpret a storage word as an address, treating zero as unset
+///
+/// # Arguments
+/// * `word` - 32-byte storage value
+///
+/// # Returns
+/// * `Option<H160>` - Address held in the low 20 bytes
+pub fn slot_to_address(word: H256) -> Option<H160> {
+    let address = H160::from_slice(&word.as_bytes()[12..]);
+    if address.is_zero() {
+        None
+    } else {
+        Some(address)
+    }
+}
+
+/// Read the EIP-1967 proxy slots of an account
+///
+/// # Arguments
+/// * `provider` - Provider connected to the network
+/// * `address` - Account to check
+/// * `block` - Block to read the slots at
+///
+/// # Returns
+/// * `Result<Option<ProxyInfo>, Box<dyn std::error::Error>>` - Proxy slots, or `None` if none are set
+pub async fn get_proxy_info(
+    provider: &Provider<Http>,
+    address: H160,
+    block: BlockId,
+) -> Result<Option<ProxyInfo>, Box<dyn std::error::Error>> {
+    let mut slots = Vec::with_capacity(3);
+    for slot in [EIP1967_IMPLEMENTATION_SLOT, EIP1967_ADMIN_SLOT, EIP1967_BEACON_SLOT] {
+        let word = provider.get_storage_at(address, H256::from_str(slot)?, Some(block)).await?;
+        slots.push(slot_to_address(word));
+    }
+
+    let info = ProxyInfo {
+        implementation: slots[0],
+        admin: slots[1],
+        beacon: slots[2],
+    };
+    if info == ProxyInfo::default() {
+        Ok(None)
+    } else {
+        Ok(Some(info))
+    }
+}
+
+/// Inspect an address: EOA or contract, nonce, code, delegation and proxy slots
+///
+/// Everything is read at one block, resolved first, so a block arriving
+/// mid-inspection cannot mix the code of one state with the nonce or
+/// balance of another.
+///
+/// # Arguments
+/// * `address_str` - The address to inspect (e.g., "0x...")
+///
+/// # Returns
+/// * `Result<AccountInfo, Box<dyn std::error::Error>>` - Account details
+pub async fn inspect_account(address_str: &str) -> Result<AccountInfo, Box<dyn std::error::Error>> {
+    let address = Address::from_str(address_str)?;
+
+    let rpc_url = std::env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+
+    let block_number = provider.get_block_number().await?;
+    let block = Some(BlockId::from(block_number));
+    let code = provider.get_code(address, block).await?;
+    let nonce = provider.get_transaction_count(address, block).await?;
+    let balance = provider.get_balance(address, block).await?;
+    let kind = classify_code(&code);
+
+    let proxy = match kind {
+        AccountKind::Eoa => None,
+        _ => get_proxy_info(&provider, address, block_number.into()).await?,
+    };
+
+    Ok(AccountInfo {
+        address,
+        block_number,
+        kind,
+        nonce,
+        balance,
+        code_size: code.len(),
+        code_hash: H256::from(keccak256(&code)),
+        proxy,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_code() {
+        assert_eq!(classify_code(&[]), AccountKind::Eoa);
+        assert_eq!(classify_code(&[0x60, 0x80, 0x60, 0x40]), AccountKind::Contract);
+
+        let mut designator = DELEGATION_PREFIX.to_vec();
+        designator.extend_from_slice(&[0x11; 20]);
+        assert_eq!(
+            classify_code(&designator),
+            AccountKind::DelegatedEoa { delegate: H160::repeat_byte(0x11) }
+        );
+
+        // Prefix alone, or with trailing bytes, is not a designator
+        designator.push(0x00);
+        assert_eq!(classify_code(&designator), AccountKind::Contract);
+    }
+
+    #[test]
+    fn test_slot_to_address() {
+        assert_eq!(slot_to_address(H256::zero()), None);
+        let mut word = [0u8; 32];
+        word[12..].copy_from_slice(&[0x22; 20]);
+        assert_eq!(slot_to_address(H256(word)), Some(H160::repeat_byte(0x22)));
+    }
+
+    #[test]
+    fn test_eip1967_slots() {
+        let implementation = U256::from_big_endian(&keccak256("eip1967.proxy.implementation")) - 1;
+        assert_eq!(H256::from_str(EIP1967_IMPLEMENTATION_SLOT).unwrap(), H256::from_uint(&implementation));
+        let admin = U256::from_big_endian(&keccak256("eip1967.proxy.admin")) - 1;
+        assert_eq!(H256::from_str(EIP1967_ADMIN_SLOT).unwrap(), H256::from_uint(&admin));
+        let beacon = U256::from_big_endian(&keccak256("eip1967.proxy.beacon")) - 1;
+        assert_eq!(H256::from_str(EIP1967_BEACON_SLOT).unwrap(), H256::from_uint(&beacon));
+    }
+}
@@ -1,5 +1,6 @@
+use arb_common::amount::Amount;
+use arb_common::price::eth_usd_value;
 use ethers::prelude::*;
-use ethers::utils::format_units;
 use std::str::FromStr;
 
 /// Query ETH balance for an address on Arbitrum Sepolia testnet
@@ -26,12 +27,41 @@ pub async fn query_balance(address_str: &str) -> Result<(U256, String), Box<dyn
     let balance = provider.get_balance(address, None).await?;
 
     // Convert wei to ETH (1 ETH = 10^18 wei)
-    let eth_value = format_units(balance, "ether")?;
+    let eth_value = Amount::from_wei(balance).to_string();
 
     Ok((balance, eth_value))
 }
 
+/// Get the chain ID of the configured RPC endpoint
+///
+/// # Returns
+/// * `Result<u64, Box<dyn std::error::Error>>` - Chain ID
+pub async fn get_chain_id() -> Result<u64, Box<dyn std::error::Error>> {
+    let rpc_url = std::env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    Ok(provider.get_chainid().await?.as_u64())
+}
+
+/// Value an ETH balance in USD using the network's Chainlink ETH/USD feed
+///
+/// # Arguments
+/// * `balance` - Balance in wei
+///
+/// # Returns
+/// * `Result<Option<String>, Box<dyn std::error::Error>>` - Formatted USD value,
+///   or `None` when no price feed is configured for the network
+pub async fn query_usd_value(balance: U256) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let rpc_url = std::env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+
+    eth_usd_value(&provider, balance).await
+}
+
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     use super::*;
 
@@ -1,29 +1,58 @@
+mod account;
 mod balance;
+mod proof;
 
-use balance::query_balance;
+use account::{inspect_account, AccountKind};
+use balance::{get_chain_id, query_balance, query_usd_value};
+use proof::{query_verified_balance, query_verified_token_balance, ProofStatus};
+use arb_common::address::{checksummed, ParsedAddress};
+use arb_common::address_book::AddressBook;
+use arb_common::amount::Amount;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load environment variables
     dotenv::dotenv().ok();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("account") {
+        let address = args.get(1).ok_or("Usage: balance-query account <ADDRESS|LABEL>")?;
+        return run_account(address).await;
+    }
+    if args.first().map(String::as_str) == Some("verify") {
+        return run_verify(&args[1..]).await;
+    }
+
     println!("=========================================================");
     println!("         Arbitrum Sepolia Balance Query");
     println!("=========================================================\n");
 
-    // The address to query
-    let target_address = "0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439";
+    // The address to query: first argument (address or label), or the default
+    let target = args
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439".to_string());
+    // Addresses can be given as address book labels
+    let book = AddressBook::load_default()?;
+    let target_address = book.resolve(&target, get_chain_id().await?)?;
+    print_address_warnings(&target_address);
+    let target_address = target_address.address;
 
-    println!("Target Address: {}\n", target_address);
+    println!("Target Address: {}\n", book.display(target_address));
 
     // Query the balance
-    match query_balance(target_address).await {
+    match query_balance(&format!("{:#x}", target_address)).await {
         Ok((balance, eth_value)) => {
             println!("---------------------------------------------------------");
             println!("Balance Query Results:");
             println!("---------------------------------------------------------");
             println!("  Raw Balance (wei): {} wei", balance);
             println!("  Formatted Balance: {} ETH", eth_value);
+            match query_usd_value(balance).await {
+                Ok(Some(usd)) => println!("  USD Value:         {}", usd),
+                Ok(None) => println!("  USD Value:         (no price feed configured)"),
+                Err(e) => println!("  USD Value:         unavailable ({})", e),
+            }
             println!("---------------------------------------------------------");
             println!("\nQuery completed successfully!");
         }
@@ -35,3 +64,141 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Account inspection: EOA vs contract, nonce, code, delegation and proxy slots
+async fn run_account(address: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("=========================================================");
+    println!("         Arbitrum Sepolia Account Inspection");
+    println!("=========================================================\n");
+
+    let book = AddressBook::load_default()?;
+    let address = book.resolve(address, get_chain_id().await?)?;
+    print_address_warnings(&address);
+    let address = address.address;
+    println!("Target Address: {}\n", book.display(address));
+
+    let info = inspect_account(&format!("{:#x}", address)).await?;
+
+    println!("---------------------------------------------------------");
+    println!("Account Details:");
+    println!("---------------------------------------------------------");
+    println!("  Address:    {}", book.display(info.address));
+    println!("  Block:      #{}", info.block_number);
+    match &info.kind {
+        AccountKind::Eoa => println!("  Type:       EOA (externally owned account)"),
+        AccountKind::DelegatedEoa { delegate } => {
+            println!("  Type:       EOA with EIP-7702 delegation");
+            println!("  Delegate:   {}", book.display(*delegate));
+        }
+        AccountKind::Contract => println!("  Type:       Contract"),
+    }
+    println!("  Nonce:      {}", info.nonce);
+    println!("  Balance:    {} ETH", Amount::from_wei(info.balance));
+    println!("  Code Size:  {} bytes", info.code_size);
+    println!("  Code Hash:  {:#x}", info.code_hash);
+    match &info.proxy {
+        Some(proxy) => {
+            println!("  EIP-1967 Proxy: yes");
+            if let Some(implementation) = proxy.implementation {
+                println!("    Implementation: {}", book.display(implementation));
+            }
+            if let Some(admin) = proxy.admin {
+                println!("    Admin:          {}", book.display(admin));
+            }
+            if let Some(beacon) = proxy.beacon {
+                println!("    Beacon:         {}", book.display(beacon));
+            }
+        }
+        None if info.kind != AccountKind::Eoa => println!("  EIP-1967 Proxy: no"),
+        None => {}
+    }
+    println!("---------------------------------------------------------");
+    println!("\nInspection completed successfully!");
+
+    Ok(())
+}
+
+/// Print warnings about an address the user typed in (missing checksum, zero address, precompile)
+fn print_address_warnings(parsed: &ParsedAddress) {
+    for warning in &parsed.warnings {
+        println!("⚠️  {}: {}", checksummed(parsed.address), warning);
+    }
+}
+
+/// Print a proof verification status line
+fn print_status(status: &ProofStatus) {
+    match status {
+        ProofStatus::Verified => println!("  Proof:             ✅ verified against state root"),
+        ProofStatus::Mismatch(reason) => println!("  Proof:             ❌ mismatch ({})", reason),
+    }
+}
+
+/// Verified-balance mode: check the RPC's answer with `eth_getProof`
+///
+/// Usage: `balance-query verify <ADDRESS> [--token <TOKEN> [--slot <N>]]`
+///
+/// `--slot` is the declaration slot of the token's balances mapping
+/// (defaults to `TOKEN_BALANCE_SLOT` or 0). Address and token may be
+/// address book labels.
+async fn run_verify(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: balance-query verify <ADDRESS> [--token <TOKEN> [--slot <N>]]";
+    let target = args.first().ok_or(usage)?;
+    let book = AddressBook::load_default()?;
+    let chain_id = get_chain_id().await?;
+    let parsed = book.resolve(target, chain_id)?;
+    print_address_warnings(&parsed);
+    let address = format!("{:#x}", parsed.address);
+    let option = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    };
+
+    println!("=========================================================");
+    println!("         Arbitrum Sepolia Verified Balance");
+    println!("=========================================================\n");
+
+    let verified = query_verified_balance(&address).await?;
+    let mut all_verified = verified.status == ProofStatus::Verified;
+    println!("---------------------------------------------------------");
+    println!("ETH Balance (block #{}):", verified.block_number);
+    println!("---------------------------------------------------------");
+    println!("  Address:           {}", book.display(verified.address));
+    println!("  State Root:        {:#x}", verified.state_root);
+    println!("  Raw Balance (wei): {} wei", verified.balance);
+    println!("  Formatted Balance: {} ETH", Amount::from_wei(verified.balance));
+    print_status(&verified.status);
+
+    if let Some(token) = option("--token") {
+        let token = book.resolve(&token, chain_id)?;
+        print_address_warnings(&token);
+        let token = format!("{:#x}", token.address);
+        let slot = option("--slot")
+            .or_else(|| std::env::var("TOKEN_BALANCE_SLOT").ok())
+            .map(|s| s.parse::<u64>())
+            .transpose()
+            .map_err(|_| "--slot must be a number")?
+            .unwrap_or(0);
+
+        let verified = query_verified_token_balance(&token, &address, slot).await?;
+        println!("---------------------------------------------------------");
+        println!("Token Balance:");
+        println!("---------------------------------------------------------");
+        println!("  Token:             {}", book.display(verified.token));
+        println!("  Holder:            {}", book.display(verified.holder));
+        println!("  Storage Slot:      {:#x} (mapping slot {})", verified.slot, slot);
+        println!("  Raw Balance:       {}", verified.balance);
+        println!("  balanceOf:         {}", verified.reported);
+        print_status(&verified.status);
+        all_verified &= verified.status == ProofStatus::Verified;
+    }
+    println!("---------------------------------------------------------");
+
+    if !all_verified {
+        return Err("Balance could not be verified".into());
+    }
+    println!("\nVerification completed successfully!");
+
+    Ok(())
+}
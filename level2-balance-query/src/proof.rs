@@ -0,0 +1,528 @@
+use ethers::abi::parse_abi;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use ethers::utils::rlp::Rlp;
+use std::str::FromStr;
+
+/// Root hash of an empty trie: `keccak256(rlp(""))`
+const EMPTY_TRIE_ROOT: &str = "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421";
+
+/// Outcome of checking a value against a Merkle-Patricia proof
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProofStatus {
+    /// The proof is valid and proves the reported value
+    Verified,
+    /// The proof is invalid or proves a different value
+    Mismatch(String),
+}
+
+/// ETH balance checked against the block's state root
+#[derive(Debug, Clone)]
+pub struct VerifiedBalance {
+    pub address: H160,
+    pub block_number: U64,
+    pub state_root: H256,
+    /// Balance reported by `eth_getBalance` at the same block
+    pub balance: U256,
+    pub status: ProofStatus,
+}
+
+/// ERC20 balance checked against the token contract's storage root
+#[derive(Debug, Clone)]
+pub struct VerifiedTokenBalance {
+    pub token: H160,
+    pub holder: H160,
+    /// Storage slot of `balances[holder]`
+    pub slot: H256,
+    /// Raw balance reported by the RPC alongside the proof
+    pub balance: U256,
+    /// Raw balance from `balanceOf(holder)` at the same block
+    pub reported: U256,
+    pub status: ProofStatus,
+}
+
+/// Reference from a trie node to its child
+enum NodeRef {
+    Hash(H256),
+    Inline(Vec<u8>),
+}
+
+/// Split bytes into 4-bit nibbles
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+/// Decode a hex-prefix encoded path into `(nibbles, is_leaf)`
+fn decode_hex_prefix(encoded: &[u8]) -> Result<(Vec<u8>, bool), String> {
+    let first = *encoded.first().ok_or("Empty hex-prefix path")?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(format!("Invalid hex-prefix flag: {}", flag));
+    }
+    let is_leaf = flag & 2 != 0;
+    let is_odd = flag & 1 != 0;
+
+    let mut nibbles = Vec::with_capacity(encoded.len() * 2);
+    if is_odd {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend(to_nibbles(&encoded[1..]));
+    Ok((nibbles, is_leaf))
+}
+
+/// Read a child reference out of a branch or extension node
+fn child_ref(item: &Rlp) -> Result<Option<NodeRef>, String> {
+    if item.is_list() {
+        return Ok(Some(NodeRef::Inline(item.as_raw().to_vec())));
+    }
+    let data = item.data().map_err(|e| format!("Malformed child reference: {}", e))?;
+    match data.len() {
+        0 => Ok(None),
+        32 => Ok(Some(NodeRef::Hash(H256::from_slice(data)))),
+        n => Err(format!("Invalid child reference length: {}", n)),
+    }
+}
+
+/// Walk a Merkle-Patricia proof from `root` along `key`
+///
+/// # Arguments
+/// * `root` - Trie root hash
+/// * `key` - Trie key (already hashed for the secure state/storage tries)
+/// * `proof` - RLP-encoded nodes from the root towards the leaf
+///
+/// # Returns
+/// * `Result<Option<Vec<u8>>, String>` - The RLP value stored at `key`,
+///   `None` if the proof shows the key is absent, or an error if the proof is invalid
+pub fn verify_proof(root: H256, key: &[u8], proof: &[Bytes]) -> Result<Option<Vec<u8>>, String> {
+    let path = to_nibbles(key);
+    let mut position = 0;
+    let mut nodes = proof.iter();
+    let mut next = NodeRef::Hash(root);
+
+    if root == H256::from_str(EMPTY_TRIE_ROOT).expect("valid constant") && proof.is_empty() {
+        return Ok(None);
+    }
+
+    loop {
+        let node = match next {
+            NodeRef::Hash(hash) => {
+                let node = nodes.next().ok_or("Proof ends before reaching the key")?;
+                if H256::from(keccak256(node)) != hash {
+                    return Err(format!("Proof node hash mismatch: expected {:#x}", hash));
+                }
+                node.to_vec()
+            }
+            NodeRef::Inline(node) => node,
+        };
+
+        let rlp = Rlp::new(&node);
+        let items = rlp.item_count().map_err(|e| format!("Malformed trie node: {}", e))?;
+        match items {
+            17 => {
+                if position == path.len() {
+                    let value = rlp.at(16).and_then(|v| v.data().map(<[u8]>::to_vec));
+                    let value = value.map_err(|e| format!("Malformed branch value: {}", e))?;
+                    return Ok(if value.is_empty() { None } else { Some(value) });
+                }
+                let child = rlp.at(path[position] as usize).map_err(|e| e.to_string())?;
+                match child_ref(&child)? {
+                    Some(child) => next = child,
+                    None => return Ok(None),
+                }
+                position += 1;
+            }
+            2 => {
+                let encoded = rlp.at(0).and_then(|p| p.data().map(<[u8]>::to_vec));
+                let encoded = encoded.map_err(|e| format!("Malformed node path: {}", e))?;
+                let (node_path, is_leaf) = decode_hex_prefix(&encoded)?;
+                let remaining = &path[position..];
+
+                if is_leaf {
+                    if remaining != node_path.as_slice() {
+                        return Ok(None);
+                    }
+                    let value = rlp.at(1).and_then(|v| v.data().map(<[u8]>::to_vec));
+                    return value.map(Some).map_err(|e| format!("Malformed leaf value: {}", e));
+                }
+
+                if !remaining.starts_with(&node_path) {
+                    return Ok(None);
+                }
+                position += node_path.len();
+                let child = rlp.at(1).map_err(|e| e.to_string())?;
+                next = child_ref(&child)?.ok_or("Extension node without child")?;
+            }
+            n => return Err(format!("Unexpected trie node with {} items", n)),
+        }
+    }
+}
+
+/// Check an `eth_getProof` account proof against a state root
+///
+/// The proof is walked along the requested address, not the one the RPC
+/// echoes back, so a valid proof for another account does not verify.
+///
+/// # Arguments
+/// * `state_root` - State root of the block the proof was taken at
+/// * `address` - Account that was requested
+/// * `response` - The `eth_getProof` response
+///
+/// # Returns
+/// * `ProofStatus` - Whether nonce, balance, storage root and code hash are proven
+pub fn verify_account_proof(state_root: H256, address: H160, response: &EIP1186ProofResponse) -> ProofStatus {
+    if response.address != address {
+        return ProofStatus::Mismatch(format!("Proof is for {:#x}, not {:#x}", response.address, address));
+    }
+    let key = keccak256(address);
+    let value = match verify_proof(state_root, &key, &response.account_proof) {
+        Ok(value) => value,
+        Err(e) => return ProofStatus::Mismatch(e),
+    };
+
+    let Some(value) = value else {
+        // An absent account must be reported as empty
+        let empty = response.nonce.is_zero() && response.balance.is_zero();
+        return if empty {
+            ProofStatus::Verified
+        } else {
+            ProofStatus::Mismatch("Account is absent from the state trie".to_string())
+        };
+    };
+
+    let account = Rlp::new(&value);
+    let decoded = (|| -> Result<(U256, U256, H256, H256), ethers::utils::rlp::DecoderError> {
+        Ok((account.val_at(0)?, account.val_at(1)?, account.val_at(2)?, account.val_at(3)?))
+    })();
+    let (nonce, balance, storage_root, code_hash) = match decoded {
+        Ok(fields) => fields,
+        Err(e) => return ProofStatus::Mismatch(format!("Malformed account leaf: {}", e)),
+    };
+
+    if nonce != U256::from(response.nonce.as_u64()) {
+        return ProofStatus::Mismatch(format!("Nonce: proof {} vs RPC {}", nonce, response.nonce));
+    }
+    if balance != response.balance {
+        return ProofStatus::Mismatch(format!("Balance: proof {} vs RPC {}", balance, response.balance));
+    }
+    if storage_root != response.storage_hash {
+        return ProofStatus::Mismatch(format!("Storage root: proof {:#x} vs RPC {:#x}", storage_root, response.storage_hash));
+    }
+    if code_hash != response.code_hash {
+        return ProofStatus::Mismatch(format!("Code hash: proof {:#x} vs RPC {:#x}", code_hash, response.code_hash));
+    }
+    ProofStatus::Verified
+}
+
+/// Check a storage proof against an account's storage root
+///
+/// As with accounts, the proof is walked along the requested slot rather
+/// than the key the RPC echoes back.
+///
+/// # Arguments
+/// * `storage_root` - Storage root of the account (itself proven by the account proof)
+/// * `slot` - Storage slot that was requested
+/// * `proof` - Storage proof entry from `eth_getProof`
+///
+/// # Returns
+/// * `ProofStatus` - Whether the reported slot value is proven
+pub fn verify_storage_proof(storage_root: H256, slot: H256, proof: &StorageProof) -> ProofStatus {
+    if H256::from_uint(&proof.key) != slot {
+        return ProofStatus::Mismatch(format!("Proof is for slot {:#x}, not {:#x}", H256::from_uint(&proof.key), slot));
+    }
+    let key = keccak256(slot);
+
+    let proven = match verify_proof(storage_root, &key, &proof.proof) {
+        Ok(Some(value)) => match Rlp::new(&value).as_val::<U256>() {
+            Ok(v) => v,
+            Err(e) => return ProofStatus::Mismatch(format!("Malformed storage leaf: {}", e)),
+        },
+        Ok(None) => U256::zero(),
+        Err(e) => return ProofStatus::Mismatch(e),
+    };
+
+    if proven == proof.value {
+        ProofStatus::Verified
+    } else {
+        ProofStatus::Mismatch(format!("Slot value: proof {} vs RPC {}", proven, proof.value))
+    }
+}
+
+/// Storage slot of `balances[holder]` for a Solidity `mapping(address => uint256)`
+///
+/// # Arguments
+/// * `holder` - Token holder address
+/// * `mapping_slot` - Declaration slot of the balances mapping (0 for OpenZeppelin ERC20, 3 for WETH9)
+///
+/// # Returns
+/// * `H256` - `keccak256(pad32(holder) || pad32(mapping_slot))`
+pub fn balance_slot(holder: H160, mapping_slot: u64) -> H256 {
+    let mut preimage = [0u8; 64];
+    preimage[12..32].copy_from_slice(holder.as_bytes());
+    U256::from(mapping_slot).to_big_endian(&mut preimage[32..]);
+    H256::from(keccak256(preimage))
+}
+
+/// Fetch the latest block's state root and a provider for the configured RPC
+async fn latest_state() -> Result<(Provider<Http>, U64, H256), Box<dyn std::error::Error>> {
+    let rpc_url = std::env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+
+    let block = provider
+        .get_block(BlockNumber::Latest)
+        .await?
+        .ok_or("Failed to get latest block")?;
+    let number = block.number.ok_or("Latest block has no number")?;
+
+    Ok((provider, number, block.state_root))
+}
+
+/// Query an ETH balance and verify it with `eth_getProof` against the block's state root
+///
+/// # Arguments
+/// * `address_str` - The address to query (e.g., "0x...")
+///
+/// # Returns
+/// * `Result<VerifiedBalance, Box<dyn std::error::Error>>` - Balance and verification status
+pub async fn query_verified_balance(address_str: &str) -> Result<VerifiedBalance, Box<dyn std::error::Error>> {
+    let address = Address::from_str(address_str)?;
+    let (provider, block_number, state_root) = latest_state().await?;
+    let block = Some(BlockId::from(block_number));
+
+    let balance = provider.get_balance(address, block).await?;
+    let response = provider.get_proof(address, vec![], block).await?;
+
+    let status = match verify_account_proof(state_root, address, &response) {
+        ProofStatus::Verified if response.balance != balance => ProofStatus::Mismatch(format!(
+            "eth_getBalance returned {} but the proven balance is {}",
+            balance, response.balance
+        )),
+        status => status,
+    };
+
+    Ok(VerifiedBalance {
+        address,
+        block_number,
+        state_root,
+        balance,
+        status,
+    })
+}
+
+/// Query an ERC20 balance and verify the storage slot with `eth_getProof`
+///
+/// The token account is proven against the state root first, then the
+/// balance slot against the token's proven storage root. The token's own
+/// `balanceOf` at the same block must agree with the proven slot, which
+/// also catches a wrong `mapping_slot`.
+///
+/// # Arguments
+/// * `token_str` - ERC20 token contract address
+/// * `holder_str` - Token holder address
+/// * `mapping_slot` - Declaration slot of the token's balances mapping
+///
+/// # Returns
+/// * `Result<VerifiedTokenBalance, Box<dyn std::error::Error>>` - Raw balance and verification status
+pub async fn query_verified_token_balance(
+    token_str: &str,
+    holder_str: &str,
+    mapping_slot: u64,
+) -> Result<VerifiedTokenBalance, Box<dyn std::error::Error>> {
+    let token = Address::from_str(token_str)?;
+    let holder = Address::from_str(holder_str)?;
+    let slot = balance_slot(holder, mapping_slot);
+    let (provider, block_number, state_root) = latest_state().await?;
+
+    let block = Some(BlockId::from(block_number));
+
+    let erc20 = BaseContract::from(parse_abi(&["function balanceOf(address) view returns (uint256)"])?);
+    let call = TransactionRequest::new().to(token).data(erc20.encode("balanceOf", holder)?);
+    let reported: U256 = erc20.decode_output("balanceOf", provider.call(&call.into(), block).await?)?;
+    let response = provider.get_proof(token, vec![slot], block).await?;
+    let storage = response
+        .storage_proof
+        .first()
+        .ok_or("eth_getProof returned no storage proof")?;
+
+    let status = match verify_account_proof(state_root, token, &response) {
+        ProofStatus::Verified => match verify_storage_proof(response.storage_hash, slot, storage) {
+            ProofStatus::Verified if storage.value != reported => ProofStatus::Mismatch(format!(
+                "balanceOf returned {} but the proven slot holds {} (wrong mapping slot?)",
+                reported, storage.value
+            )),
+            status => status,
+        },
+        ProofStatus::Mismatch(e) => ProofStatus::Mismatch(format!("Token account: {}", e)),
+    };
+
+    Ok(VerifiedTokenBalance {
+        token,
+        holder,
+        slot,
+        balance: storage.value,
+        reported,
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::rlp::RlpStream;
+
+    /// Hex-prefix encode a nibble path
+    fn hex_prefix(path: &[u8], is_leaf: bool) -> Vec<u8> {
+        let flag = if is_leaf { 0x20 } else { 0x00 };
+        let mut encoded = vec![if path.len() % 2 == 1 { flag | 0x10 | path[0] } else { flag }];
+        let rest = if path.len() % 2 == 1 { &path[1..] } else { path };
+        encoded.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+        encoded
+    }
+
+    /// Encode a leaf node for tests: `rlp([hex_prefix(path, leaf), value])`
+    fn leaf_node(path: &[u8], value: &[u8]) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&hex_prefix(path, true));
+        stream.append(&value.to_vec());
+        stream.out().to_vec()
+    }
+
+    /// Encode an extension node for tests: `rlp([hex_prefix(path, extension), child hash])`
+    fn extension_node(path: &[u8], child: &[u8]) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&hex_prefix(path, false));
+        stream.append(&keccak256(child).to_vec());
+        stream.out().to_vec()
+    }
+
+    #[test]
+    fn test_single_leaf_proof() {
+        let key = keccak256([0x01]);
+        let leaf = leaf_node(&to_nibbles(&key), b"value");
+        let root = H256::from(keccak256(&leaf));
+        let proof = vec![Bytes::from(leaf)];
+
+        assert_eq!(verify_proof(root, &key, &proof).unwrap(), Some(b"value".to_vec()));
+
+        // A different key in the same trie is proven absent
+        assert_eq!(verify_proof(root, &keccak256([0x02]), &proof).unwrap(), None);
+
+        // Tampering with the root breaks the proof
+        assert!(verify_proof(H256::repeat_byte(0xaa), &key, &proof).is_err());
+    }
+
+    #[test]
+    fn test_branch_proof() {
+        let key_a = [0x10u8; 32];
+        let key_b = [0x20u8; 32];
+        let leaf_a = leaf_node(&to_nibbles(&key_a)[1..], &[0xaa; 40]);
+        let leaf_b = leaf_node(&to_nibbles(&key_b)[1..], &[0xbb; 40]);
+
+        let mut branch = RlpStream::new_list(17);
+        for nibble in 0..16 {
+            match nibble {
+                1 => branch.append(&keccak256(&leaf_a).to_vec()),
+                2 => branch.append(&keccak256(&leaf_b).to_vec()),
+                _ => branch.append_empty_data(),
+            };
+        }
+        branch.append_empty_data();
+        let branch = branch.out().to_vec();
+        let root = H256::from(keccak256(&branch));
+
+        let proof = vec![Bytes::from(branch.clone()), Bytes::from(leaf_b.clone())];
+        assert_eq!(verify_proof(root, &key_b, &proof).unwrap(), Some(vec![0xbb; 40]));
+
+        // Empty branch slot proves absence without further nodes
+        assert_eq!(verify_proof(root, &[0x30u8; 32], &proof[..1]).unwrap(), None);
+
+        // Swapping in the wrong leaf is detected
+        let forged = vec![Bytes::from(branch), Bytes::from(leaf_a)];
+        assert!(verify_proof(root, &key_b, &forged).is_err());
+    }
+
+    #[test]
+    fn test_extension_proof() {
+        // Both keys start with nibbles 1, 2, 3, held by one extension node
+        let mut key_a = [0xaau8; 32];
+        key_a[..2].copy_from_slice(&[0x12, 0x34]);
+        let mut key_b = [0xbbu8; 32];
+        key_b[..2].copy_from_slice(&[0x12, 0x35]);
+        let leaf_a = leaf_node(&to_nibbles(&key_a)[4..], &[0xaa; 40]);
+        let leaf_b = leaf_node(&to_nibbles(&key_b)[4..], &[0xbb; 40]);
+
+        let mut branch = RlpStream::new_list(17);
+        for nibble in 0..16 {
+            match nibble {
+                4 => branch.append(&keccak256(&leaf_a).to_vec()),
+                5 => branch.append(&keccak256(&leaf_b).to_vec()),
+                _ => branch.append_empty_data(),
+            };
+        }
+        branch.append_empty_data();
+        let branch = branch.out().to_vec();
+        let extension = extension_node(&[1, 2, 3], &branch);
+        let root = H256::from(keccak256(&extension));
+
+        let proof = vec![Bytes::from(extension.clone()), Bytes::from(branch.clone()), Bytes::from(leaf_a.clone())];
+        assert_eq!(verify_proof(root, &key_a, &proof).unwrap(), Some(vec![0xaa; 40]));
+        let proof_b = vec![Bytes::from(extension.clone()), Bytes::from(branch.clone()), Bytes::from(leaf_b)];
+        assert_eq!(verify_proof(root, &key_b, &proof_b).unwrap(), Some(vec![0xbb; 40]));
+
+        // A key leaving the extension's path is proven absent by the extension alone
+        let mut key_c = key_a;
+        key_c[1] = 0x44;
+        assert_eq!(verify_proof(root, &key_c, &proof[..1]).unwrap(), None);
+
+        // An extension whose child is not the next node is detected
+        let forged = vec![Bytes::from(extension), Bytes::from(leaf_a)];
+        assert!(verify_proof(root, &key_a, &forged).is_err());
+    }
+
+    #[test]
+    fn test_proof_for_another_account_or_slot() {
+        // A trie holding one account, and one holding one storage slot
+        let alice = H160::repeat_byte(0xa1);
+        let bob = H160::repeat_byte(0xb0);
+        let mut account = RlpStream::new_list(4);
+        account.append(&U256::from(7)).append(&U256::from(1_000)).append(&H256::repeat_byte(0x33)).append(&H256::repeat_byte(0x44));
+        let leaf = leaf_node(&to_nibbles(&keccak256(alice)), &account.out());
+        let state_root = H256::from(keccak256(&leaf));
+        let response = EIP1186ProofResponse {
+            address: alice,
+            balance: U256::from(1_000),
+            code_hash: H256::repeat_byte(0x44),
+            nonce: U64::from(7),
+            storage_hash: H256::repeat_byte(0x33),
+            account_proof: vec![Bytes::from(leaf)],
+            storage_proof: vec![],
+        };
+        assert_eq!(verify_account_proof(state_root, alice, &response), ProofStatus::Verified);
+
+        // Alice's proof, whether or not it claims to be Bob's, does not verify Bob
+        assert!(matches!(verify_account_proof(state_root, bob, &response), ProofStatus::Mismatch(_)));
+        let relabelled = EIP1186ProofResponse { address: bob, ..response };
+        assert!(matches!(verify_account_proof(state_root, bob, &relabelled), ProofStatus::Mismatch(_)));
+
+        let (slot, other) = (balance_slot(alice, 0), balance_slot(bob, 0));
+        let mut value = RlpStream::new();
+        value.append(&U256::from(500));
+        let leaf = leaf_node(&to_nibbles(&keccak256(slot)), &value.out());
+        let storage_root = H256::from(keccak256(&leaf));
+        let proof = StorageProof { key: U256::from_big_endian(slot.as_bytes()), proof: vec![Bytes::from(leaf)], value: U256::from(500) };
+        assert_eq!(verify_storage_proof(storage_root, slot, &proof), ProofStatus::Verified);
+        assert!(matches!(verify_storage_proof(storage_root, other, &proof), ProofStatus::Mismatch(_)));
+        let relabelled = StorageProof { key: U256::from_big_endian(other.as_bytes()), ..proof };
+        assert!(matches!(verify_storage_proof(storage_root, other, &relabelled), ProofStatus::Mismatch(_)));
+    }
+
+    #[test]
+    fn test_balance_slot() {
+        let holder = H160::from_str("0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439").unwrap();
+        let mut preimage = vec![0u8; 12];
+        preimage.extend_from_slice(holder.as_bytes());
+        preimage.extend_from_slice(&[0u8; 31]);
+        preimage.push(3);
+        assert_eq!(balance_slot(holder, 3), H256::from(keccak256(preimage)));
+    }
+}
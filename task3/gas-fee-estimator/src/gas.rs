@@ -1,5 +1,6 @@
+use arb_common::amount::Amount;
+use arb_common::price::eth_usd_value;
 use ethers::prelude::*;
-use ethers::utils::format_units;
 use std::env;
 
 /// Get the current gas price from Arbitrum Sepolia testnet
@@ -33,14 +34,14 @@ pub async fn estimate_gas_fee(gas_limit: u64) -> Result<(U256, String), Box<dyn
     let gas_fee = gas_price * gas_limit;
 
     // Convert to Gwei (1 Gwei = 10^9 wei)
-    let gwei_value = format_units(gas_fee, "gwei")?;
+    let gwei_value = Amount::wei_as_gwei(gas_fee);
 
     // Convert to ETH (1 ETH = 10^18 wei)
-    let eth_value = format_units(gas_fee, "ether")?;
+    let eth_value = Amount::from_wei(gas_fee);
 
     let formatted = format!(
         "Gas Price: {} Gwei\nGas Limit: {} units\nEstimated Fee: {} Gwei ({} ETH)",
-        format_units(gas_price, "gwei")?,
+        Amount::wei_as_gwei(gas_price),
         gas_limit,
         gwei_value,
         eth_value
@@ -49,6 +50,22 @@ pub async fn estimate_gas_fee(gas_limit: u64) -> Result<(U256, String), Box<dyn
     Ok((gas_fee, formatted))
 }
 
+/// Value a gas fee in USD using the network's Chainlink ETH/USD feed
+///
+/// # Arguments
+/// * `gas_fee` - Gas fee in wei
+///
+/// # Returns
+/// * `Result<Option<String>, Box<dyn std::error::Error>>` - Formatted USD value,
+///   or `None` when no price feed is configured for the network
+pub async fn estimate_gas_fee_usd(gas_fee: U256) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+
+    eth_usd_value(&provider, gas_fee).await
+}
+
 /// Get the recommended gas limit for a basic ETH transfer
 ///
 /// Arbitrum uses a different gas model than Ethereum mainnet.
@@ -70,8 +87,8 @@ pub fn get_basic_transfer_gas_limit() -> u64 {
 pub async fn get_gas_price_info() -> Result<String, Box<dyn std::error::Error>> {
     let gas_price = get_gas_price().await?;
 
-    let gwei_value = format_units(gas_price, "gwei")?;
-    let eth_value = format_units(gas_price, "ether")?;
+    let gwei_value = Amount::wei_as_gwei(gas_price);
+    let eth_value = Amount::from_wei(gas_price);
 
     Ok(format!(
         "Current Gas Price:\n  {} wei\n  {} Gwei\n  {} ETH",
@@ -82,6 +99,7 @@ pub async fn get_gas_price_info() -> Result<String, Box<dyn std::error::Error>>
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     use super::*;
 
@@ -1,6 +1,6 @@
 mod gas;
 
-use gas::{get_basic_transfer_gas_limit, get_gas_price_info, estimate_gas_fee};
+use gas::{get_basic_transfer_gas_limit, get_gas_price_info, estimate_gas_fee, estimate_gas_fee_usd};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -29,7 +29,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("---------------------------------------------------------");
             println!("{}", formatted);
             println!("---------------------------------------------------------");
-            println!("Raw Gas Fee: {} wei\n", gas_fee);
+            println!("Raw Gas Fee: {} wei", gas_fee);
+            match estimate_gas_fee_usd(gas_fee).await {
+                Ok(Some(usd)) => println!("Fee in USD:  {}\n", usd),
+                Ok(None) => println!("Fee in USD:  (no price feed configured)\n"),
+                Err(e) => println!("Fee in USD:  unavailable ({})\n", e),
+            }
         }
         Err(e) => {
             eprintln!("Error estimating gas fee: {}", e);
@@ -0,0 +1,508 @@
+use crate::hd::{derive_wallet, read_mnemonic, read_mnemonic_passphrase};
+use crate::keystore::{decrypt_keystore, PassphraseSource};
+use crate::transfer::get_wallet;
+use ethers::prelude::*;
+use arb_common::address::checksummed;
+use async_trait::async_trait;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::{Eip712, TypedData};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Where an account's signing key lives
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SignerSource {
+    /// Encrypted JSON keystore
+    Keystore {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password_file: Option<PathBuf>,
+    },
+    /// HD account derived from the mnemonic at `MNEMONIC_PATH`
+    Mnemonic { index: u32 },
+    /// Whatever the environment configures (`KEYSTORE_PATH`, `MNEMONIC_PATH` or `PRIVATE_KEY`)
+    Env,
+    /// External signer answering `eth_signTransaction` (e.g. Clef, Web3Signer)
+    Remote { url: String },
+}
+
+impl std::fmt::Display for SignerSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerSource::Keystore { path, .. } => write!(f, "keystore {}", path.display()),
+            SignerSource::Mnemonic { index } => write!(f, "mnemonic index {}", index),
+            SignerSource::Env => write!(f, "environment"),
+            SignerSource::Remote { url } => write!(f, "remote {}", url),
+        }
+    }
+}
+
+/// A named account in the registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountEntry {
+    pub name: String,
+    /// Expected address; required for remote signers, checked against local ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<H160>,
+    pub signer: SignerSource,
+}
+
+/// The local accounts registry file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountsRegistry {
+    pub accounts: Vec<AccountEntry>,
+}
+
+/// Error from an account signer
+#[derive(Debug)]
+pub struct AccountSignerError(String);
+
+impl std::fmt::Display for AccountSignerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for AccountSignerError {}
+
+impl From<WalletError> for AccountSignerError {
+    fn from(e: WalletError) -> Self {
+        AccountSignerError(e.to_string())
+    }
+}
+
+/// A loaded signer: a local key, or an external signer reached over JSON-RPC
+#[derive(Debug, Clone)]
+pub enum AccountSigner {
+    Local(LocalWallet),
+    Remote { url: String, address: H160, chain_id: u64 },
+}
+
+impl AccountSigner {
+    /// Provider pointed at the remote signer
+    fn remote_provider(url: &str) -> Result<Provider<Http>, AccountSignerError> {
+        Provider::<Http>::try_from(url).map_err(|e| AccountSignerError(format!("Invalid signer URL {}: {}", url, e)))
+    }
+
+    /// Sign EIP-712 typed data given as JSON
+    ///
+    /// Unlike `Signer::sign_typed_data`, this also works with remote signers,
+    /// which receive the JSON through `eth_signTypedData_v4`.
+    pub async fn sign_typed_data_json(&self, typed: &TypedData) -> Result<Signature, AccountSignerError> {
+        match self {
+            AccountSigner::Local(wallet) => Ok(wallet.sign_typed_data(typed).await?),
+            AccountSigner::Remote { url, address, .. } => {
+                let json = serde_json::to_string(typed)
+                    .map_err(|e| AccountSignerError(format!("Failed to encode typed data: {}", e)))?;
+                let signature: Bytes = Self::remote_provider(url)?
+                    .request("eth_signTypedData_v4", (address, json))
+                    .await
+                    .map_err(|e| AccountSignerError(format!("Remote signer error: {}", e)))?;
+                Signature::try_from(signature.as_ref())
+                    .map_err(|e| AccountSignerError(format!("Invalid signature from remote signer: {}", e)))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for AccountSigner {
+    type Error = AccountSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        match self {
+            AccountSigner::Local(wallet) => Ok(wallet.sign_message(message).await?),
+            AccountSigner::Remote { url, address, .. } => {
+                // `eth_sign` applies the EIP-191 personal message prefix
+                let data = Bytes::from(message.as_ref().to_vec());
+                let signature: Bytes = Self::remote_provider(url)?
+                    .request("eth_sign", (address, data))
+                    .await
+                    .map_err(|e| AccountSignerError(format!("Remote signer error: {}", e)))?;
+                Signature::try_from(signature.as_ref())
+                    .map_err(|e| AccountSignerError(format!("Invalid signature from remote signer: {}", e)))
+            }
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let from = self.address();
+        let chain_id = tx.chain_id().map(|c| c.as_u64()).unwrap_or_else(|| self.chain_id());
+        let audit_error = |e: Box<dyn std::error::Error>| AccountSignerError(format!("Audit log: {}", e));
+        let decision = crate::policy::enforce(from, chain_id, tx).map_err(|e| AccountSignerError(e.to_string()))?;
+        if let Some(denial) = decision.denial(from) {
+            crate::audit::record(from, chain_id, tx, None, decision).map_err(audit_error)?;
+            return Err(AccountSignerError(denial));
+        }
+        let (signature, signed) = match self {
+            AccountSigner::Local(wallet) => (wallet.sign_transaction(tx).await?, None),
+            AccountSigner::Remote { url, address, chain_id } => {
+                let mut tx = tx.clone();
+                tx.set_from(*address);
+                if tx.chain_id().is_none() {
+                    tx.set_chain_id(*chain_id);
+                }
+                let response: serde_json::Value = Self::remote_provider(url)?
+                    .request("eth_signTransaction", [&tx])
+                    .await
+                    .map_err(|e| AccountSignerError(format!("Remote signer error: {}", e)))?;
+                let raw = parse_signed_response(&response)?;
+                let (signed, signature) = check_remote_signed(&tx, &raw, *address)?;
+                (signature, Some(signed))
+            }
+        };
+        // A signature that cannot be logged is not handed out. For remote
+        // signers the log shows what was actually signed.
+        let logged = signed.as_ref().unwrap_or(tx);
+        crate::audit::record(from, chain_id, logged, Some(&signature), decision).map_err(audit_error)?;
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        match self {
+            AccountSigner::Local(wallet) => Ok(wallet.sign_typed_data(payload).await?),
+            AccountSigner::Remote { .. } => Err(AccountSignerError(
+                "Typed data signing is not supported for remote signers".to_string(),
+            )),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            AccountSigner::Local(wallet) => wallet.address(),
+            AccountSigner::Remote { address, .. } => *address,
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            AccountSigner::Local(wallet) => wallet.chain_id(),
+            AccountSigner::Remote { chain_id, .. } => *chain_id,
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            AccountSigner::Local(wallet) => AccountSigner::Local(wallet.with_chain_id(chain_id)),
+            AccountSigner::Remote { url, address, .. } => AccountSigner::Remote {
+                url,
+                address,
+                chain_id: chain_id.into(),
+            },
+        }
+    }
+}
+
+/// Extract raw bytes from an `eth_signTransaction` response
+///
+/// Geth and Clef return `{ "raw": "0x..", "tx": {..} }`, other signers a bare hex string.
+fn parse_signed_response(response: &serde_json::Value) -> Result<Bytes, AccountSignerError> {
+    let raw = response
+        .as_str()
+        .or_else(|| response.get("raw").and_then(|r| r.as_str()))
+        .ok_or_else(|| AccountSignerError("Remote signer returned an unexpected response".to_string()))?;
+    Bytes::from_str(raw).map_err(|e| AccountSignerError(format!("Invalid hex from remote signer: {}", e)))
+}
+
+/// Decode a remote signer's raw transaction and check it is the one requested
+///
+/// The signer could change the recipient, value, calldata, nonce, gas or fees,
+/// or sign with another key; any difference from the transaction the policy
+/// approved is rejected.
+///
+/// # Arguments
+/// * `requested` - Transaction sent to `eth_signTransaction`
+/// * `raw` - Signed RLP returned by the signer
+/// * `address` - Address the signature must recover to
+///
+/// # Returns
+/// * `Result<(TypedTransaction, Signature), AccountSignerError>` - Decoded transaction and its signature
+fn check_remote_signed(
+    requested: &TypedTransaction,
+    raw: &Bytes,
+    address: H160,
+) -> Result<(TypedTransaction, Signature), AccountSignerError> {
+    let (mut signed, signature) = TypedTransaction::decode_signed(&ethers::utils::rlp::Rlp::new(raw))
+        .map_err(|e| AccountSignerError(format!("Invalid transaction from remote signer: {}", e)))?;
+
+    let priority_fee = |tx: &TypedTransaction| match tx {
+        TypedTransaction::Eip1559(tx) => tx.max_priority_fee_per_gas,
+        _ => None,
+    };
+    let access_list = |tx: &TypedTransaction| tx.access_list().cloned().unwrap_or_default();
+    let mismatches: Vec<&str> = [
+        ("type", std::mem::discriminant(requested) == std::mem::discriminant(&signed)),
+        ("to", requested.to_addr() == signed.to_addr()),
+        ("value", requested.value().copied().unwrap_or_default() == signed.value().copied().unwrap_or_default()),
+        ("data", requested.data().cloned().unwrap_or_default() == signed.data().cloned().unwrap_or_default()),
+        ("nonce", requested.nonce() == signed.nonce()),
+        ("gas", requested.gas() == signed.gas()),
+        ("fee", requested.gas_price() == signed.gas_price()),
+        ("priority fee", priority_fee(requested) == priority_fee(&signed)),
+        ("access list", access_list(requested) == access_list(&signed)),
+        ("chain_id", requested.chain_id() == signed.chain_id()),
+    ]
+    .into_iter()
+    .filter(|(_, same)| !same)
+    .map(|(field, _)| field)
+    .collect();
+    if !mismatches.is_empty() {
+        return Err(AccountSignerError(format!(
+            "Remote signer changed the transaction ({}); refusing its signature",
+            mismatches.join(", ")
+        )));
+    }
+
+    let recovered = signature
+        .recover(signed.sighash())
+        .map_err(|e| AccountSignerError(format!("Invalid signature from remote signer: {}", e)))?;
+    if recovered != address {
+        return Err(AccountSignerError(format!(
+            "Remote signer signed with {}, expected {}",
+            checksummed(recovered),
+            checksummed(address)
+        )));
+    }
+    signed.set_from(address);
+    Ok((signed, signature))
+}
+
+/// Path of the registry file: `ACCOUNTS_FILE` or `accounts.json`
+pub fn registry_path() -> PathBuf {
+    PathBuf::from(env::var("ACCOUNTS_FILE").unwrap_or_else(|_| "accounts.json".to_string()))
+}
+
+impl AccountsRegistry {
+    /// Load the registry, returning an empty one if the file does not exist
+    ///
+    /// # Arguments
+    /// * `path` - Registry JSON file
+    ///
+    /// # Returns
+    /// * `Result<AccountsRegistry, Box<dyn std::error::Error>>` - Parsed registry
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(AccountsRegistry::default());
+        }
+        let json = fs::read_to_string(path)
+            .map_err(|_| format!("Failed to read accounts file: {}", path.display()))?;
+        let registry: AccountsRegistry = serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid accounts file {}: {}", path.display(), e))?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Write the registry as pretty-printed JSON
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        self.validate()?;
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// Reject duplicate names and remote signers without an address
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        for (i, entry) in self.accounts.iter().enumerate() {
+            if entry.name.trim().is_empty() {
+                return Err("Account name must not be empty".into());
+            }
+            if self.accounts[..i].iter().any(|e| e.name == entry.name) {
+                return Err(format!("Duplicate account name: {}", entry.name).into());
+            }
+            if matches!(entry.signer, SignerSource::Remote { .. }) && entry.address.is_none() {
+                return Err(format!("Remote account {} needs an address", entry.name).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Find an account by name
+    pub fn get(&self, name: &str) -> Option<&AccountEntry> {
+        self.accounts.iter().find(|e| e.name == name)
+    }
+
+    /// Add a new account
+    pub fn add(&mut self, entry: AccountEntry) -> Result<(), Box<dyn std::error::Error>> {
+        self.accounts.push(entry);
+        if let Err(e) = self.validate() {
+            self.accounts.pop();
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+/// Load the signer for a registry entry
+///
+/// For local signers the derived address must match the entry's address, if one is recorded.
+///
+/// # Arguments
+/// * `entry` - Registry entry
+///
+/// # Returns
+/// * `Result<AccountSigner, Box<dyn std::error::Error>>` - Ready-to-use signer
+pub fn load_signer(entry: &AccountEntry) -> Result<AccountSigner, Box<dyn std::error::Error>> {
+    let signer = match &entry.signer {
+        SignerSource::Keystore { path, password_file } => {
+            let source = match password_file {
+                Some(file) => PassphraseSource::File(file.clone()),
+                None => PassphraseSource::from_env(),
+            };
+            AccountSigner::Local(decrypt_keystore(path, &source)?)
+        }
+        SignerSource::Mnemonic { index } => {
+            let phrase = read_mnemonic()?;
+            let passphrase = read_mnemonic_passphrase()?;
+            AccountSigner::Local(derive_wallet(&phrase, *index, passphrase.as_ref().map(|p| p.as_str()))?)
+        }
+        SignerSource::Env => AccountSigner::Local(get_wallet()?),
+        SignerSource::Remote { url } => AccountSigner::Remote {
+            url: url.clone(),
+            address: entry.address.ok_or("Remote account needs an address")?,
+            chain_id: 1,
+        },
+    };
+
+    if let Some(expected) = entry.address {
+        if signer.address() != expected {
+            return Err(format!(
+                "Account {} is registered as {} but its signer is {}",
+                entry.name,
+                checksummed(expected),
+                checksummed(signer.address())
+            )
+            .into());
+        }
+    }
+    Ok(signer)
+}
+
+/// Resolve the sending account: a registry name, or the environment signer
+///
+/// # Arguments
+/// * `name` - Account name from `--from`, if given
+///
+/// # Returns
+/// * `Result<AccountSigner, Box<dyn std::error::Error>>` - Signer for the sender
+pub fn resolve_signer(name: Option<&str>) -> Result<AccountSigner, Box<dyn std::error::Error>> {
+    match name {
+        Some(name) => {
+            let path = registry_path();
+            let registry = AccountsRegistry::load(&path)?;
+            let entry = registry
+                .get(name)
+                .ok_or_else(|| format!("Unknown account '{}' (not in {})", name, path.display()))?;
+            load_signer(entry)
+        }
+        None => Ok(AccountSigner::Local(get_wallet()?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{test_wallet, use_temp_audit_log};
+
+    #[test]
+    fn test_registry_json() {
+        let json = r#"{
+            "accounts": [
+                { "name": "treasury", "signer": { "type": "keystore", "path": "keystore/treasury.json" } },
+                { "name": "tester", "signer": { "type": "mnemonic", "index": 3 } },
+                { "name": "dev", "signer": { "type": "env" } },
+                {
+                    "name": "ops",
+                    "address": "0xd78677efed3b87f8f421e68da3f984ad8ef76439",
+                    "signer": { "type": "remote", "url": "http://localhost:8550" }
+                }
+            ]
+        }"#;
+        let registry: AccountsRegistry = serde_json::from_str(json).unwrap();
+        assert!(registry.validate().is_ok());
+        assert_eq!(registry.get("tester").unwrap().signer, SignerSource::Mnemonic { index: 3 });
+        assert!(registry.get("missing").is_none());
+
+        let roundtrip: AccountsRegistry =
+            serde_json::from_str(&serde_json::to_string(&registry).unwrap()).unwrap();
+        assert_eq!(roundtrip, registry);
+    }
+
+    #[test]
+    fn test_registry_validation() {
+        let mut registry = AccountsRegistry::default();
+        let dev = AccountEntry { name: "dev".to_string(), address: None, signer: SignerSource::Env };
+        registry.add(dev.clone()).unwrap();
+        assert!(registry.add(dev).is_err());
+        assert_eq!(registry.accounts.len(), 1);
+
+        let remote = AccountEntry {
+            name: "ops".to_string(),
+            address: None,
+            signer: SignerSource::Remote { url: "http://localhost:8550".to_string() },
+        };
+        assert!(registry.add(remote).is_err());
+    }
+
+    #[test]
+    fn test_parse_signed_response() {
+        let bare = serde_json::json!("0x02f8");
+        assert_eq!(parse_signed_response(&bare).unwrap(), Bytes::from(vec![0x02, 0xf8]));
+        let geth = serde_json::json!({ "raw": "0xf86c", "tx": {} });
+        assert_eq!(parse_signed_response(&geth).unwrap(), Bytes::from(vec![0xf8, 0x6c]));
+        assert!(parse_signed_response(&serde_json::json!({})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_remote_signed() {
+        let wallet = test_wallet();
+        let request = || {
+            TransactionRequest::new()
+                .from(wallet.address())
+                .to(H160::repeat_byte(0x11))
+                .value(1)
+                .nonce(0)
+                .gas(21_000)
+                .gas_price(1)
+                .chain_id(421614u64)
+        };
+        let tx: TypedTransaction = request().into();
+        let raw = tx.rlp_signed(&wallet.sign_transaction(&tx).await.unwrap());
+
+        let (signed, _) = check_remote_signed(&tx, &raw, wallet.address()).unwrap();
+        assert_eq!(signed.to_addr(), tx.to_addr());
+
+        // The signer swapped the recipient and value
+        let other: TypedTransaction = request().to(H160::repeat_byte(0x22)).value(2).into();
+        let raw_other = other.rlp_signed(&wallet.sign_transaction(&other).await.unwrap());
+        let err = check_remote_signed(&tx, &raw_other, wallet.address()).unwrap_err();
+        assert!(err.to_string().contains("to, value"));
+
+        // Right transaction, wrong key
+        let stranger = LocalWallet::from_bytes(&[0x22; 32]).unwrap().with_chain_id(421614u64);
+        let raw_stranger = tx.rlp_signed(&stranger.sign_transaction(&tx).await.unwrap());
+        assert!(check_remote_signed(&tx, &raw_stranger, wallet.address()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_signer_signs_for_its_address() {
+        use_temp_audit_log();
+        let wallet = test_wallet();
+        let signer = AccountSigner::Local(wallet.clone()).with_chain_id(421614u64);
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(H160::repeat_byte(0x11))
+            .value(1)
+            .nonce(0)
+            .gas(21_000)
+            .gas_price(1)
+            .chain_id(421614u64)
+            .into();
+
+        let signature = signer.sign_transaction(&tx).await.unwrap();
+        let raw = tx.rlp_signed(&signature);
+        let decoded: Transaction = ethers::utils::rlp::decode(&raw).unwrap();
+        assert_eq!(decoded.recover_from().unwrap(), wallet.address());
+    }
+}
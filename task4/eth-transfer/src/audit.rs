@@ -0,0 +1,361 @@
+//! Tamper-evident audit log of signed transactions
+//!
+//! Every transaction an `AccountSigner` signs, or the policy denies, is
+//! appended to a JSON-lines log (`--audit-log FILE`, else `AUDIT_LOG`, else
+//! `audit.log`). Each
+//! entry carries the hash of the one before it and its own hash over its
+//! contents, so editing, removing or reordering entries breaks the chain.
+//! A head file next to the log (`audit.log.head`) records the entry count
+//! and last hash, which catches truncation; keeping a copy of the head hash
+//! elsewhere also catches the log and head being rewritten together.
+
+use crate::policy::PolicyDecision;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Signature, H160, H256, U256};
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Serializes appends within the process
+static APPEND: Mutex<()> = Mutex::new(());
+
+/// Log path set by `--audit-log`, ahead of `AUDIT_LOG`
+static LOG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Write the audit log to `path` for the rest of the process
+pub fn set_log_path(path: PathBuf) {
+    *LOG_PATH.lock().unwrap() = Some(path);
+}
+
+/// One signing attempt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditEntry {
+    /// Position in the log, from 0
+    pub seq: u64,
+    /// Unix time of signing
+    pub time: u64,
+    pub signer: H160,
+    pub chain_id: u64,
+    pub tx_type: String,
+    pub nonce: Option<U256>,
+    pub to: Option<H160>,
+    pub value: U256,
+    /// keccak256 of the calldata
+    pub data_hash: H256,
+    pub data_len: usize,
+    pub gas_limit: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Hash of the signed transaction; `None` if the policy denied it
+    pub tx_hash: Option<H256>,
+    pub policy: PolicyDecision,
+    pub prev_hash: H256,
+    /// keccak256 of this entry's JSON with `hash` zeroed
+    pub hash: H256,
+}
+
+impl AuditEntry {
+    /// Entry for a transaction, not yet chained
+    ///
+    /// # Arguments
+    /// * `signer` - Signing account
+    /// * `chain_id` - Chain the transaction is signed for
+    /// * `tx` - The transaction
+    /// * `signature` - Its signature, `None` if signing was refused
+    /// * `policy` - Policy decision for it
+    pub fn new(signer: H160, chain_id: u64, tx: &TypedTransaction, signature: Option<&Signature>, policy: PolicyDecision) -> Self {
+        let data = tx.data().map(|d| d.to_vec()).unwrap_or_default();
+        let (tx_type, gas_price, max_fee_per_gas, max_priority_fee_per_gas) = match tx {
+            TypedTransaction::Legacy(tx) => ("legacy", tx.gas_price, None, None),
+            TypedTransaction::Eip2930(tx) => ("eip2930", tx.tx.gas_price, None, None),
+            TypedTransaction::Eip1559(tx) => ("eip1559", None, tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+        };
+        AuditEntry {
+            seq: 0,
+            time: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+            signer,
+            chain_id,
+            tx_type: tx_type.to_string(),
+            nonce: tx.nonce().copied(),
+            to: tx.to_addr().copied(),
+            value: tx.value().copied().unwrap_or_default(),
+            data_hash: H256::from(keccak256(&data)),
+            data_len: data.len(),
+            gas_limit: tx.gas().copied(),
+            gas_price,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            tx_hash: signature.map(|signature| tx.hash(signature)),
+            policy,
+            prev_hash: H256::zero(),
+            hash: H256::zero(),
+        }
+    }
+
+    /// Hash over the entry's contents, including `prev_hash`
+    pub fn compute_hash(&self) -> H256 {
+        let mut unhashed = self.clone();
+        unhashed.hash = H256::zero();
+        H256::from(keccak256(serde_json::to_vec(&unhashed).expect("audit entry serializes")))
+    }
+}
+
+/// Entry count and last hash, stored next to the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditHead {
+    pub count: u64,
+    pub head: H256,
+}
+
+/// Log path: the one set by `set_log_path`, else `AUDIT_LOG` or `audit.log`
+pub fn audit_path() -> PathBuf {
+    if let Some(path) = LOG_PATH.lock().unwrap().clone() {
+        return path;
+    }
+    PathBuf::from(env::var("AUDIT_LOG").unwrap_or_else(|_| "audit.log".to_string()))
+}
+
+/// Head file for a log
+pub fn head_path(log: &Path) -> PathBuf {
+    let mut name = log.as_os_str().to_owned();
+    name.push(".head");
+    PathBuf::from(name)
+}
+
+/// Read a log's head file
+///
+/// A missing head is only accepted for a log with no entries.
+fn read_head(path: &Path, log_is_empty: bool) -> Result<AuditHead, Box<dyn std::error::Error>> {
+    let head_file = head_path(path);
+    match fs::read_to_string(&head_file) {
+        Ok(head) => Ok(serde_json::from_str(&head).map_err(|e| format!("Invalid head file {}: {}", head_file.display(), e))?),
+        Err(_) if log_is_empty => Ok(AuditHead { count: 0, head: H256::zero() }),
+        Err(_) => Err(format!("Head file {} is missing: cannot check for truncation", head_file.display()).into()),
+    }
+}
+
+/// Chain an entry onto the log and update its head
+///
+/// Refuses to append when the log's last entry does not match its head
+/// file, so a truncated log is not extended into one that verifies.
+///
+/// # Arguments
+/// * `path` - Log file, created if missing
+/// * `entry` - Entry from `AuditEntry::new`
+///
+/// # Returns
+/// * `Result<AuditEntry, Box<dyn std::error::Error>>` - The entry as written, with `seq` and hashes set
+pub fn append(path: &Path, mut entry: AuditEntry) -> Result<AuditEntry, Box<dyn std::error::Error>> {
+    let _guard = APPEND.lock().unwrap();
+    let last = if path.exists() {
+        let text = fs::read_to_string(path).map_err(|_| format!("Failed to read audit log {}", path.display()))?;
+        match text.lines().next_back() {
+            Some(line) => Some(
+                serde_json::from_str::<AuditEntry>(line)
+                    .map_err(|e| format!("Audit log {} ends with an invalid entry: {}", path.display(), e))?,
+            ),
+            None => None,
+        }
+    } else {
+        None
+    };
+    let tail = AuditHead {
+        count: last.as_ref().map(|l| l.seq + 1).unwrap_or(0),
+        head: last.as_ref().map(|l| l.hash).unwrap_or_default(),
+    };
+    let head = read_head(path, last.is_none())?;
+    if head != tail {
+        return Err(format!(
+            "Audit log {} ends at entry {} ({:#x}), but its head file records {} ({:#x}): refusing to append to a truncated or replaced log",
+            path.display(),
+            tail.count,
+            tail.head,
+            head.count,
+            head.head
+        )
+        .into());
+    }
+    entry.seq = tail.count;
+    entry.prev_hash = last.map(|l| l.hash).unwrap_or_default();
+    entry.hash = entry.compute_hash();
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open audit log {}: {}", path.display(), e))?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    file.sync_all()?;
+
+    let head = AuditHead { count: entry.seq + 1, head: entry.hash };
+    let head_file = head_path(path);
+    let tmp = head_file.with_extension("head.tmp");
+    fs::write(&tmp, serde_json::to_string(&head)?)?;
+    fs::rename(&tmp, &head_file)?;
+    Ok(entry)
+}
+
+/// Record a signing attempt in the default log
+pub fn record(
+    signer: H160,
+    chain_id: u64,
+    tx: &TypedTransaction,
+    signature: Option<&Signature>,
+    policy: PolicyDecision,
+) -> Result<AuditEntry, Box<dyn std::error::Error>> {
+    append(&audit_path(), AuditEntry::new(signer, chain_id, tx, signature, policy))
+}
+
+/// Check a log's hash chain against itself and its head
+///
+/// # Arguments
+/// * `path` - Log file
+/// * `expected_head` - Head hash recorded elsewhere, if any
+///
+/// # Returns
+/// * `Result<Vec<AuditEntry>, Box<dyn std::error::Error>>` - Entries if the log is intact, else the first problem found
+pub fn verify(path: &Path, expected_head: Option<H256>) -> Result<Vec<AuditEntry>, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path).map_err(|_| format!("Failed to read audit log {}", path.display()))?;
+    let mut entries: Vec<AuditEntry> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let entry: AuditEntry =
+            serde_json::from_str(line).map_err(|e| format!("Line {}: not a valid audit entry: {}", line_no, e))?;
+        if entry.seq != i as u64 {
+            return Err(format!("Line {}: sequence number {} (expected {}): entries removed or reordered", line_no, entry.seq, i).into());
+        }
+        let prev = entries.last().map(|e| e.hash).unwrap_or_default();
+        if entry.prev_hash != prev {
+            return Err(format!("Line {}: previous hash does not match line {}: chain broken", line_no, i).into());
+        }
+        if entry.compute_hash() != entry.hash {
+            return Err(format!("Line {}: contents do not match the entry hash: entry edited", line_no).into());
+        }
+        entries.push(entry);
+    }
+    if !text.is_empty() && !text.ends_with('\n') {
+        return Err("Last line is incomplete: the log was cut off mid-write".into());
+    }
+
+    let last = entries.last().map(|e| e.hash).unwrap_or_default();
+    let head = read_head(path, entries.is_empty())?;
+    if head.count != entries.len() as u64 || head.head != last {
+        return Err(format!(
+            "Log has {} entries ending {:#x}, but the head file records {} ending {:#x}: log truncated or replaced",
+            entries.len(),
+            last,
+            head.count,
+            head.head
+        )
+        .into());
+    }
+    if let Some(expected) = expected_head.filter(|expected| *expected != last) {
+        return Err(format!("Log ends {:#x}, not the expected head {:#x}", last, expected).into());
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_path, test_wallet};
+    use ethers::signers::Signer;
+    use ethers::types::TransactionRequest;
+
+    fn write_log(name: &str, count: u64) -> PathBuf {
+        let path = temp_path(&format!("audit-test-{}.log", name));
+        fs::remove_file(&path).ok();
+        fs::remove_file(head_path(&path)).ok();
+        let wallet = test_wallet();
+        for nonce in 0..count {
+            let tx: TypedTransaction = TransactionRequest::new()
+                .to(H160::repeat_byte(0x11))
+                .value(1000 + nonce)
+                .nonce(nonce)
+                .gas(21_000)
+                .gas_price(1)
+                .chain_id(421614u64)
+                .into();
+            let signature = wallet.sign_transaction_sync(&tx).unwrap();
+            let entry = append(&path, AuditEntry::new(wallet.address(), 421614, &tx, Some(&signature), PolicyDecision::Allowed)).unwrap();
+            assert_eq!(entry.seq, nonce);
+            assert_eq!(entry.tx_hash, Some(tx.hash(&signature)));
+        }
+        path
+    }
+
+    fn cleanup(path: &Path) {
+        fs::remove_file(path).ok();
+        fs::remove_file(head_path(path)).ok();
+    }
+
+    #[test]
+    fn test_chain_verifies() {
+        let path = write_log("ok", 3);
+        let entries = verify(&path, None).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].prev_hash, entries[1].hash);
+        assert!(verify(&path, Some(entries[2].hash)).is_ok());
+        assert!(verify(&path, Some(entries[1].hash)).unwrap_err().to_string().contains("expected head"));
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_detects_edits_and_truncation() {
+        let path = write_log("tamper", 3);
+        let original = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = original.lines().collect();
+
+        // Edited value
+        fs::write(&path, original.replacen("\"0x3e9\"", "\"0x3e8\"", 1)).unwrap();
+        assert!(verify(&path, None).unwrap_err().to_string().contains("Line 2: contents"));
+
+        // Removed middle entry
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert!(verify(&path, None).unwrap_err().to_string().contains("Line 2: sequence"));
+
+        // Removed last entry
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[1])).unwrap();
+        assert!(verify(&path, None).unwrap_err().to_string().contains("truncated"));
+
+        // Missing head file
+        fs::write(&path, &original).unwrap();
+        assert!(verify(&path, None).is_ok());
+        fs::remove_file(head_path(&path)).unwrap();
+        assert!(verify(&path, None).unwrap_err().to_string().contains("missing"));
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_refuses_to_extend_truncated_log() {
+        let path = write_log("extend", 3);
+        let original = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = original.lines().collect();
+        let wallet = test_wallet();
+        let tx: TypedTransaction = TransactionRequest::new().to(H160::repeat_byte(0x11)).nonce(9).chain_id(421614u64).into();
+        let entry = || AuditEntry::new(wallet.address(), 421614, &tx, None, PolicyDecision::Allowed);
+
+        // Appending after the last entry was cut must not paper over the cut
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[1])).unwrap();
+        assert!(append(&path, entry()).unwrap_err().to_string().contains("refusing to append"));
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        assert!(verify(&path, None).unwrap_err().to_string().contains("truncated"));
+
+        // Nor after the head file was removed
+        fs::write(&path, &original).unwrap();
+        fs::remove_file(head_path(&path)).unwrap();
+        assert!(append(&path, entry()).unwrap_err().to_string().contains("missing"));
+        assert!(verify(&path, None).is_err());
+        cleanup(&path);
+    }
+}
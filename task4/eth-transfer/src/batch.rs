@@ -0,0 +1,655 @@
+//! CSV-driven batch payouts with resumable state
+//!
+//! Each row is `address,amount[,token]`: an address book label or a
+//! checksummed address, an amount in ETH (or in token units when a token is
+//! given), and an optional ERC20 token. Every row is validated before
+//! anything is sent. Progress is written to a state file after every
+//! signature, before the transaction is broadcast, so a crashed run can be
+//! resumed without paying anyone twice.
+
+use crate::nonce::NonceManager;
+use crate::transfer::check_recipient;
+use arb_common::address::{checksummed, AddressWarning};
+use arb_common::address_book::AddressBook;
+use arb_common::amount::Amount;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One validated payout row
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRow {
+    /// Line number in the CSV file, starting at 1
+    pub line: usize,
+    pub recipient: H160,
+    /// Amount as written in the file
+    pub amount: String,
+    /// ERC20 token, or `None` for ETH
+    pub token: Option<H160>,
+}
+
+/// Parse and validate a payout CSV
+///
+/// A leading `address,amount,token` header, blank lines and `#` comments are
+/// skipped. Addresses without an EIP-55 checksum are rejected, as are the
+/// zero address, precompiles and repeated payouts of the same asset to the
+/// same recipient.
+///
+/// # Arguments
+/// * `csv` - File contents
+/// * `book` - Address book for labels and chain restrictions
+/// * `chain_id` - Connected chain
+///
+/// # Returns
+/// * `Result<Vec<BatchRow>, String>` - Rows, or every problem found, one per line
+pub fn parse_batch(csv: &str, book: &AddressBook, chain_id: u64) -> Result<Vec<BatchRow>, String> {
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen: HashMap<(H160, Option<H160>), usize> = HashMap::new();
+    let mut first = true;
+
+    for (i, text) in csv.lines().enumerate() {
+        let line = i + 1;
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+        if std::mem::take(&mut first) && fields[0].eq_ignore_ascii_case("address") {
+            continue;
+        }
+        if fields.len() < 2 || fields.len() > 3 {
+            errors.push(format!("line {}: expected address,amount[,token], got {} field(s)", line, fields.len()));
+            continue;
+        }
+
+        let recipient = match book.resolve(fields[0], chain_id) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                errors.push(format!("line {}: {}", line, e));
+                continue;
+            }
+        };
+        if recipient.warnings.contains(&AddressWarning::NoChecksum) {
+            errors.push(format!(
+                "line {}: {} has no EIP-55 checksum; use {} or an address book label",
+                line,
+                fields[0],
+                checksummed(recipient.address)
+            ));
+            continue;
+        }
+        if let Err(e) = check_recipient(&recipient) {
+            errors.push(format!("line {}: {}", line, e));
+            continue;
+        }
+
+        let token = match fields.get(2).filter(|t| !t.is_empty()) {
+            Some(token) => match book.resolve(token, chain_id) {
+                Ok(parsed) if parsed.warnings.is_empty() => Some(parsed.address),
+                Ok(parsed) => {
+                    errors.push(format!("line {}: token {}: {}", line, token, parsed.warnings[0]));
+                    continue;
+                }
+                Err(e) => {
+                    errors.push(format!("line {}: token {}", line, e));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        if fields[1].is_empty() {
+            errors.push(format!("line {}: missing amount", line));
+            continue;
+        }
+
+        if let Some(first_line) = seen.insert((recipient.address, token), line) {
+            errors.push(format!(
+                "line {}: duplicate payout to {} (first on line {})",
+                line,
+                book.display(recipient.address),
+                first_line
+            ));
+            continue;
+        }
+        rows.push(BatchRow {
+            line,
+            recipient: recipient.address,
+            amount: fields[1].to_string(),
+            token,
+        });
+    }
+
+    if rows.is_empty() && errors.is_empty() {
+        errors.push("no payouts in the file".to_string());
+    }
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Parse every row's amount with its asset's decimals
+///
+/// # Arguments
+/// * `rows` - Rows from `parse_batch`
+/// * `decimals` - Decimals of every token in the rows
+///
+/// # Returns
+/// * `Result<Vec<Amount>, String>` - Amounts in row order, or every problem found
+pub fn parse_amounts(rows: &[BatchRow], decimals: &HashMap<H160, u8>) -> Result<Vec<Amount>, String> {
+    let mut amounts = Vec::new();
+    let mut errors = Vec::new();
+    for row in rows {
+        let parsed = match row.token {
+            Some(token) => match decimals.get(&token) {
+                Some(d) => Amount::parse(&row.amount, *d),
+                None => Err(format!("no decimals for token {}", checksummed(token)).into()),
+            },
+            None => Amount::parse_ether(&row.amount),
+        };
+        match parsed {
+            Ok(amount) if amount.is_zero() => errors.push(format!("line {}: amount is zero", row.line)),
+            Ok(amount) => amounts.push(amount),
+            Err(e) => errors.push(format!("line {}: {}", row.line, e)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(amounts)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Raw total per asset (`None` is ETH)
+///
+/// # Arguments
+/// * `rows` - Rows from `parse_batch`
+/// * `amounts` - Amounts from `parse_amounts`, in row order
+///
+/// # Returns
+/// * `Result<BTreeMap<Option<H160>, U256>, String>` - Totals, or the row at which a total overflows
+pub fn totals(rows: &[BatchRow], amounts: &[Amount]) -> Result<BTreeMap<Option<H160>, U256>, String> {
+    let mut totals = BTreeMap::new();
+    for (row, amount) in rows.iter().zip(amounts) {
+        let total = totals.entry(row.token).or_insert_with(U256::zero);
+        *total = total
+            .checked_add(amount.raw())
+            .ok_or_else(|| format!("line {}: total amount overflows 256 bits", row.line))?;
+    }
+    Ok(totals)
+}
+
+/// Where a payout stands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PayoutStatus {
+    /// Not sent yet
+    Pending,
+    /// Signed and handed to the node; may or may not be mined
+    Sent { nonce: U256, tx_hash: H256 },
+    /// Mined successfully
+    Confirmed { tx_hash: H256, block: U64 },
+    /// Reverted, rejected, or its nonce went to another transaction
+    Failed {
+        error: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tx_hash: Option<H256>,
+    },
+}
+
+/// A payout and its progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PayoutRecord {
+    pub line: usize,
+    /// Checksummed recipient
+    pub recipient: String,
+    pub amount: String,
+    /// Checksummed token, `None` for ETH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(flatten)]
+    pub status: PayoutStatus,
+}
+
+/// Progress of a batch, saved next to the CSV
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchState {
+    /// Hash of the CSV contents the state belongs to
+    pub source: String,
+    /// Checksummed sending account
+    pub sender: String,
+    pub payouts: Vec<PayoutRecord>,
+}
+
+/// Hash identifying a CSV file's contents
+pub fn source_hash(csv: &str) -> String {
+    format!("{:#x}", H256::from(keccak256(csv.as_bytes())))
+}
+
+/// Default state file for a CSV: the same path with `.state.json` appended
+pub fn state_path(csv_path: &Path) -> PathBuf {
+    let mut path = csv_path.as_os_str().to_owned();
+    path.push(".state.json");
+    PathBuf::from(path)
+}
+
+impl BatchState {
+    /// Fresh state with every payout pending
+    pub fn new(csv: &str, sender: H160, rows: &[BatchRow]) -> Self {
+        BatchState {
+            source: source_hash(csv),
+            sender: checksummed(sender),
+            payouts: rows
+                .iter()
+                .map(|row| PayoutRecord {
+                    line: row.line,
+                    recipient: checksummed(row.recipient),
+                    amount: row.amount.clone(),
+                    token: row.token.map(checksummed),
+                    status: PayoutStatus::Pending,
+                })
+                .collect(),
+        }
+    }
+
+    /// Load a state file
+    ///
+    /// # Returns
+    /// * `Result<Option<BatchState>, Box<dyn std::error::Error>>` - State, or `None` if the file does not exist
+    pub fn load(path: &Path) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(path)?;
+        let state = serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid batch state file {}: {}", path.display(), e))?;
+        Ok(Some(state))
+    }
+
+    /// Write the state file, replacing it atomically
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Check that a saved state belongs to this CSV and sender
+    pub fn check_matches(&self, csv: &str, sender: H160) -> Result<(), String> {
+        if self.source != source_hash(csv) {
+            return Err("The CSV changed since the state file was written; \
+                        finish the original batch or remove the state file"
+                .to_string());
+        }
+        if self.sender != checksummed(sender) {
+            return Err(format!(
+                "The state file belongs to sender {}, not {}",
+                self.sender,
+                checksummed(sender)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Nonces of payouts that were sent but are not settled
+    pub fn in_flight_nonces(&self) -> Vec<U256> {
+        self.payouts
+            .iter()
+            .filter_map(|p| match p.status {
+                PayoutStatus::Sent { nonce, .. } => Some(nonce),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Count of payouts per status name
+    pub fn summary(&self) -> BTreeMap<&'static str, usize> {
+        let mut summary = BTreeMap::new();
+        for payout in &self.payouts {
+            let name = match payout.status {
+                PayoutStatus::Pending => "pending",
+                PayoutStatus::Sent { .. } => "sent",
+                PayoutStatus::Confirmed { .. } => "confirmed",
+                PayoutStatus::Failed { .. } => "failed",
+            };
+            *summary.entry(name).or_insert(0) += 1;
+        }
+        summary
+    }
+}
+
+/// Send one payout, saving its nonce and hash before the broadcast
+///
+/// A payout whose broadcast fails after it was signed stays `Sent`: the
+/// node may have accepted it anyway (an RPC timeout, say), so `reconcile`
+/// decides what happened before anything is resent. Only a payout that was
+/// never signed is marked failed.
+///
+/// # Arguments
+/// * `manager` - Nonce manager for the sender
+/// * `tx` - Payout transaction
+/// * `state` - Batch state to update
+/// * `index` - Payout in `state`
+/// * `state_path` - State file, saved after every change
+///
+/// # Returns
+/// * `Result<(U256, H256), Box<dyn std::error::Error>>` - Nonce and transaction hash
+pub async fn send_payout<M: Middleware>(
+    manager: &NonceManager<M>,
+    tx: TypedTransaction,
+    state: &mut BatchState,
+    index: usize,
+    state_path: &Path,
+) -> Result<(U256, H256), Box<dyn std::error::Error>> {
+    let result = manager
+        .send_with(tx, &mut |nonce, tx_hash| {
+            state.payouts[index].status = PayoutStatus::Sent { nonce, tx_hash };
+            state.save(state_path)
+        })
+        .await;
+    if let Err(e) = &result {
+        if !matches!(state.payouts[index].status, PayoutStatus::Sent { .. }) {
+            state.payouts[index].status = PayoutStatus::Failed { error: e.to_string(), tx_hash: None };
+        }
+    }
+    state.save(state_path)?;
+    result
+}
+
+/// Bring `Sent` payouts from an earlier run up to date
+///
+/// Mined payouts become confirmed or failed, ones the node still has stay
+/// sent. A payout the node never received goes back to pending, unless its
+/// nonce has been used since, in which case it is marked failed rather than
+/// risk paying twice.
+///
+/// # Arguments
+/// * `client` - Provider
+/// * `state` - Batch state to update
+/// * `sender` - Sending account
+///
+/// # Returns
+/// * `Result<(), Box<dyn std::error::Error>>` - Ok once every sent payout is checked
+pub async fn reconcile<M: Middleware>(
+    client: &M,
+    state: &mut BatchState,
+    sender: H160,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Read before the receipts, so a payout mined in between still shows up as mined
+    let used = client
+        .get_transaction_count(sender, Some(BlockNumber::Latest.into()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for payout in &mut state.payouts {
+        let PayoutStatus::Sent { nonce, tx_hash } = payout.status.clone() else {
+            continue;
+        };
+        let receipt = client.get_transaction_receipt(tx_hash).await.map_err(|e| e.to_string())?;
+        if let Some(block) = receipt.as_ref().and_then(|r| r.block_number) {
+            payout.status = if receipt.and_then(|r| r.status) == Some(U64::from(1)) {
+                PayoutStatus::Confirmed { tx_hash, block }
+            } else {
+                PayoutStatus::Failed { error: "transaction reverted".to_string(), tx_hash: Some(tx_hash) }
+            };
+            continue;
+        }
+        if client.get_transaction(tx_hash).await.map_err(|e| e.to_string())?.is_some() {
+            continue;
+        }
+        payout.status = if used > nonce {
+            PayoutStatus::Failed {
+                error: format!("nonce {} was used by another transaction; check the account before retrying with --retry-nonce-reused", nonce),
+                tx_hash: Some(tx_hash),
+            }
+        } else {
+            PayoutStatus::Pending
+        };
+    }
+    Ok(())
+}
+
+/// Put failed payouts back to pending so they are sent again
+///
+/// Payouts that were never broadcast and payouts that reverted are always
+/// retried. One whose transaction never mined because another transaction
+/// took its nonce may have been paid by that transaction (a speed-up from a
+/// wallet, say), so it is only retried with `nonce_reused`. A "failed"
+/// payout that turns out to have mined successfully is marked confirmed.
+///
+/// # Arguments
+/// * `client` - Provider
+/// * `state` - Batch state to update
+/// * `nonce_reused` - Also retry payouts whose nonce went to another transaction
+///
+/// # Returns
+/// * `Result<Vec<usize>, Box<dyn std::error::Error>>` - Payouts left failed because their nonce was reused
+pub async fn retry_failed<M: Middleware>(
+    client: &M,
+    state: &mut BatchState,
+    nonce_reused: bool,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    let mut kept = Vec::new();
+    for (i, payout) in state.payouts.iter_mut().enumerate() {
+        let PayoutStatus::Failed { tx_hash, .. } = payout.status.clone() else {
+            continue;
+        };
+        let Some(tx_hash) = tx_hash else {
+            payout.status = PayoutStatus::Pending;
+            continue;
+        };
+        let receipt = client.get_transaction_receipt(tx_hash).await.map_err(|e| e.to_string())?;
+        match receipt.as_ref().and_then(|r| r.block_number) {
+            Some(block) if receipt.and_then(|r| r.status) == Some(U64::from(1)) => {
+                payout.status = PayoutStatus::Confirmed { tx_hash, block };
+            }
+            Some(_) => payout.status = PayoutStatus::Pending,
+            None if nonce_reused => payout.status = PayoutStatus::Pending,
+            None => kept.push(i),
+        }
+    }
+    Ok(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_wallet;
+    use arb_common::address_book::AddressEntry;
+    use ethers::providers::{JsonRpcError, MockResponse};
+    use std::str::FromStr;
+
+    const ALICE: &str = "0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439";
+    const BOB: &str = "0x7292dD72151DaCFBbE76305db1C8Ab1928E922E4";
+    const TOKEN: &str = "0x812dD1c3EB07bb1F5f93540350EF9aF838AB0528";
+
+    fn book() -> AddressBook {
+        let mut book = AddressBook::default();
+        book.add(AddressEntry {
+            label: "bob".to_string(),
+            address: H160::from_str(BOB).unwrap(),
+            notes: None,
+            chains: vec![],
+        })
+        .unwrap();
+        book
+    }
+
+    #[test]
+    fn test_parse_batch() {
+        let csv = format!("address,amount,token\n# testers\n{},0.01\nbob,5,{}\n\n", ALICE, TOKEN);
+        let rows = parse_batch(&csv, &book(), 421614).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], BatchRow { line: 3, recipient: H160::from_str(ALICE).unwrap(), amount: "0.01".into(), token: None });
+        assert_eq!(rows[1].token, Some(H160::from_str(TOKEN).unwrap()));
+
+        let decimals = HashMap::from([(H160::from_str(TOKEN).unwrap(), 6u8)]);
+        let amounts = parse_amounts(&rows, &decimals).unwrap();
+        assert_eq!(amounts[1].raw(), U256::from(5_000_000));
+        let totals = totals(&rows, &amounts).unwrap();
+        assert_eq!(totals[&None], U256::from(10_000_000_000_000_000u64));
+
+        let huge = [Amount::new(U256::MAX, 18), Amount::new(U256::one(), 18)];
+        let rows = [rows[0].clone(), BatchRow { line: 7, ..rows[0].clone() }];
+        assert_eq!(super::totals(&rows, &huge).unwrap_err(), "line 7: total amount overflows 256 bits");
+    }
+
+    #[test]
+    fn test_parse_batch_reports_every_problem() {
+        let csv = format!(
+            "{},1\n{},2\n{}\n0x0000000000000000000000000000000000000000,1\n{},0.5\n",
+            ALICE.to_lowercase(),
+            BOB,
+            ALICE,
+            "bob"
+        );
+        let err = parse_batch(&csv, &book(), 421614).unwrap_err();
+        let lines: Vec<&str> = err.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", err);
+        assert!(lines[0].starts_with("line 1:") && lines[0].contains("no EIP-55 checksum"));
+        assert!(lines[1].starts_with("line 3:") && lines[1].contains("expected address,amount"));
+        assert!(lines[2].starts_with("line 4:"));
+        assert!(lines[3].contains("duplicate payout") && lines[3].contains("first on line 2"));
+
+        let rows = parse_batch(&format!("{},0\n", ALICE), &book(), 421614).unwrap();
+        assert!(parse_amounts(&rows, &HashMap::new()).unwrap_err().contains("amount is zero"));
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let csv = format!("{},0.01\n", ALICE);
+        let sender = H160::from_str(BOB).unwrap();
+        let rows = parse_batch(&csv, &book(), 421614).unwrap();
+        let mut state = BatchState::new(&csv, sender, &rows);
+        state.payouts[0].status = PayoutStatus::Sent { nonce: U256::from(3), tx_hash: H256::repeat_byte(0xab) };
+
+        let path = std::env::temp_dir().join(format!("eth-transfer-batch-{}.csv", std::process::id()));
+        let path = state_path(&path);
+        state.save(&path).unwrap();
+        let loaded = BatchState::load(&path).unwrap().unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.in_flight_nonces(), vec![U256::from(3)]);
+        assert!(loaded.check_matches(&csv, sender).is_ok());
+        assert!(loaded.check_matches(&format!("{},0.02\n", ALICE), sender).is_err());
+        assert!(loaded.check_matches(&csv, H160::from_str(ALICE).unwrap()).is_err());
+        fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_reconcile() {
+        let (provider, mock) = Provider::mocked();
+        let csv = format!("{},0.01\n{},0.02\n", ALICE, BOB);
+        let sender = H160::repeat_byte(0x11);
+        let rows = parse_batch(&csv, &book(), 421614).unwrap();
+        let mut state = BatchState::new(&csv, sender, &rows);
+        state.payouts[0].status = PayoutStatus::Sent { nonce: U256::from(4), tx_hash: H256::repeat_byte(1) };
+        state.payouts[1].status = PayoutStatus::Sent { nonce: U256::from(5), tx_hash: H256::repeat_byte(2) };
+
+        // Served last-in first-out: nonce count 5, first payout mined, second
+        // never reached the node
+        mock.push(serde_json::Value::Null).unwrap();
+        mock.push(serde_json::Value::Null).unwrap();
+        mock.push::<TransactionReceipt, _>(TransactionReceipt {
+            block_number: Some(U64::from(77)),
+            status: Some(U64::from(1)),
+            ..Default::default()
+        })
+        .unwrap();
+        mock.push::<U256, _>(U256::from(5)).unwrap();
+
+        reconcile(&provider, &mut state, sender).await.unwrap();
+        assert_eq!(
+            state.payouts[0].status,
+            PayoutStatus::Confirmed { tx_hash: H256::repeat_byte(1), block: U64::from(77) }
+        );
+        assert_eq!(state.payouts[1].status, PayoutStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_retry_failed() {
+        let (provider, mock) = Provider::mocked();
+        let csv = format!("{},0.01\n{},0.02\n{},0.03\n", ALICE, BOB, TOKEN);
+        let rows = parse_batch(&csv, &book(), 421614).unwrap();
+        let mut state = BatchState::new(&csv, H160::repeat_byte(0x11), &rows);
+        state.payouts[0].status = PayoutStatus::Failed { error: "insufficient funds".to_string(), tx_hash: None };
+        state.payouts[1].status =
+            PayoutStatus::Failed { error: "transaction reverted".to_string(), tx_hash: Some(H256::repeat_byte(2)) };
+        state.payouts[2].status = PayoutStatus::Failed {
+            error: "nonce 5 was used by another transaction; check the account before retrying".to_string(),
+            tx_hash: Some(H256::repeat_byte(3)),
+        };
+
+        // Served last-in first-out: the second payout reverted in a block,
+        // the third never mined
+        mock.push(serde_json::Value::Null).unwrap();
+        mock.push::<TransactionReceipt, _>(TransactionReceipt {
+            block_number: Some(U64::from(9)),
+            status: Some(U64::zero()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(retry_failed(&provider, &mut state, false).await.unwrap(), vec![2]);
+        assert_eq!(state.payouts[0].status, PayoutStatus::Pending);
+        assert_eq!(state.payouts[1].status, PayoutStatus::Pending);
+        assert!(matches!(state.payouts[2].status, PayoutStatus::Failed { .. }));
+
+        // Only the explicit flag resends a payout whose nonce was reused
+        mock.push(serde_json::Value::Null).unwrap();
+        assert!(retry_failed(&provider, &mut state, true).await.unwrap().is_empty());
+        assert_eq!(state.payouts[2].status, PayoutStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_failed_broadcast_stays_sent() {
+        let (provider, mock) = Provider::mocked();
+        let wallet = test_wallet();
+        let sender = wallet.address();
+        let manager = NonceManager::new(SignerMiddleware::new(provider.clone(), wallet.clone()), sender);
+        let csv = format!("{},0.01\n", ALICE);
+        let rows = parse_batch(&csv, &book(), 421614).unwrap();
+        let mut state = BatchState::new(&csv, sender, &rows);
+        let path = state_path(&std::env::temp_dir().join(format!("eth-transfer-batch-send-{}.csv", std::process::id())));
+        let tx: TypedTransaction = TransactionRequest::new()
+            .from(sender)
+            .to(H160::from_str(ALICE).unwrap())
+            .value(1000)
+            .gas(21_000)
+            .gas_price(100_000_000)
+            .chain_id(421614u64)
+            .into();
+
+        // Served last-in first-out: pending count 4, then the broadcast times
+        // out although the node may have the transaction
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32000,
+            message: "request timed out".to_string(),
+            data: None,
+        }));
+        mock.push::<U256, _>(U256::from(4)).unwrap();
+        assert!(send_payout(&manager, tx.clone(), &mut state, 0, &path).await.is_err());
+
+        let mut signed = tx;
+        signed.set_nonce(4);
+        let tx_hash = H256::from(keccak256(signed.rlp_signed(&wallet.sign_transaction_sync(&signed).unwrap())));
+        let sent = PayoutStatus::Sent { nonce: U256::from(4), tx_hash };
+        assert_eq!(state.payouts[0].status, sent);
+        assert_eq!(BatchState::load(&path).unwrap().unwrap().payouts[0].status, sent);
+
+        // The node has it pending, so it is neither failed nor resent
+        mock.push::<Transaction, _>(Transaction { hash: tx_hash, ..Default::default() }).unwrap();
+        mock.push(serde_json::Value::Null).unwrap();
+        mock.push::<U256, _>(U256::from(4)).unwrap();
+        reconcile(&provider, &mut state, sender).await.unwrap();
+        assert_eq!(state.payouts[0].status, sent);
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,71 @@
+use crate::accounts::{registry_path, AccountEntry, AccountsRegistry, SignerSource};
+use crate::transfer::validate_address;
+use super::option_value;
+
+use arb_common::address::checksummed;
+use std::path::PathBuf;
+
+/// Named signer accounts
+///
+/// Usage:
+/// * `eth-transfer accounts list`
+/// * `eth-transfer accounts add <NAME> keystore <PATH> [--password-file FILE]`
+/// * `eth-transfer accounts add <NAME> mnemonic <INDEX>`
+/// * `eth-transfer accounts add <NAME> env`
+/// * `eth-transfer accounts add <NAME> remote <URL> --address ADDR`
+///
+/// The registry lives in `ACCOUNTS_FILE` (default `accounts.json`).
+pub fn run_accounts(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer accounts <list | add <NAME> <keystore PATH | mnemonic INDEX | env | remote URL>> \
+                 [--address ADDR] [--password-file FILE]";
+    let path = registry_path();
+    let mut registry = AccountsRegistry::load(&path)?;
+
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            if registry.accounts.is_empty() {
+                println!("No accounts in {}", path.display());
+            }
+            for entry in &registry.accounts {
+                let address = entry
+                    .address
+                    .map(checksummed)
+                    .unwrap_or_else(|| "(address not recorded)".to_string());
+                println!("   {:<12} {}  {}", entry.name, address, entry.signer);
+            }
+        }
+        Some("add") => {
+            let name = args.get(1).ok_or(usage)?.clone();
+            let value = args.get(3).filter(|a| !a.starts_with("--")).cloned();
+            let signer = match args.get(2).map(String::as_str) {
+                Some("keystore") => SignerSource::Keystore {
+                    path: PathBuf::from(value.ok_or(usage)?),
+                    password_file: option_value(args, "--password-file").map(PathBuf::from),
+                },
+                Some("mnemonic") => SignerSource::Mnemonic {
+                    index: value.ok_or(usage)?.parse().map_err(|_| "INDEX must be a number")?,
+                },
+                Some("env") => SignerSource::Env,
+                Some("remote") => SignerSource::Remote { url: value.ok_or(usage)? },
+                _ => return Err(usage.into()),
+            };
+            let address = match option_value(args, "--address") {
+                Some(address) => {
+                    let parsed = validate_address(&address)?;
+                    for warning in &parsed.warnings {
+                        println!("⚠️  {}", warning);
+                    }
+                    Some(parsed.address)
+                }
+                None => None,
+            };
+
+            registry.add(AccountEntry { name: name.clone(), address, signer })?;
+            registry.save(&path)?;
+            println!("✅ Added account '{}' to {}", name, path.display());
+        }
+        _ => return Err(usage.into()),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use super::option_value;
+
+use arb_common::address_book::{address_book_path, parse_checksummed, AddressBook, AddressEntry};
+
+/// Address book of labelled recipients
+///
+/// Usage:
+/// * `eth-transfer addressbook list`
+/// * `eth-transfer addressbook add <LABEL> <ADDRESS> [--note TEXT] [--chains ID,ID]`
+///
+/// The book lives in `ADDRESS_BOOK` (default `address_book.json`; a `.toml`
+/// path is read and written as TOML). Addresses must be EIP-55 checksummed.
+pub fn run_address_book(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer addressbook <list | add <LABEL> <ADDRESS>> [--note TEXT] [--chains ID,ID]";
+    let path = address_book_path();
+    let mut book = AddressBook::load(&path)?;
+
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            if book.entries.is_empty() {
+                println!("No entries in {}", path.display());
+            }
+            for entry in &book.entries {
+                let chains = if entry.chains.is_empty() {
+                    "any chain".to_string()
+                } else {
+                    format!("chains {:?}", entry.chains)
+                };
+                println!("   {:<12} {}  ({})", entry.label, book.display(entry.address), chains);
+                if let Some(notes) = &entry.notes {
+                    println!("                {}", notes);
+                }
+            }
+        }
+        Some("add") => {
+            let label = args.get(1).ok_or(usage)?.clone();
+            let address = parse_checksummed(args.get(2).ok_or(usage)?)?;
+            let chains = option_value(args, "--chains")
+                .map(|list| {
+                    list.split(',')
+                        .map(|c| c.trim().parse::<u64>())
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()
+                .map_err(|_| "--chains must be a comma-separated list of chain IDs")?
+                .unwrap_or_default();
+
+            book.add(AddressEntry { label: label.clone(), address, notes: option_value(args, "--note"), chains })?;
+            book.save(&path)?;
+            println!("✅ Added '{}' to {}", label, path.display());
+        }
+        _ => return Err(usage.into()),
+    }
+
+    Ok(())
+}
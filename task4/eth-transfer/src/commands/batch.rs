@@ -0,0 +1,216 @@
+use crate::accounts::resolve_signer;
+use crate::batch::{
+    parse_amounts,
+    parse_batch,
+    reconcile,
+    retry_failed,
+    send_payout,
+    state_path as batch_state_path,
+    totals as batch_totals,
+    BatchState,
+    PayoutStatus,
+};
+use crate::token::{encode_token_write, get_token_balance, token_abi, TokenWrite};
+use crate::tracker::{wait_for_confirmations, TrackerConfig, TxStatus};
+use crate::transfer::{get_balance, get_chain_id, get_gas_price, nonce_manager, prepare_call, prepare_transfer};
+use super::{option_value, confirm_send, tracker_provider, load_token_metadata};
+
+use arb_common::address_book::AddressBook;
+use arb_common::amount::Amount;
+use ethers::signers::Signer;
+use ethers::types::{H160, U256, U64};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Pay many recipients from a CSV file
+///
+/// Usage: `eth-transfer batch <CSV> [--from NAME] [--state FILE] [--abi FILE] [--retry-failed] [--retry-nonce-reused] [--yes] [--dry-run]`
+///
+/// Rows are `address,amount[,token]` (see `batch::parse_batch`). Everything
+/// is validated up front: checksums, duplicates, and totals against the
+/// sender's balances. Transactions are sent back to back with managed nonces
+/// and recorded in the state file (default `<CSV>.state.json`) before they
+/// are broadcast; running the same command again resumes the batch without
+/// repeating payouts. `--retry-failed` sends failed and reverted payouts
+/// again, but not ones whose nonce another transaction took, since that
+/// transaction may have paid them; `--retry-nonce-reused` resends those too
+/// (see `batch::retry_failed`). The tracker options of `run_tx` apply while
+/// waiting for confirmations.
+pub async fn run_batch(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer batch <CSV> [--from NAME] [--state FILE] [--abi FILE] [--retry-failed] [--retry-nonce-reused] [--yes] [--dry-run]";
+    let csv_path = PathBuf::from(args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?);
+    let csv = std::fs::read_to_string(&csv_path).map_err(|_| format!("Failed to read {}", csv_path.display()))?;
+    let book = AddressBook::load_default()?;
+    let chain_id = get_chain_id().await?;
+    let signer = resolve_signer(option_value(args, "--from").as_deref())?.with_chain_id(chain_id);
+    let sender = signer.address();
+
+    // Validate every row before touching the network for balances
+    let rows = parse_batch(&csv, &book, chain_id).map_err(|e| format!("Batch file has errors:\n{}", e))?;
+    let (symbols, decimals) = load_token_metadata(&rows).await?;
+    let amounts = parse_amounts(&rows, &decimals).map_err(|e| format!("Batch file has errors:\n{}", e))?;
+    batch_totals(&rows, &amounts).map_err(|e| format!("Batch file has errors:\n{}", e))?;
+    let symbol = |token: Option<H160>| token.map_or("ETH".to_string(), |t| symbols[&t].clone());
+
+    let state_path = option_value(args, "--state").map(PathBuf::from).unwrap_or_else(|| batch_state_path(&csv_path));
+    let mut state = match BatchState::load(&state_path)? {
+        Some(state) => {
+            state.check_matches(&csv, sender)?;
+            println!("📂 Resuming batch from {}", state_path.display());
+            state
+        }
+        None => BatchState::new(&csv, sender, &rows),
+    };
+    let provider = tracker_provider()?;
+    let nonce_reused = args.iter().any(|a| a == "--retry-nonce-reused");
+    if nonce_reused || args.iter().any(|a| a == "--retry-failed") {
+        for i in retry_failed(&provider, &mut state, nonce_reused).await? {
+            println!(
+                "⚠️  line {:>3}: not retried, its nonce was used by another transaction that may have paid it; check the account, then pass --retry-nonce-reused",
+                state.payouts[i].line
+            );
+        }
+    }
+    reconcile(&provider, &mut state, sender).await?;
+
+    println!("=========================================================");
+    println!("  Batch Payout: {}", csv_path.display());
+    println!("=========================================================");
+    println!("From: {}", book.display(sender));
+    println!("---------------------------------------------------------");
+    for (row, payout) in rows.iter().zip(&state.payouts) {
+        let status = match &payout.status {
+            PayoutStatus::Pending => "pending".to_string(),
+            PayoutStatus::Sent { tx_hash, .. } => format!("sent {:#x}", tx_hash),
+            PayoutStatus::Confirmed { block, .. } => format!("confirmed in block {}", block),
+            PayoutStatus::Failed { error, .. } => format!("failed: {}", error),
+        };
+        println!("line {:>3}  {}  {} {}  [{}]", row.line, book.display(row.recipient), row.amount, symbol(row.token), status);
+    }
+    println!("---------------------------------------------------------");
+
+    // Only payouts still to be sent count against the balances
+    let pending: Vec<usize> = (0..rows.len())
+        .filter(|i| state.payouts[*i].status == PayoutStatus::Pending)
+        .collect();
+    let pending_rows: Vec<_> = pending.iter().map(|i| rows[*i].clone()).collect();
+    let pending_amounts: Vec<_> = pending.iter().map(|i| amounts[*i]).collect();
+    let fee_per_payout = {
+        let gas_price = get_gas_price().await?;
+        gas_price
+            .checked_add(gas_price / 5)
+            .and_then(|price| price.checked_mul(U256::from(100_000)))
+            .ok_or("Estimated fee per payout overflows 256 bits")?
+    };
+    let eth_balance = get_balance(sender).await?.raw();
+    let mut problems = Vec::new();
+    let pending_totals = batch_totals(&pending_rows, &pending_amounts)?;
+    for (&token, &total) in &pending_totals {
+        match token {
+            Some(token) => {
+                let balance = get_token_balance(token, sender).await?;
+                let total = Amount::new(total, decimals[&token]);
+                println!("Total {}: {} (balance {})", symbol(Some(token)), total, Amount::new(balance, decimals[&token]));
+                if balance < total.raw() {
+                    problems.push(format!("insufficient {} balance", symbol(Some(token))));
+                }
+            }
+            None => println!("Total ETH: {}", Amount::from_wei(total)),
+        }
+    }
+    let eth_needed = fee_per_payout
+        .checked_mul(U256::from(pending.len()))
+        .and_then(|fees| fees.checked_add(pending_totals.get(&None).copied().unwrap_or_default()))
+        .ok_or("ETH needed for the payouts and fees overflows 256 bits")?;
+    println!(
+        "ETH needed incl. estimated fees: {} (balance {})",
+        Amount::from_wei(eth_needed),
+        Amount::from_wei(eth_balance)
+    );
+    println!("---------------------------------------------------------");
+    println!();
+    if eth_balance < eth_needed {
+        problems.push("insufficient ETH balance for the payouts and fees".to_string());
+    }
+    if !problems.is_empty() {
+        return Err(format!("Batch cannot be paid: {}", problems.join(", ")).into());
+    }
+
+    if args.iter().any(|a| a == "--dry-run") {
+        println!("🧪 Dry run: {} payout(s) would be sent, nothing was broadcast.", pending.len());
+        return Ok(());
+    }
+    if !pending.is_empty() {
+        println!("⚠️  {} payout(s) will be sent from {}.", pending.len(), book.display(sender));
+        if !confirm_send(args)? {
+            return Ok(());
+        }
+        state.save(&state_path)?;
+
+        let abi = token_abi(option_value(args, "--abi").as_deref())?;
+        let manager = nonce_manager(&signer)?;
+        manager.record_submitted(&state.in_flight_nonces());
+        for i in pending {
+            let row = &rows[i];
+            let prepared = match row.token {
+                Some(token) => {
+                    let data = encode_token_write(&abi, &TokenWrite::Transfer { to: row.recipient }, amounts[i].raw())?;
+                    prepare_call(&signer, token, U256::zero(), Some(data), None, Some(abi.clone())).await
+                }
+                None => prepare_transfer(&signer, row.recipient, &row.amount).await,
+            };
+            let result = match prepared {
+                Ok(prepared) => send_payout(&manager, prepared.tx.clone(), &mut state, i, &state_path).await,
+                Err(e) => {
+                    state.payouts[i].status = PayoutStatus::Failed { error: e.to_string(), tx_hash: None };
+                    state.save(&state_path)?;
+                    Err(e)
+                }
+            };
+            match (result, &state.payouts[i].status) {
+                (Ok((nonce, tx_hash)), _) => println!("📤 line {:>3}: nonce {} {:#x}", row.line, nonce, tx_hash),
+                (Err(e), PayoutStatus::Sent { tx_hash, .. }) => println!(
+                    "⚠️  line {:>3}: {} (signed as {:#x}; it may still reach the chain, so it is checked before any resend)",
+                    row.line, e, tx_hash
+                ),
+                (Err(e), _) => println!("❌ line {:>3}: {}", row.line, e),
+            }
+        }
+        println!();
+    }
+
+    // Wait for everything in flight, including payouts from an earlier run
+    let config = TrackerConfig::from_args(args)?;
+    for i in 0..state.payouts.len() {
+        let PayoutStatus::Sent { tx_hash, .. } = state.payouts[i].status.clone() else {
+            continue;
+        };
+        print!("⏳ line {:>3}: ", state.payouts[i].line);
+        std::io::stdout().flush().ok();
+        let status = wait_for_confirmations(&provider, tx_hash, &config, |_| {}).await;
+        let (update, message) = match status {
+            TxStatus::Confirmed { receipt, .. } if receipt.status == Some(U64::from(1)) => {
+                let block = receipt.block_number.unwrap_or_default();
+                (Some(PayoutStatus::Confirmed { tx_hash, block }), format!("✅ confirmed in block {}", block))
+            }
+            TxStatus::Confirmed { .. } => (
+                Some(PayoutStatus::Failed { error: "transaction reverted".to_string(), tx_hash: Some(tx_hash) }),
+                "❌ reverted".to_string(),
+            ),
+            // Left as sent; the next run reconciles it before doing anything else
+            TxStatus::Replaced { nonce } => (None, format!("🔁 nonce {} was used by another transaction", nonce)),
+            TxStatus::Dropped => (None, "🗑️  dropped by the node, run the batch again to resend".to_string()),
+            TxStatus::TimedOut { .. } => (None, "⏱️  not confirmed yet, run the batch again to resume".to_string()),
+        };
+        println!("{}", message);
+        if let Some(update) = update {
+            state.payouts[i].status = update;
+            state.save(&state_path)?;
+        }
+    }
+
+    let summary: Vec<String> = state.summary().iter().map(|(k, v)| format!("{} {}", v, k)).collect();
+    println!();
+    println!("📊 Batch status: {} (state in {})", summary.join(", "), state_path.display());
+    Ok(())
+}
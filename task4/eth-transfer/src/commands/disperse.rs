@@ -0,0 +1,229 @@
+use crate::accounts::resolve_signer;
+use crate::batch::{parse_amounts, parse_batch};
+use crate::disperse::{
+    disperse_abi,
+    disperse_address,
+    encode_disperse,
+    estimate_individual,
+    estimate_token_disperse,
+    is_deployed,
+    load_init_code,
+    plan as disperse_plan,
+};
+use crate::token::{encode_token_write, erc20_abi, get_token_allowance, get_token_balance, TokenWrite};
+use crate::tracker::TrackerConfig;
+use crate::transfer::{
+    get_balance,
+    get_chain_id,
+    get_gas_price,
+    nonce_manager,
+    prepare_call,
+    prepare_deploy,
+    send_prepared,
+    send_transfer,
+    simulate_transfer,
+};
+use super::{option_value, confirm_send, dry_run, track_transaction, load_token_metadata};
+
+use arb_common::address_book::AddressBook;
+use arb_common::address::checksummed;
+use arb_common::amount::Amount;
+use ethers::signers::Signer;
+use ethers::types::{H256, U256, U64};
+use ethers::utils::get_contract_address;
+use std::env;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Print gas for separate transfers next to one disperse transaction
+///
+/// `estimated` marks a disperse figure from `estimate_token_disperse`
+/// rather than a simulation.
+fn print_gas_comparison(individual: U256, disperse: U256, approve: U256, gas_price: U256, estimated: bool) {
+    let batched = disperse + approve;
+    println!("⛽ Gas comparison at {} Gwei:", Amount::wei_as_gwei(gas_price));
+    if estimated {
+        println!("   (disperse gas estimated from the separate transfers; it is simulated once the approval is mined)");
+    }
+    println!("   Separate transfers:  {:>9} gas  ({} ETH)", individual, Amount::from_wei(individual * gas_price));
+    if approve.is_zero() {
+        println!("   Disperse:            {:>9} gas  ({} ETH)", batched, Amount::from_wei(batched * gas_price));
+    } else {
+        println!(
+            "   Approve + disperse:  {:>9} gas  ({} ETH)",
+            batched,
+            Amount::from_wei(batched * gas_price)
+        );
+    }
+    if batched < individual {
+        let saved = individual - batched;
+        println!("   Saves {} gas ({}%)", saved, (saved * U256::from(100) / individual).as_u64());
+    } else {
+        println!("   ⚠️  Disperse costs {} gas more than separate transfers", batched - individual);
+    }
+    println!();
+}
+
+/// Pay many recipients in one transaction through a disperse contract
+///
+/// Usage:
+/// * `eth-transfer disperse <CSV> [--from NAME] [--contract ADDR] [--yes] [--dry-run]`
+/// * `eth-transfer disperse deploy [--artifact FILE] [--from NAME] [--yes] [--dry-run]`
+///
+/// The CSV has the batch format and must pay a single asset. The contract
+/// is `--contract`, else `DISPERSE_ADDRESS`, else the disperse.app
+/// deployment; `deploy` creates one from a compiled `contracts/Disperse.sol`
+/// artifact (`--artifact` or `DISPERSE_ARTIFACT`). Token payouts approve
+/// the contract first when the allowance is short. The gas of one disperse
+/// transaction is compared with separate transfers before anything is sent,
+/// the approval included.
+pub async fn run_disperse(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer disperse <CSV|deploy> [--from NAME] [--contract ADDR] [--artifact FILE] [--yes] [--dry-run]";
+    let target = args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?;
+    let book = AddressBook::load_default()?;
+    let chain_id = get_chain_id().await?;
+    let signer = resolve_signer(option_value(args, "--from").as_deref())?.with_chain_id(chain_id);
+    let sender = signer.address();
+    let dry = args.iter().any(|a| a == "--dry-run");
+
+    if target == "deploy" {
+        let artifact = option_value(args, "--artifact")
+            .or_else(|| env::var("DISPERSE_ARTIFACT").ok())
+            .ok_or("No artifact given: pass --artifact FILE or set DISPERSE_ARTIFACT")?;
+        let prepared = prepare_deploy(&signer, load_init_code(Path::new(&artifact))?).await?;
+        println!("⚠️  Ready to deploy the disperse contract:");
+        println!("   From:       {}", book.display(sender));
+        println!("   Gas Limit:  {}", prepared.gas_limit);
+        println!("   Max Fee:    {} ETH", Amount::from_wei(prepared.max_fee()));
+        println!();
+        if dry {
+            return dry_run(&prepared).await;
+        }
+        if !confirm_send(args)? {
+            return Ok(());
+        }
+        let manager = nonce_manager(&prepared.signer)?;
+        let (nonce, tx_hash) = send_prepared(&manager, &prepared).await?;
+        let address = get_contract_address(sender, nonce);
+        println!("✅ Deployment submitted: {:#x}", tx_hash);
+        println!("   Contract address: {}", checksummed(address));
+        println!();
+        let config = TrackerConfig::from_args(args)?;
+        if let Some(receipt) = track_transaction(tx_hash, &config).await? {
+            if receipt.status == Some(U64::from(1)) {
+                println!("🎉 Disperse deployed. Use it with --contract {0} or DISPERSE_ADDRESS={0}", checksummed(address));
+            }
+        }
+        return Ok(());
+    }
+
+    let csv = std::fs::read_to_string(target).map_err(|_| format!("Failed to read {}", target))?;
+    let rows = parse_batch(&csv, &book, chain_id).map_err(|e| format!("Payout file has errors:\n{}", e))?;
+    let (symbols, decimals) = load_token_metadata(&rows).await?;
+    let amounts = parse_amounts(&rows, &decimals).map_err(|e| format!("Payout file has errors:\n{}", e))?;
+    let plan = disperse_plan(&rows, &amounts)?;
+    let contract = disperse_address(option_value(args, "--contract").as_deref())?;
+    if !is_deployed(contract).await? {
+        return Err(format!(
+            "No disperse contract at {} on chain {}; deploy one with `eth-transfer disperse deploy --artifact FILE` \
+             and pass --contract or set DISPERSE_ADDRESS",
+            checksummed(contract),
+            chain_id
+        )
+        .into());
+    }
+
+    let (symbol, unit) = match plan.token {
+        Some(token) => (symbols[&token].clone(), decimals[&token]),
+        None => ("ETH".to_string(), 18),
+    };
+    println!("=========================================================");
+    println!("  Disperse Payout: {} recipient(s)", plan.recipients.len());
+    println!("=========================================================");
+    println!("From:     {}", book.display(sender));
+    println!("Contract: {}", checksummed(contract));
+    println!("---------------------------------------------------------");
+    for (recipient, amount) in plan.recipients.iter().zip(&amounts) {
+        println!("   {}  {} {}", book.display(*recipient), amount, symbol);
+    }
+    println!("---------------------------------------------------------");
+    println!("Total: {} {}", Amount::new(plan.total, unit), symbol);
+    println!();
+
+    let gas_price = get_gas_price().await?;
+    let individual = estimate_individual(sender, &plan).await?;
+    let mut approve_gas = U256::zero();
+    if let Some(token) = plan.token {
+        let balance = get_token_balance(token, sender).await?;
+        if balance < plan.total {
+            return Err(format!("Insufficient {} balance: {}", symbol, Amount::new(balance, unit)).into());
+        }
+        let allowance = get_token_allowance(token, sender, contract).await?;
+        if allowance < plan.total {
+            let abi = erc20_abi();
+            let data = encode_token_write(&abi, &TokenWrite::Approve { spender: contract }, plan.total)?;
+            let approval = prepare_call(&signer, token, U256::zero(), Some(data), None, Some(abi)).await?;
+            approve_gas = simulate_transfer(&approval).await?;
+            println!(
+                "🔓 The contract may move {} {}; an approval for {} {} is needed first ({} gas).",
+                Amount::new(allowance, unit),
+                symbol,
+                Amount::new(plan.total, unit),
+                symbol,
+                approve_gas
+            );
+            println!();
+            print_gas_comparison(
+                individual,
+                estimate_token_disperse(individual, plan.recipients.len()),
+                approve_gas,
+                gas_price,
+                true,
+            );
+            if dry {
+                println!("🧪 Dry run: the disperse call can only be simulated once the approval is mined.");
+                return dry_run(&approval).await;
+            }
+            if !confirm_send(args)? {
+                return Ok(());
+            }
+            let tx_hash = send_transfer(&approval).await?;
+            let config = TrackerConfig::from_args(args)?;
+            match track_transaction(H256::from_str(&tx_hash)?, &config).await? {
+                Some(receipt) if receipt.status == Some(U64::from(1)) => {}
+                _ => return Err("Approval was not confirmed; nothing was dispersed".into()),
+            }
+        }
+    }
+
+    let value = if plan.token.is_none() { plan.total } else { U256::zero() };
+    println!("🔎 Simulating the disperse call against pending state...");
+    let prepared = prepare_call(&signer, contract, value, Some(encode_disperse(&plan)?), None, Some(disperse_abi())).await?;
+    let disperse_gas = simulate_transfer(&prepared).await?;
+    println!("   ✅ Call would succeed\n");
+    print_gas_comparison(individual, disperse_gas, approve_gas, gas_price, false);
+
+    let eth_balance = get_balance(sender).await?.raw();
+    if eth_balance < prepared.total_cost() {
+        return Err(format!(
+            "Insufficient ETH: need {} ETH including the fee, have {} ETH",
+            Amount::from_wei(prepared.total_cost()),
+            Amount::from_wei(eth_balance)
+        )
+        .into());
+    }
+    if dry {
+        return dry_run(&prepared).await;
+    }
+    println!("⚠️  Ready to pay {} recipient(s) in one transaction.", plan.recipients.len());
+    if !confirm_send(args)? {
+        return Ok(());
+    }
+    let tx_hash = send_transfer(&prepared).await?;
+    println!("✅ Disperse submitted: {}", tx_hash);
+    println!("   https://sepolia.arbiscan.io/tx/{}", tx_hash);
+    println!();
+    let config = TrackerConfig::from_args(args)?;
+    track_transaction(H256::from_str(&tx_hash)?, &config).await?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::hd::{
+    derivation_path,
+    derive_addresses,
+    generate_mnemonic,
+    read_mnemonic,
+    read_mnemonic_passphrase,
+    validate_mnemonic,
+};
+use crate::transfer::get_balance;
+use super::option_value;
+
+use arb_common::address::checksummed;
+
+/// HD wallet accounts derived from a BIP-39 mnemonic
+///
+/// Usage:
+/// * `eth-transfer hd new [--words 12|24]` - generate a new mnemonic
+/// * `eth-transfer hd validate` - check the mnemonic's words and checksum
+/// * `eth-transfer hd list [COUNT]` - derived addresses with balances
+///
+/// The mnemonic is read from `MNEMONIC_PATH` or prompted for. To send from a
+/// derived account, set `MNEMONIC_PATH` and `MNEMONIC_INDEX`.
+pub async fn run_hd(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer hd <new [--words N] | validate | list [COUNT]>";
+
+    match args.first().map(String::as_str) {
+        Some("new") => {
+            let words = option_value(args, "--words")
+                .map(|w| w.parse::<usize>())
+                .transpose()
+                .map_err(|_| "--words must be a number")?
+                .unwrap_or(12);
+            let phrase = generate_mnemonic(words)?;
+            println!("🔑 New {}-word mnemonic (write it down and keep it offline):\n", words);
+            println!("   {}\n", phrase.as_str());
+            println!("   First address: {}", checksummed(derive_addresses(&phrase, 1, None)?[0]));
+        }
+        Some("validate") => {
+            let phrase = read_mnemonic()?;
+            validate_mnemonic(&phrase)?;
+            println!("✅ Mnemonic is valid ({} words)", phrase.split(' ').count());
+        }
+        Some("list") => {
+            let count = args
+                .get(1)
+                .map(|c| c.parse::<u32>())
+                .transpose()
+                .map_err(|_| "COUNT must be a number")?
+                .unwrap_or(5);
+            let phrase = read_mnemonic()?;
+            let passphrase = read_mnemonic_passphrase()?;
+            let addresses = derive_addresses(&phrase, count, passphrase.as_ref().map(|p| p.as_str()))?;
+
+            println!("📋 Derived accounts:");
+            for (index, address) in addresses.into_iter().enumerate() {
+                let balance = get_balance(address).await?;
+                println!("   [{}] {}  {}  {} ETH", index, derivation_path(index as u32), checksummed(address), balance);
+            }
+        }
+        _ => return Err(usage.into()),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use crate::keystore::{change_password, create_keystore, decrypt_keystore, PassphraseSource};
+use super::option_value;
+
+use arb_common::address::checksummed;
+use ethers::signers::Signer;
+use std::path::{Path, PathBuf};
+
+/// Keystore management
+///
+/// Usage:
+/// * `eth-transfer keystore new [DIR] [--name NAME]`
+/// * `eth-transfer keystore address <PATH>`
+/// * `eth-transfer keystore change-password <PATH> [--new-password-file FILE]`
+///
+/// Passphrases are prompted for, or read from `--password-file` /
+/// `KEYSTORE_PASSWORD_FILE`.
+pub fn run_keystore(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer keystore <new [DIR] | address <PATH> | change-password <PATH>> \
+                 [--name NAME] [--password-file FILE] [--new-password-file FILE]";
+    let source = match option_value(args, "--password-file") {
+        Some(path) => PassphraseSource::File(PathBuf::from(path)),
+        None => PassphraseSource::from_env(),
+    };
+    let positional = |i: usize| args.get(i).filter(|a| !a.starts_with("--")).cloned();
+
+    match args.first().map(String::as_str) {
+        Some("new") => {
+            let dir = positional(1).unwrap_or_else(|| "keystore".to_string());
+            let name = option_value(args, "--name");
+            let (address, path) = create_keystore(Path::new(&dir), name.as_deref(), &source)?;
+            println!("✅ Created keystore");
+            println!("   Address: {}", checksummed(address));
+            println!("   File:    {}", path.display());
+            println!();
+            println!("   Set KEYSTORE_PATH={} to sign with it.", path.display());
+        }
+        Some("address") => {
+            let path = positional(1).ok_or(usage)?;
+            let wallet = decrypt_keystore(Path::new(&path), &source)?;
+            println!("Address: {}", checksummed(wallet.address()));
+        }
+        Some("change-password") => {
+            let path = positional(1).ok_or(usage)?;
+            let new_source = match option_value(args, "--new-password-file") {
+                Some(path) => PassphraseSource::File(PathBuf::from(path)),
+                None => PassphraseSource::Prompt,
+            };
+            let address = change_password(Path::new(&path), &source, &new_source)?;
+            println!("✅ Passphrase changed for {}", checksummed(address));
+        }
+        _ => return Err(usage.into()),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use crate::accounts::{registry_path, resolve_signer, AccountsRegistry};
+use crate::migrate::{migration_tokens, MigrationReport, MigrationStep, RemainingBalance, StepStatus};
+use crate::token::{encode_token_write, erc20_abi, get_token_balance, get_token_metadata, TokenWrite};
+use crate::tracker::TrackerConfig;
+use crate::transfer::{
+    check_recipient,
+    get_balance,
+    get_chain_id,
+    get_gas_price,
+    prepare_call,
+    prepare_sweep,
+    SWEEP_HEADROOM_PERCENT,
+    send_transfer,
+    PreparedTransfer,
+};
+use super::{option_value, confirm_send, track_transaction};
+
+use arb_common::address_book::AddressBook;
+use arb_common::address::checksummed;
+use arb_common::amount::Amount;
+use ethers::signers::Signer;
+use ethers::types::{H256, U256, U64};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Send a migration transaction and wait for it
+///
+/// # Returns
+/// * `(Option<H256>, StepStatus)` - Hash if it was broadcast, and the report status
+async fn send_migration_step(prepared: &PreparedTransfer, config: &TrackerConfig) -> (Option<H256>, StepStatus) {
+    let tx_hash = match send_transfer(prepared).await.map(|h| H256::from_str(&h)) {
+        Ok(Ok(hash)) => hash,
+        Ok(Err(e)) => return (None, StepStatus::Failed { error: e.to_string() }),
+        Err(e) => return (None, StepStatus::Failed { error: e.to_string() }),
+    };
+    println!("   Submitted: {:#x}", tx_hash);
+    println!("   https://sepolia.arbiscan.io/tx/{:#x}", tx_hash);
+    let status = match track_transaction(tx_hash, config).await {
+        Ok(Some(receipt)) if receipt.status == Some(U64::from(1)) => StepStatus::Confirmed,
+        Ok(Some(_)) => StepStatus::Failed { error: "transaction reverted".to_string() },
+        Ok(None) => StepStatus::Failed { error: "not confirmed (replaced, dropped or timed out)".to_string() },
+        Err(e) => StepStatus::Failed { error: e.to_string() },
+    };
+    (Some(tx_hash), status)
+}
+
+/// Move every asset from one account to another (key rotation)
+///
+/// Usage: `eth-transfer migrate [--from NAME] --to ACCOUNT|LABEL|ADDRESS [--tokens A,B] [--headroom PERCENT] [--report FILE] [--yes] [--dry-run]`
+///
+/// `--to` is an account from the registry (it needs a recorded address),
+/// an address book label, or a raw address. The tokens checked are
+/// `--tokens` or `MIGRATE_TOKENS`. Every token balance is transferred
+/// first, each waited for before the next, while the old account still has
+/// ETH for gas; then the ETH is swept (see `prepare_sweep`). If a token
+/// transfer fails the sweep is skipped so the ETH is still there to retry.
+/// Afterwards the old account's balances are read again and a JSON report
+/// (default `migration-<FROM>-<TIME>.json`) records every step. The old
+/// account counts as empty when no token balance is left and the ETH left
+/// (the refunded fee headroom) would not pay for another transfer.
+pub async fn run_migrate(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer migrate [--from NAME] --to ACCOUNT|LABEL|ADDRESS [--tokens A,B] \
+                 [--headroom PERCENT] [--report FILE] [--yes] [--dry-run]";
+    let to_arg = option_value(args, "--to").ok_or(usage)?;
+    let headroom = match option_value(args, "--headroom") {
+        Some(h) => h.parse::<u64>().map_err(|_| format!("Invalid --headroom percentage: {}", h))?,
+        None => SWEEP_HEADROOM_PERCENT,
+    };
+
+    println!("=========================================================");
+    println!("         Account Migration");
+    println!("=========================================================\n");
+
+    let book = AddressBook::load_default()?;
+    let chain_id = get_chain_id().await?;
+    let signer = resolve_signer(option_value(args, "--from").as_deref())?;
+    let old = signer.address();
+
+    let registry = AccountsRegistry::load(&registry_path())?;
+    let new = match registry.get(&to_arg) {
+        Some(entry) => entry.address.ok_or_else(|| {
+            format!("Account '{}' has no recorded address; pass its address or address book label instead", to_arg)
+        })?,
+        None => {
+            let target = book.resolve(&to_arg, chain_id)?;
+            for warning in &target.warnings {
+                println!("⚠️  {}", warning);
+            }
+            check_recipient(&target)?;
+            target.address
+        }
+    };
+    if new == old {
+        return Err("--to is the same account as the one being migrated".into());
+    }
+    let tokens = migration_tokens(option_value(args, "--tokens").as_deref(), &book, chain_id)?;
+
+    println!("📋 Migration:");
+    println!("   From: {}", book.display(old));
+    println!("   To:   {}", book.display(new));
+    println!("   Network: Arbitrum Sepolia (Chain ID: {})\n", chain_id);
+
+    println!("🔍 Enumerating balances...");
+    let mut holdings = Vec::new();
+    for &token in &tokens {
+        let (symbol, decimals) = get_token_metadata(token).await?;
+        let balance = get_token_balance(token, old).await?;
+        println!("   {:<8} {} ({})", symbol, Amount::new(balance, decimals), book.display(token));
+        if !balance.is_zero() {
+            holdings.push((token, symbol, decimals, balance));
+        }
+    }
+    let eth_balance = get_balance(old).await?;
+    println!("   {:<8} {}", "ETH", eth_balance);
+    if tokens.is_empty() {
+        println!("   (no tokens configured; pass --tokens or set MIGRATE_TOKENS to include ERC20 balances)");
+    }
+    println!();
+
+    // Anything cheaper to leave than to move counts as empty
+    let dust_limit = get_gas_price().await? * U256::from(100_000);
+    if holdings.is_empty() && eth_balance.raw() < dust_limit {
+        println!("✅ Nothing to migrate: {} holds no movable assets.", book.display(old));
+        return Ok(());
+    }
+
+    // Simulate every token transfer now so a revert stops the run before anything is sent
+    let abi = erc20_abi();
+    println!("🔎 Simulating token transfers against pending state...");
+    let mut token_fees = U256::zero();
+    for (token, symbol, _, balance) in &holdings {
+        let data = encode_token_write(&abi, &TokenWrite::Transfer { to: new }, *balance)?;
+        let prepared = prepare_call(&signer, *token, U256::zero(), Some(data), None, Some(abi.clone()))
+            .await
+            .map_err(|e| format!("{} transfer would fail: {}", symbol, e))?;
+        token_fees += prepared.max_fee();
+    }
+    if eth_balance.raw() < token_fees {
+        return Err(format!(
+            "Insufficient ETH for gas: the token transfers need up to {} ETH, the account has {} ETH",
+            Amount::from_wei(token_fees),
+            eth_balance
+        )
+        .into());
+    }
+    println!("   ✅ All calls would succeed\n");
+
+    println!("⚠️  Ready to migrate:");
+    for (i, (_, symbol, decimals, balance)) in holdings.iter().enumerate() {
+        println!("   {}. Transfer {} {}", i + 1, Amount::new(*balance, *decimals), symbol);
+    }
+    println!("   {}. Sweep the remaining ETH (about {} ETH after gas)", holdings.len() + 1, Amount::from_wei(eth_balance.raw() - token_fees));
+    println!("   Max Fee (tokens): {} ETH", Amount::from_wei(token_fees));
+    println!();
+    if args.iter().any(|a| a == "--dry-run") {
+        println!("🧪 Dry run: nothing was sent. The sweep is priced once the token transfers are confirmed.");
+        return Ok(());
+    }
+    if !confirm_send(args)? {
+        return Ok(());
+    }
+
+    let config = TrackerConfig::from_args(args)?;
+    let mut report = MigrationReport::new(old, new, chain_id);
+    let report_path = option_value(args, "--report")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| report.default_path());
+
+    for (token, symbol, decimals, balance) in &holdings {
+        let amount = Amount::new(*balance, *decimals);
+        println!("🚀 Transferring {} {}...", amount, symbol);
+        let data = encode_token_write(&abi, &TokenWrite::Transfer { to: new }, *balance)?;
+        let (tx_hash, status) = match prepare_call(&signer, *token, U256::zero(), Some(data), None, Some(abi.clone())).await {
+            Ok(prepared) => send_migration_step(&prepared, &config).await,
+            Err(e) => (None, StepStatus::Failed { error: e.to_string() }),
+        };
+        if status != StepStatus::Confirmed {
+            println!("❌ {} transfer {}", symbol, status);
+        }
+        report.steps.push(MigrationStep {
+            asset: symbol.clone(),
+            token: Some(checksummed(*token)),
+            amount: amount.to_string(),
+            tx_hash,
+            status,
+        });
+        report.save(&report_path)?;
+    }
+
+    let eth_left = get_balance(old).await?;
+    let (amount, tx_hash, status) = if !report.all_confirmed() {
+        (eth_left.to_string(), None, StepStatus::Skipped { reason: "a token transfer failed; ETH kept for gas to retry".to_string() })
+    } else if eth_left.raw() < dust_limit {
+        (eth_left.to_string(), None, StepStatus::Skipped { reason: "balance is below the cost of a transfer".to_string() })
+    } else {
+        println!("🚀 Sweeping ETH...");
+        match prepare_sweep(&signer, new, headroom).await {
+            Ok((prepared, _)) => {
+                let (tx_hash, status) = send_migration_step(&prepared, &config).await;
+                (Amount::from_wei(prepared.value).to_string(), tx_hash, status)
+            }
+            Err(e) => (eth_left.to_string(), None, StepStatus::Failed { error: e.to_string() }),
+        }
+    };
+    if status != StepStatus::Confirmed {
+        println!("⚠️  ETH sweep {}", status);
+    }
+    report.steps.push(MigrationStep { asset: "ETH".to_string(), token: None, amount, tx_hash, status });
+    report.save(&report_path)?;
+
+    // Read everything back rather than trusting the receipts
+    println!("🔍 Verifying the old account...");
+    let mut tokens_left = false;
+    for &token in &tokens {
+        let (symbol, decimals) = get_token_metadata(token).await?;
+        let balance = get_token_balance(token, old).await?;
+        if !balance.is_zero() {
+            tokens_left = true;
+            report.remaining.push(RemainingBalance { asset: symbol, amount: Amount::new(balance, decimals).to_string() });
+        }
+    }
+    let eth_left = get_balance(old).await?;
+    if !eth_left.is_zero() {
+        report.remaining.push(RemainingBalance { asset: "ETH".to_string(), amount: eth_left.to_string() });
+    }
+    report.empty = !tokens_left && eth_left.raw() < dust_limit;
+    report.save(&report_path)?;
+
+    println!("---------------------------------------------------------");
+    for step in &report.steps {
+        let hash = step.tx_hash.map(|h| format!(" {:#x}", h)).unwrap_or_default();
+        println!("   {:<8} {:<24} {}{}", step.asset, step.amount, step.status, hash);
+    }
+    println!("---------------------------------------------------------");
+    for remaining in &report.remaining {
+        println!("   Left on old account: {} {}", remaining.amount, remaining.asset);
+    }
+    println!("📄 Report written to {}", report_path.display());
+    println!();
+
+    if !report.empty {
+        return Err(format!(
+            "Migration incomplete: {} still holds assets. Fix the failed steps and run migrate again.",
+            book.display(old)
+        )
+        .into());
+    }
+    if !eth_left.is_zero() {
+        println!("   {} ETH of refunded fee headroom is left; it costs more to move than it is worth.", eth_left);
+    }
+    println!("🎉 Migration complete: {} is empty.", book.display(old));
+    Ok(())
+}
@@ -0,0 +1,226 @@
+//! Subcommand handlers, one module per command, and the helpers they share
+
+pub mod accounts;
+pub mod address_book;
+pub mod batch;
+pub mod disperse;
+pub mod hd;
+pub mod keystore;
+pub mod migrate;
+pub mod nonce;
+pub mod sign;
+pub mod token;
+pub mod transfer;
+pub mod tx;
+pub mod verify;
+pub mod verify_log;
+
+use crate::batch::BatchRow;
+use crate::token::get_token_metadata;
+use crate::tracker::{wait_for_confirmations, TrackEvent, TrackerConfig, TxStatus};
+use crate::transfer::{PreparedTransfer, sign_transfer, simulate_transfer};
+
+use arb_common::amount::Amount;
+use ethers::providers::{Http, Provider};
+use ethers::types::{TransactionReceipt, H160, H256, U64};
+use std::collections::HashMap;
+use std::env;
+use std::io::Write;
+
+/// Value following a `--flag` argument
+fn option_value(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+/// Ask a yes/no question on the terminal; only a typed "yes" counts
+///
+/// Fails when stdin is not a terminal, so scripts have to pass `--yes` explicitly.
+fn confirm(prompt: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        return Err("Confirmation required: pass --yes to send without a terminal".into());
+    }
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("yes"))
+}
+
+/// `--yes`, or a typed "yes" on the terminal
+fn confirm_send(args: &[String]) -> Result<bool, Box<dyn std::error::Error>> {
+    if args.iter().any(|a| a == "--yes") {
+        println!("   --yes given, sending without asking.\n");
+        return Ok(true);
+    }
+    if !confirm("   Type 'yes' to send: ")? {
+        println!("❌ Transfer cancelled, nothing was sent.");
+        return Ok(false);
+    }
+    println!();
+    Ok(true)
+}
+
+/// `--yes`, or a typed "yes" before signing something that is not sent right away
+fn confirm_sign(args: &[String]) -> Result<bool, Box<dyn std::error::Error>> {
+    if args.iter().any(|a| a == "--yes") {
+        println!("   --yes given, signing without asking.\n");
+        return Ok(true);
+    }
+    if !confirm("   Type 'yes' to sign: ")? {
+        println!("❌ Signing cancelled.");
+        return Ok(false);
+    }
+    println!();
+    Ok(true)
+}
+
+/// Sign and simulate a prepared transaction and print it without broadcasting
+async fn dry_run(prepared: &PreparedTransfer) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🧪 Dry run: signing and simulating without broadcasting...\n");
+    let raw = sign_transfer(prepared).await?;
+    let gas_used = simulate_transfer(prepared).await?;
+    println!("---------------------------------------------------------");
+    println!("✅ Simulation succeeded");
+    println!("---------------------------------------------------------");
+    println!("Nonce:          {}", prepared.tx.nonce().copied().unwrap_or_default());
+    println!("Gas (estimate): {} of {} limit", gas_used, prepared.gas_limit);
+    println!("Max Fee:        {} ETH", Amount::from_wei(prepared.max_fee()));
+    println!("Total Cost:     {} ETH", Amount::from_wei(prepared.total_cost()));
+    println!("Tx Hash:        {:#x}", H256::from(ethers::utils::keccak256(&raw)));
+    println!("Raw Signed Tx:  {}", raw);
+    println!("---------------------------------------------------------");
+    println!("Nothing was broadcast.");
+    Ok(())
+}
+
+/// Print tracker progress
+fn print_track_event(event: &TrackEvent, config: &TrackerConfig) {
+    match event {
+        TrackEvent::Pending | TrackEvent::NotFound => {
+            print!(".");
+            // Flush stdout to show the dots
+            std::io::stdout().flush().ok();
+        }
+        TrackEvent::Included { block, confirmations } => {
+            println!();
+            println!("   Included in block {} ({}/{} confirmations)", block, confirmations, config.confirmations);
+        }
+        TrackEvent::Reorged { block } => {
+            println!();
+            println!("⚠️  Block {} was reorganised away, waiting for the transaction again", block);
+        }
+        TrackEvent::RpcError(e) => {
+            println!();
+            println!("⚠️  Error polling the node (will retry): {}", e);
+        }
+    }
+}
+
+/// Print how a tracked transaction ended
+///
+/// # Arguments
+/// * `status` - Tracker result
+/// * `tx_hash` - Transaction that was tracked, for speed-up/cancel hints
+/// * `config` - Tracker settings
+///
+/// # Returns
+/// * `Option<TransactionReceipt>` - Receipt if confirmed
+fn report_track_status(status: TxStatus, tx_hash: H256, config: &TrackerConfig) -> Option<TransactionReceipt> {
+    println!();
+    match status {
+        TxStatus::Confirmed { receipt, confirmations } => {
+            println!("---------------------------------------------------------");
+            println!("✅ Transaction Confirmed!");
+            println!("---------------------------------------------------------");
+            println!("Transaction Hash: {:#x}", receipt.transaction_hash);
+            println!("Block Number: {}", receipt.block_number.unwrap_or_default());
+            println!("Confirmations: {}", confirmations);
+            println!("Gas Used: {}", receipt.gas_used.unwrap_or_default());
+            println!("Status: {}", if receipt.status == Some(U64::from(1)) {
+                "Success ✓"
+            } else {
+                "Failed ✗"
+            });
+            println!("---------------------------------------------------------");
+            println!();
+            Some(*receipt)
+        }
+        TxStatus::Replaced { nonce } => {
+            println!("🔁 Transaction was replaced: nonce {} was used by another transaction from the sender.", nonce);
+            println!("   Check the sender's history on the explorer for the replacement.");
+            None
+        }
+        TxStatus::Dropped => {
+            println!("🗑️  Transaction was dropped: the node no longer knows it and its nonce is unused.");
+            println!("   It can be sent again.");
+            None
+        }
+        TxStatus::TimedOut { block, confirmations } => {
+            match block {
+                Some(block) => println!(
+                    "⏱️  Timed out with {}/{} confirmations (block {}).",
+                    confirmations, config.confirmations, block
+                ),
+                None => {
+                    println!("⏱️  Transaction not mined within {} seconds.", config.timeout.as_secs());
+                    println!("   Speed it up:  eth-transfer tx speedup {:#x}", tx_hash);
+                    println!("   Cancel it:    eth-transfer tx cancel {:#x}", tx_hash);
+                }
+            }
+            println!("   It may still be processing. Check the explorer link above.");
+            None
+        }
+    }
+}
+
+/// Provider for the tracker
+fn tracker_provider() -> Result<Provider<Http>, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    Ok(Provider::<Http>::try_from(rpc_url)?)
+}
+
+/// Wait for a submitted transaction and print how it ended
+///
+/// # Arguments
+/// * `tx_hash` - Transaction to track
+/// * `config` - Confirmation depth, timeout and poll interval
+///
+/// # Returns
+/// * `Result<Option<TransactionReceipt>, Box<dyn std::error::Error>>` - Receipt once
+///   confirmed; `None` if the transaction was replaced, dropped or timed out
+async fn track_transaction(
+    tx_hash: H256,
+    config: &TrackerConfig,
+) -> Result<Option<TransactionReceipt>, Box<dyn std::error::Error>> {
+    let provider = tracker_provider()?;
+    println!(
+        "⏳ Waiting for {} confirmation(s) (timeout {}s, polling every {}s)...",
+        config.confirmations,
+        config.timeout.as_secs(),
+        config.poll_interval.as_secs()
+    );
+    let status = wait_for_confirmations(&provider, tx_hash, config, |event| print_track_event(event, config)).await;
+    Ok(report_track_status(status, tx_hash, config))
+}
+
+/// Symbols and decimals of every token in a payout file
+async fn load_token_metadata(
+    rows: &[BatchRow],
+) -> Result<(HashMap<H160, String>, HashMap<H160, u8>), Box<dyn std::error::Error>> {
+    let mut symbols = HashMap::new();
+    let mut decimals = HashMap::new();
+    for token in rows.iter().filter_map(|r| r.token) {
+        if let std::collections::hash_map::Entry::Vacant(entry) = symbols.entry(token) {
+            let (symbol, token_decimals) = get_token_metadata(token).await?;
+            entry.insert(symbol);
+            decimals.insert(token, token_decimals);
+        }
+    }
+    Ok((symbols, decimals))
+}
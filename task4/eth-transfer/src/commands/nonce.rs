@@ -0,0 +1,61 @@
+use crate::accounts::resolve_signer;
+use crate::transfer::{get_chain_id, nonce_manager};
+use super::{option_value, confirm_send};
+
+use arb_common::address_book::AddressBook;
+use ethers::signers::Signer;
+use ethers::types::U256;
+
+/// Nonce inspection and gap filling
+///
+/// Usage:
+/// * `eth-transfer nonce status [--from NAME]`
+/// * `eth-transfer nonce fill-gaps --queued N,N,... [--from NAME] [--yes]`
+///
+/// `--queued` lists nonces of transactions already sent but stuck behind a
+/// missing nonce; every nonce between the node's pending count and the
+/// highest queued one that is not listed is filled with a 0-value transfer
+/// to the sender itself.
+pub async fn run_nonce(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer nonce <status|fill-gaps> [--from NAME] [--queued N,N,...] [--yes]";
+    let chain_id = get_chain_id().await?;
+    let signer = resolve_signer(option_value(args, "--from").as_deref())?.with_chain_id(chain_id);
+    let manager = nonce_manager(&signer)?;
+    let book = AddressBook::load_default()?;
+
+    match args.first().map(String::as_str) {
+        Some("status") => {
+            let (latest, pending) = manager.node_counts().await?;
+            println!("Account:        {}", book.display(manager.address()));
+            println!("Mined nonce:    {}", latest);
+            println!("Pending nonce:  {}", pending);
+            println!("In mempool:     {}", pending.saturating_sub(latest));
+            Ok(())
+        }
+        Some("fill-gaps") => {
+            let queued = option_value(args, "--queued").ok_or(usage)?;
+            let queued = queued
+                .split(',')
+                .map(|n| n.trim().parse::<u64>().map(U256::from).map_err(|_| format!("Invalid nonce: {}", n)))
+                .collect::<Result<Vec<_>, _>>()?;
+            manager.record_submitted(&queued);
+
+            let gaps = manager.gaps().await?;
+            if gaps.is_empty() {
+                println!("✅ No nonce gaps below the queued transactions.");
+                return Ok(());
+            }
+            let gaps: Vec<String> = gaps.iter().map(U256::to_string).collect();
+            println!("⚠️  Missing nonces for {}: {}", book.display(manager.address()), gaps.join(", "));
+            println!("   Each will be filled with a 0-value transfer to the sender.");
+            if !confirm_send(args)? {
+                return Ok(());
+            }
+            for (nonce, hash) in manager.fill_gaps().await? {
+                println!("   Nonce {}: {:#x}", nonce, hash);
+            }
+            Ok(())
+        }
+        _ => Err(usage.into()),
+    }
+}
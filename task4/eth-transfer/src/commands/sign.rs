@@ -0,0 +1,109 @@
+use crate::accounts::resolve_signer;
+use crate::message::{display_message, format_signature, message_bytes, message_hash, recover_message, SignedMessage};
+use crate::typed_data::{
+    load_typed_data,
+    preview as typed_data_preview,
+    recover_typed_data,
+    typed_data_hashes,
+    warnings as typed_data_warnings,
+};
+use super::{option_value, confirm_sign};
+
+use arb_common::address::checksummed;
+use ethers::signers::Signer;
+use ethers::types::{H256, U256};
+use std::path::{Path, PathBuf};
+
+/// Sign data with the configured wallet
+///
+/// Usage:
+/// * `eth-transfer sign message [TEXT] [--hex HEX | --file FILE] [--from NAME] [--out FILE]`
+/// * `eth-transfer sign typed-data <FILE> [--from NAME] [--chain-id N] [--yes]`
+///
+/// `message` produces an EIP-191 personal message signature, e.g. to prove
+/// ownership of an account to a partner. `--out` also writes
+/// `{address, msg, sig, version}` JSON that `verify --signed` and most
+/// explorers accept. `typed-data` signs EIP-712 JSON (permits, orders)
+/// after showing the decoded message and asking for a typed "yes"; the
+/// domain is checked against `--chain-id` (default 421614). Nothing is sent
+/// to the network (except to a remote signer).
+pub async fn run_sign(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer sign message [TEXT] [--hex HEX | --file FILE] [--from NAME] [--out FILE]\n       \
+                 eth-transfer sign typed-data <FILE> [--from NAME] [--chain-id N] [--yes]";
+    match args.first().map(String::as_str) {
+        Some("message") => {}
+        Some("typed-data") => return sign_typed_data_file(args).await,
+        _ => return Err(usage.into()),
+    }
+    let text = args.get(1).filter(|a| !a.starts_with("--"));
+    let file = option_value(args, "--file").map(PathBuf::from);
+    let message = message_bytes(text.map(String::as_str), option_value(args, "--hex").as_deref(), file.as_deref())?;
+
+    let signer = resolve_signer(option_value(args, "--from").as_deref())?;
+    let signature = signer.sign_message(&message).await?;
+    // Catch signers that apply a different prefix or none
+    if recover_message(&message, &signature)? != signer.address() {
+        return Err("Signer returned a signature that does not recover to its own address".into());
+    }
+
+    println!("---------------------------------------------------------");
+    println!("✍️  Message signed (EIP-191 personal_sign)");
+    println!("---------------------------------------------------------");
+    println!("Address:   {}", checksummed(signer.address()));
+    println!("Message:   {}", display_message(&message));
+    println!("Hash:      {:#x}", message_hash(&message));
+    println!("Signature: {}", format_signature(&signature));
+    println!("---------------------------------------------------------");
+    if let Some(out) = option_value(args, "--out") {
+        let signed = SignedMessage::new(signer.address(), &message, &signature);
+        std::fs::write(&out, serde_json::to_string_pretty(&signed)?)?;
+        println!("📄 Signed message written to {}", out);
+    }
+    Ok(())
+}
+
+/// `sign typed-data`: preview, confirm and sign an EIP-712 JSON file
+async fn sign_typed_data_file(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.get(1).filter(|a| !a.starts_with("--")).ok_or("Usage: eth-transfer sign typed-data <FILE>")?;
+    let chain_id = match option_value(args, "--chain-id") {
+        Some(c) => c.parse::<u64>().map_err(|_| format!("Invalid --chain-id: {}", c))?,
+        None => 421614,
+    };
+    let typed = load_typed_data(Path::new(path))?;
+    let hashes = typed_data_hashes(&typed)?;
+    let signer = resolve_signer(option_value(args, "--from").as_deref())?;
+
+    println!("⚠️  About to sign EIP-712 typed data as {}:", checksummed(signer.address()));
+    println!("---------------------------------------------------------");
+    for line in typed_data_preview(&typed) {
+        println!("{}", line);
+    }
+    println!("---------------------------------------------------------");
+    println!("Domain Separator: {:#x}", hashes.domain_separator);
+    println!("Struct Hash:      {:#x}", hashes.struct_hash);
+    println!("Digest:           {:#x}", hashes.digest);
+    for warning in typed_data_warnings(&typed, chain_id) {
+        println!("⚠️  {}", warning);
+    }
+    println!();
+    if !confirm_sign(args)? {
+        return Ok(());
+    }
+
+    let signature = signer.sign_typed_data_json(&typed).await?;
+    if recover_typed_data(&typed, &signature)? != signer.address() {
+        return Err("Signer returned a signature that does not recover to its own address".into());
+    }
+    println!("✅ Signed");
+    println!("Signature: {}", format_signature(&signature));
+    // Split form for contracts that take permit(v, r, s)
+    let word = |value: U256| {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        H256::from(bytes)
+    };
+    println!("   r: {:#x}", word(signature.r));
+    println!("   s: {:#x}", word(signature.s));
+    println!("   v: {}", if signature.v < 27 { signature.v + 27 } else { signature.v });
+    Ok(())
+}
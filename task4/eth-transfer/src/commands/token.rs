@@ -0,0 +1,88 @@
+use crate::accounts::resolve_signer;
+use crate::token::{get_token_metadata, prepare_token_write, token_abi, TokenWrite};
+use crate::tracker::TrackerConfig;
+use crate::transfer::{check_recipient, get_chain_id, send_transfer};
+use super::{option_value, confirm_send, dry_run, track_transaction};
+
+use arb_common::address_book::AddressBook;
+use arb_common::amount::Amount;
+use ethers::signers::Signer;
+use ethers::types::H256;
+use std::str::FromStr;
+
+/// ERC20 transfers and approvals
+///
+/// Usage:
+/// * `eth-transfer token transfer <TOKEN> --to LABEL|ADDRESS --amount N`
+/// * `eth-transfer token approve <TOKEN> --spender LABEL|ADDRESS --amount N`
+///
+/// Common options: `--from NAME`, `--abi FILE` (custom errors for revert
+/// decoding, default `TOKEN_ABI_PATH`), `--yes`, `--dry-run`. The call is
+/// simulated against pending state first and aborted with the decoded revert
+/// reason if it would fail. Once sent, the transaction is tracked like a
+/// transfer (`--confirmations`, `--timeout`, `--poll`).
+pub async fn run_token(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer token <transfer|approve> <TOKEN> <--to|--spender> ADDR --amount N \
+                 [--from NAME] [--abi FILE] [--yes] [--dry-run]";
+    let book = AddressBook::load_default()?;
+    let chain_id = get_chain_id().await?;
+
+    let token_arg = args.get(1).filter(|a| !a.starts_with("--")).ok_or(usage)?;
+    let token = book.resolve(token_arg, chain_id)?.address;
+    let amount = option_value(args, "--amount").ok_or(usage)?;
+    let (name, target_flag) = match args.first().map(String::as_str) {
+        Some("transfer") => ("transfer", "--to"),
+        Some("approve") => ("approve", "--spender"),
+        _ => return Err(usage.into()),
+    };
+    let target = book.resolve(&option_value(args, target_flag).ok_or(usage)?, chain_id)?;
+    for warning in &target.warnings {
+        println!("⚠️  {}", warning);
+    }
+    let write = if name == "transfer" {
+        check_recipient(&target)?;
+        TokenWrite::Transfer { to: target.address }
+    } else {
+        TokenWrite::Approve { spender: target.address }
+    };
+
+    let signer = resolve_signer(option_value(args, "--from").as_deref())?;
+    let abi = token_abi(option_value(args, "--abi").as_deref())?;
+    let (symbol, _) = get_token_metadata(token).await?;
+
+    println!("🔎 Simulating {} against pending state...", name);
+    let (prepared, amount) = prepare_token_write(&signer, token, &write, &amount, abi).await?;
+    println!("   ✅ Call would succeed\n");
+
+    if args.iter().any(|a| a == "--dry-run") {
+        return dry_run(&prepared).await;
+    }
+
+    println!("⚠️  Ready to send transaction:");
+    println!("   Token:      {} ({})", book.display(token), symbol);
+    println!("   From:       {}", book.display(signer.address()));
+    match &write {
+        TokenWrite::Transfer { to } => println!("   To:         {}", book.display(*to)),
+        TokenWrite::Approve { spender } => println!("   Spender:    {}", book.display(*spender)),
+    }
+    println!("   Amount:     {} {}", amount, symbol);
+    println!("   Max Fee:    {} ETH ({} Gwei × {} gas)", Amount::from_wei(prepared.max_fee()), Amount::wei_as_gwei(prepared.gas_price), prepared.gas_limit);
+    println!();
+    if !confirm_send(args)? {
+        return Ok(());
+    }
+
+    let tx_hash = send_transfer(&prepared).await?;
+    println!("---------------------------------------------------------");
+    println!("✅ Transaction Submitted Successfully!");
+    println!("---------------------------------------------------------");
+    println!("Transaction Hash: {}", tx_hash);
+    println!("   https://sepolia.arbiscan.io/tx/{}", tx_hash);
+    println!("---------------------------------------------------------");
+    println!();
+
+    let config = TrackerConfig::from_args(args)?;
+    track_transaction(H256::from_str(&tx_hash)?, &config).await?;
+
+    Ok(())
+}
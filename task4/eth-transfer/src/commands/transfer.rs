@@ -0,0 +1,190 @@
+use crate::accounts::resolve_signer;
+use crate::tracker::TrackerConfig;
+use crate::transfer::{
+    check_recipient,
+    get_balance,
+    get_chain_id,
+    prepare_sweep,
+    prepare_transfer,
+    SWEEP_HEADROOM_PERCENT,
+    send_transfer,
+    estimate_transfer_fee,
+};
+use super::{option_value, confirm_send, dry_run, track_transaction};
+
+use arb_common::address_book::AddressBook;
+use arb_common::amount::Amount;
+use ethers::signers::Signer;
+use ethers::types::{H256, U64};
+use std::str::FromStr;
+
+/// Send ETH from the configured wallet
+///
+/// Usage: `eth-transfer [send] [--from NAME] [--to LABEL|ADDRESS] [--amount ETH | --max [--headroom PERCENT]] [--yes] [--dry-run]`
+///
+/// `--max` (or `eth-transfer sweep`) sends the whole balance minus the
+/// maximum fee, see `prepare_sweep`.
+///
+/// `--from NAME` picks an account from the registry; without it the
+/// environment signer (`KEYSTORE_PATH`, `MNEMONIC_PATH` or `PRIVATE_KEY`) is used.
+/// `--to` accepts an address book label or a raw address. The transfer is
+/// only sent after a typed "yes" (or `--yes`); `--dry-run` signs and
+/// simulates it and prints the raw transaction instead of broadcasting.
+/// After sending, the transaction is tracked until `--confirmations N`
+/// blocks deep (see `run_tx` for the tracker options).
+pub async fn run_transfer(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    println!("=========================================================");
+    println!("         Arbitrum Sepolia ETH Transfer");
+    println!("=========================================================\n");
+
+    let book = AddressBook::load_default()?;
+    let chain_id = get_chain_id().await?;
+
+    // The sender is whoever the signer is
+    let from = option_value(args, "--from");
+    let signer = resolve_signer(from.as_deref())?;
+    let sender_address = signer.address();
+    let receiver = option_value(args, "--to")
+        .unwrap_or_else(|| "0x7292dD72151DaCFBbE76305db1C8Ab1928E922E4".to_string());
+
+    // Validate addresses
+    println!("🔍 Resolving receiver address...");
+    let receiver = book.resolve(&receiver, chain_id)?;
+    for warning in &receiver.warnings {
+        println!("   ⚠️  {}", warning);
+    }
+    check_recipient(&receiver)?;
+    let receiver_address = receiver.address;
+    println!("   ✅ Address is valid\n");
+
+    let sender_display = match &from {
+        Some(name) if book.label_for(sender_address).is_none() => {
+            format!("{} ({})", book.display(sender_address), name)
+        }
+        _ => book.display(sender_address),
+    };
+    let receiver_display = book.display(receiver_address);
+
+    println!("📋 Transaction Details:");
+    println!("   From: {}", sender_display);
+    println!("   To:   {}", receiver_display);
+    if let Some(notes) = book.entry_for(receiver_address).and_then(|e| e.notes.as_ref()) {
+        println!("   Note: {}", notes);
+    }
+    println!("   Network: Arbitrum Sepolia (Chain ID: {})\n", chain_id);
+
+    // Check sender balance
+    println!("💰 Checking sender balance...");
+    let sender_balance = get_balance(sender_address).await?;
+    println!("   Sender Balance: {} ETH\n", sender_balance);
+
+    // Amount to transfer (default 0.0001 ETH), or everything with --max
+    let sweep = args.iter().any(|a| a == "--max");
+    if sweep && option_value(args, "--amount").is_some() {
+        return Err("--max sends the whole balance and cannot be combined with --amount".into());
+    }
+    let transfer_amount = option_value(args, "--amount").unwrap_or_else(|| "0.0001".to_string());
+    let transfer_amount = transfer_amount.as_str();
+
+    // Check if balance is sufficient
+    if sender_balance < Amount::parse_ether("0.001")? {
+        println!("⚠️  Warning: Sender balance is very low ({} ETH)", sender_balance);
+        println!("   You may need more ETH to cover gas fees.\n");
+    }
+
+    // Build the exact transaction that will be signed
+    let prepared = if sweep {
+        let headroom = match option_value(args, "--headroom") {
+            Some(h) => h.parse::<u64>().map_err(|_| format!("Invalid --headroom percentage: {}", h))?,
+            None => SWEEP_HEADROOM_PERCENT,
+        };
+        println!("⛽ Computing the sweep fee...");
+        let (prepared, fees) = prepare_sweep(&signer, receiver_address, headroom).await?;
+        println!("   L2 Gas:      {}", fees.l2_gas);
+        println!("   L1 Gas:      {} (+{}% headroom)", fees.l1_gas, headroom);
+        println!("   Max Fee/Gas: {} Gwei (base fee {} Gwei +{}%)", Amount::wei_as_gwei(fees.max_fee_per_gas), Amount::wei_as_gwei(prepared.base_fee), headroom);
+        println!("   Unused fee headroom is refunded to the sender after the sweep.");
+        println!();
+        println!("💸 Transfer Amount: {} ETH (entire balance minus max fee)\n", Amount::from_wei(prepared.value));
+        prepared
+    } else {
+        println!("💸 Transfer Amount: {} ETH\n", transfer_amount);
+
+        // Estimate gas fee
+        println!("⛽ Estimating gas fee...");
+        let gas_info = estimate_transfer_fee().await?;
+        println!("   {}", gas_info);
+        println!();
+
+        prepare_transfer(&signer, receiver_address, transfer_amount).await?
+    };
+    let max_fee = Amount::from_wei(prepared.max_fee());
+    let total_cost = Amount::from_wei(prepared.total_cost());
+    if sender_balance < total_cost {
+        return Err(format!(
+            "Insufficient balance: {} ETH needed (amount + max fee), sender has {} ETH",
+            total_cost, sender_balance
+        )
+        .into());
+    }
+
+    if args.iter().any(|a| a == "--dry-run") {
+        return dry_run(&prepared).await;
+    }
+
+    // Confirm before proceeding
+    println!("⚠️  Ready to send transaction:");
+    println!("   From:       {}", sender_display);
+    println!("   To:         {}", receiver_display);
+    println!("   Amount:     {} ETH", Amount::from_wei(prepared.value));
+    println!("   Max Fee:    {} ETH ({} Gwei × {} gas)", max_fee, Amount::wei_as_gwei(prepared.gas_price), prepared.gas_limit);
+    println!("   Total Cost: {} ETH", total_cost);
+    println!();
+    if !confirm_send(args)? {
+        return Ok(());
+    }
+
+    // Execute transfer
+    println!("🚀 Executing transfer...\n");
+    match send_transfer(&prepared).await {
+        Ok(tx_hash) => {
+            println!("---------------------------------------------------------");
+            println!("✅ Transaction Submitted Successfully!");
+            println!("---------------------------------------------------------");
+            println!("Transaction Hash: {}", tx_hash);
+            println!("---------------------------------------------------------");
+            println!();
+            println!("🔗 View on Arbitrum Sepolia Explorer:");
+            println!("   https://sepolia.arbiscan.io/tx/{}", tx_hash);
+            println!();
+
+            // Wait for transaction confirmation
+            let config = TrackerConfig::from_args(args)?;
+            if let Some(receipt) = track_transaction(H256::from_str(&tx_hash)?, &config).await? {
+                if receipt.status == Some(U64::from(1)) {
+                    // Check final balances
+                    println!("📊 Final Balances:");
+                    let new_sender_balance = get_balance(sender_address).await?;
+                    let new_receiver_balance = get_balance(receiver_address).await?;
+                    println!("   Sender   {}: {} ETH", sender_display, new_sender_balance);
+                    println!("   Receiver {}: {} ETH", receiver_display, new_receiver_balance);
+                    println!();
+
+                    println!("🎉 Transfer completed successfully!");
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("❌ Transaction failed: {}", e);
+            println!();
+            println!("Troubleshooting tips:");
+            println!("1. Check that KEYSTORE_PATH (or PRIVATE_KEY with ALLOW_PLAINTEXT_KEY=1) is set in .env file");
+            println!("2. Ensure the sender address has sufficient ETH balance");
+            println!("3. Verify the RPC endpoint is accessible");
+            println!("4. Check that you're using the correct key for the sender address");
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
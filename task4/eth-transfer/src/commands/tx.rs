@@ -0,0 +1,403 @@
+use crate::accounts::{registry_path, resolve_signer, AccountsRegistry};
+use crate::decode::{decode_calldata, decode_raw, type_name, Sender};
+use crate::nonce::{classify_send_error, SendErrorKind};
+use crate::offline::{decode_signed, read_raw_transaction, sign_offline, UnsignedTransaction};
+use crate::policy;
+use crate::token::token_abi;
+use crate::tracker::{wait_for_any, TrackerConfig};
+use crate::transfer::{
+    build_request,
+    check_recipient,
+    get_balance,
+    get_chain_id,
+    prepare_replacement,
+    send_replacement,
+    Replacement,
+    MIN_BUMP_PERCENT,
+};
+use super::{
+    option_value,
+    confirm_send,
+    confirm_sign,
+    dry_run,
+    print_track_event,
+    report_track_status,
+    tracker_provider,
+    track_transaction,
+};
+
+use arb_common::address_book::AddressBook;
+use arb_common::address::checksummed;
+use arb_common::amount::Amount;
+use ethers::providers::Middleware;
+use ethers::signers::Signer;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockNumber, H160, H256, U256, U64};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Speed up or cancel a pending transaction and track whichever version lands
+async fn replace_transaction(
+    args: &[String],
+    tx_hash: H256,
+    kind: Replacement,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bump = match option_value(args, "--bump") {
+        Some(b) => b.parse::<u64>().map_err(|_| format!("Invalid --bump percentage: {}", b))?,
+        None => MIN_BUMP_PERCENT,
+    };
+    let signer = resolve_signer(option_value(args, "--from").as_deref())?;
+    let book = AddressBook::load_default()?;
+
+    let prepared = prepare_replacement(&signer, tx_hash, kind, bump).await?;
+    let nonce = prepared.tx.nonce().copied().unwrap_or_default();
+    println!("⚠️  Ready to replace transaction {:#x}:", tx_hash);
+    println!("   Action:     {}", match kind {
+        Replacement::SpeedUp => "speed up (same call, higher fees)",
+        Replacement::Cancel => "cancel (0 ETH to self)",
+    });
+    println!("   From:       {}", book.display(prepared.signer.address()));
+    println!("   Nonce:      {}", nonce);
+    println!("   Gas Price:  {} Gwei (+{}% or current network price)", Amount::wei_as_gwei(prepared.gas_price), bump);
+    println!("   Max Fee:    {} ETH", Amount::from_wei(prepared.max_fee()));
+    println!();
+
+    if args.iter().any(|a| a == "--dry-run") {
+        return dry_run(&prepared).await;
+    }
+    if !confirm_send(args)? {
+        return Ok(());
+    }
+
+    let replacement = send_replacement(&prepared).await?;
+    println!("✅ Replacement submitted: {:#x}", replacement);
+    println!("   https://sepolia.arbiscan.io/tx/{:#x}", replacement);
+    println!();
+
+    let config = TrackerConfig::from_args(args)?;
+    let provider = tracker_provider()?;
+    println!("⏳ Waiting for the original or the replacement ({} confirmation(s))...", config.confirmations);
+    let status = wait_for_any(
+        &provider,
+        &[tx_hash, replacement],
+        prepared.signer.address(),
+        nonce,
+        &config,
+        |event| print_track_event(event, &config),
+    )
+    .await;
+    if let Some(receipt) = report_track_status(status, replacement, &config) {
+        if receipt.transaction_hash == replacement {
+            println!("🎉 The replacement landed; the original will never be mined.");
+        } else {
+            println!("ℹ️  The original transaction landed before the replacement.");
+        }
+    }
+    Ok(())
+}
+
+/// Transaction utilities
+///
+/// Usage:
+/// * `eth-transfer tx wait <HASH> [--confirmations N] [--timeout SECS] [--poll SECS]`
+/// * `eth-transfer tx speedup <HASH> [--bump PERCENT] [--from NAME] [--yes] [--dry-run]`
+/// * `eth-transfer tx cancel <HASH> [--bump PERCENT] [--from NAME] [--yes] [--dry-run]`
+/// * `eth-transfer tx build --from NAME|ADDRESS --to LABEL|ADDRESS --amount ETH [--nonce N] [--out FILE]`
+/// * `eth-transfer tx sign <FILE> [--from NAME] [--out FILE] [--yes]`
+/// * `eth-transfer tx broadcast <FILE|HEX> [--yes]`
+/// * `eth-transfer tx decode <FILE|HEX> [--abi FILE]`
+///
+/// Tracker defaults come from `TX_CONFIRMATIONS`, `TX_TIMEOUT_SECS` and
+/// `TX_POLL_SECS` (1 confirmation, 120 seconds, every 2 seconds). Speed-up
+/// and cancel resend the pending transaction's nonce with fees raised by
+/// `--bump` percent (default and minimum 10), then wait for whichever
+/// version is mined. Build, sign and broadcast split a transfer across
+/// machines so the key never has to be on one with network access.
+pub async fn run_tx(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer tx <wait|speedup|cancel> <HASH> [--confirmations N] [--timeout SECS] \
+                 [--poll SECS] [--bump PERCENT] [--from NAME] [--yes] [--dry-run]\n       \
+                 eth-transfer tx <build|sign|broadcast|decode> ...";
+    match args.first().map(String::as_str) {
+        Some("build") => return run_tx_build(&args[1..]).await,
+        Some("sign") => return run_tx_sign(&args[1..]).await,
+        Some("broadcast") => return run_tx_broadcast(&args[1..]).await,
+        Some("decode") => return run_tx_decode(&args[1..]),
+        _ => {}
+    }
+    let hash = args.get(1).filter(|a| !a.starts_with("--")).ok_or(usage)?;
+    let hash = H256::from_str(hash).map_err(|_| format!("Invalid transaction hash: {}", hash))?;
+
+    match args.first().map(String::as_str) {
+        Some("wait") => {
+            let config = TrackerConfig::from_args(&args[2..])?;
+            println!("🔗 https://sepolia.arbiscan.io/tx/{:#x}", hash);
+            match track_transaction(hash, &config).await? {
+                Some(_) => Ok(()),
+                None => Err("Transaction was not confirmed".into()),
+            }
+        }
+        Some("speedup") => replace_transaction(&args[2..], hash, Replacement::SpeedUp).await,
+        Some("cancel") => replace_transaction(&args[2..], hash, Replacement::Cancel).await,
+        _ => Err(usage.into()),
+    }
+}
+
+/// Print what a transaction does
+fn print_tx_details(book: &AddressBook, tx: &TypedTransaction, from: H160) {
+    println!("   From:       {}", book.display(from));
+    match tx.to_addr() {
+        Some(to) => println!("   To:         {}", book.display(*to)),
+        None => println!("   To:         (contract creation)"),
+    }
+    println!("   Amount:     {} ETH", Amount::from_wei(tx.value().copied().unwrap_or_default()));
+    if let Some(data) = tx.data().filter(|d| !d.is_empty()) {
+        println!("   Data:       {} bytes", data.len());
+    }
+    println!("   Chain ID:   {}", tx.chain_id().unwrap_or_default());
+    println!("   Nonce:      {}", tx.nonce().copied().unwrap_or_default());
+    let gas_price = tx.gas_price().unwrap_or_default();
+    let gas_limit = tx.gas().copied().unwrap_or_default();
+    println!(
+        "   Max Fee:    {} ETH ({} Gwei × {} gas)",
+        Amount::from_wei(gas_price.saturating_mul(gas_limit)),
+        Amount::wei_as_gwei(gas_price),
+        gas_limit
+    );
+}
+
+/// Build an unsigned ETH transfer for signing on an offline machine
+///
+/// Usage: `eth-transfer tx build --from NAME|ADDRESS --to LABEL|ADDRESS --amount ETH [--nonce N] [--out FILE]`
+///
+/// Needs the network but no key: `--from` is a registry account with a
+/// recorded address, an address book label, or a raw address. The nonce,
+/// fees and chain ID are fetched now; `--nonce` overrides the pending nonce
+/// when several transactions are built before any is broadcast. The file
+/// (default `unsigned-tx.json`) is what `tx sign` reads.
+pub async fn run_tx_build(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer tx build --from NAME|ADDRESS --to LABEL|ADDRESS --amount ETH [--nonce N] [--out FILE]";
+    let from_arg = option_value(args, "--from").ok_or(usage)?;
+    let to_arg = option_value(args, "--to").ok_or(usage)?;
+    let amount = Amount::parse_ether(&option_value(args, "--amount").ok_or(usage)?)?;
+    let out = PathBuf::from(option_value(args, "--out").unwrap_or_else(|| "unsigned-tx.json".to_string()));
+
+    let book = AddressBook::load_default()?;
+    let chain_id = get_chain_id().await?;
+    let registry = AccountsRegistry::load(&registry_path())?;
+    let from = match registry.get(&from_arg) {
+        Some(entry) => entry.address.ok_or_else(|| {
+            format!("Account '{}' has no recorded address; pass its address or address book label instead", from_arg)
+        })?,
+        None => book.resolve(&from_arg, chain_id)?.address,
+    };
+    let to = book.resolve(&to_arg, chain_id)?;
+    for warning in &to.warnings {
+        println!("⚠️  {}", warning);
+    }
+    check_recipient(&to)?;
+
+    // Standard gas limit for ETH transfer, as in `prepare_transfer`
+    let mut unsigned = build_request(from, Some(to.address), amount.raw(), None, Some(U256::from(100_000)), None).await?;
+    if let Some(nonce) = option_value(args, "--nonce") {
+        let nonce = nonce.parse::<u64>().map_err(|_| format!("Invalid --nonce: {}", nonce))?;
+        unsigned.tx.set_nonce(nonce);
+    }
+    let unsigned = UnsignedTransaction::new(unsigned.tx, unsigned.base_fee)?;
+
+    let balance = get_balance(from).await?;
+    let total_cost = amount.raw().saturating_add(unsigned.max_fee());
+    if balance.raw() < total_cost {
+        return Err(format!(
+            "Insufficient balance: {} ETH needed (amount + max fee), sender has {} ETH",
+            Amount::from_wei(total_cost),
+            balance
+        )
+        .into());
+    }
+
+    unsigned.save(&out)?;
+    println!("📝 Unsigned transaction written to {}", out.display());
+    print_tx_details(&book, &unsigned.tx, from);
+    println!();
+    println!("   Sign it on the offline machine:  eth-transfer tx sign {}", out.display());
+    println!("   The fees are priced for the current base fee ({} Gwei); broadcast soon after signing.", Amount::wei_as_gwei(unsigned.base_fee));
+    Ok(())
+}
+
+/// Sign an unsigned transaction file without network access
+///
+/// Usage: `eth-transfer tx sign <FILE> [--from NAME] [--out FILE] [--yes]`
+///
+/// Shows what is being signed and asks for a typed "yes" (or `--yes`). The
+/// signer (`--from NAME` or the environment signer) must be the file's
+/// sender. The raw signed transaction is written as hex (default
+/// `signed-tx.hex`).
+pub async fn run_tx_sign(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer tx sign <FILE> [--from NAME] [--out FILE] [--yes]";
+    let path = PathBuf::from(args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?);
+    let out = PathBuf::from(option_value(args, "--out").unwrap_or_else(|| "signed-tx.hex".to_string()));
+    let unsigned = UnsignedTransaction::load(&path)?;
+    let book = AddressBook::load_default()?;
+
+    println!("⚠️  About to sign:");
+    print_tx_details(&book, &unsigned.tx, unsigned.from);
+    println!();
+    if !confirm_sign(args)? {
+        return Ok(());
+    }
+
+    let signer = resolve_signer(option_value(args, "--from").as_deref())?;
+    let raw = sign_offline(&unsigned, &signer).await?;
+    std::fs::write(&out, format!("{}\n", raw))?;
+    println!("✅ Signed transaction written to {}", out.display());
+    println!("   Tx Hash: {:#x}", H256::from(ethers::utils::keccak256(&raw)));
+    println!();
+    println!("   Broadcast it from a networked machine:  eth-transfer tx broadcast {}", out.display());
+    Ok(())
+}
+
+/// Submit a raw signed transaction and track it
+///
+/// Usage: `eth-transfer tx broadcast <FILE|HEX> [--yes]`
+///
+/// The transaction is decoded and its sender recovered before anything is
+/// sent; it is refused if it is for another chain or its nonce is already
+/// used. The tracker options of `tx wait` apply.
+pub async fn run_tx_broadcast(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer tx broadcast <FILE|HEX> [--yes] [--confirmations N] [--timeout SECS] [--poll SECS]";
+    let input = args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?;
+    let raw = read_raw_transaction(input)?;
+    let (tx, from, tx_hash) = decode_signed(&raw)?;
+    let book = AddressBook::load_default()?;
+    let provider = tracker_provider()?;
+
+    let chain_id = provider.get_chainid().await?;
+    if tx.chain_id() != Some(U64::from(chain_id.as_u64())) {
+        return Err(format!(
+            "Transaction is for chain {}, but the RPC is on chain {}",
+            tx.chain_id().unwrap_or_default(),
+            chain_id
+        )
+        .into());
+    }
+    let nonce = tx.nonce().copied().unwrap_or_default();
+    let mined = provider.get_transaction_count(from, Some(BlockNumber::Latest.into())).await?;
+    if nonce < mined {
+        return Err(format!(
+            "Nonce {} of {} is already used: this transaction (or one replacing it) was mined",
+            nonce,
+            checksummed(from)
+        )
+        .into());
+    }
+
+    println!("⚠️  Ready to broadcast:");
+    print_tx_details(&book, &tx, from);
+    println!("   Tx Hash:    {:#x}", tx_hash);
+    if let Some(base_fee) = provider.get_block(BlockNumber::Latest).await?.and_then(|b| b.base_fee_per_gas) {
+        if tx.gas_price().unwrap_or_default() < base_fee {
+            println!("⚠️  The base fee is now {} Gwei, above this transaction's fee cap; it will wait until it drops.", Amount::wei_as_gwei(base_fee));
+        }
+    }
+    println!();
+    if !confirm_send(args)? {
+        return Ok(());
+    }
+
+    match provider.send_raw_transaction(raw).await {
+        Ok(_) => {}
+        Err(e) if classify_send_error(&e.to_string()) == SendErrorKind::AlreadyKnown => {
+            println!("   The node already has this transaction.");
+        }
+        Err(e) => return Err(format!("Broadcast failed: {}", e).into()),
+    }
+    policy::record_sent(from, &tx);
+    println!("✅ Transaction Submitted: {:#x}", tx_hash);
+    println!("   https://sepolia.arbiscan.io/tx/{:#x}", tx_hash);
+    println!();
+
+    let config = TrackerConfig::from_args(args)?;
+    match track_transaction(tx_hash, &config).await? {
+        Some(_) => Ok(()),
+        None => Err("Transaction was not confirmed".into()),
+    }
+}
+
+/// Decode a raw transaction without sending it
+///
+/// Usage: `eth-transfer tx decode <FILE|HEX> [--abi FILE]`
+///
+/// Handles legacy, EIP-2930, EIP-1559 and EIP-7702 envelopes and Arbitrum's
+/// system types (see `decode::decode_raw`). Calldata is decoded against
+/// `--abi` (default `TOKEN_ABI_PATH`, then the built-in ERC20 ABI).
+pub fn run_tx_decode(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer tx decode <FILE|HEX> [--abi FILE]";
+    let input = args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?;
+    let raw = read_raw_transaction(input)?;
+    let decoded = decode_raw(&raw)?;
+    let abi = token_abi(option_value(args, "--abi").as_deref())?;
+    let book = AddressBook::load_default()?;
+
+    println!("---------------------------------------------------------");
+    println!("Type:         {} (0x{:02x})", type_name(decoded.tx_type), decoded.tx_type);
+    println!("Tx Hash:      {:#x}", decoded.hash);
+    match decoded.chain_id {
+        Some(chain_id) => println!("Chain ID:     {}", chain_id),
+        None => println!("Chain ID:     (none)"),
+    }
+    match decoded.sender {
+        Sender::Recovered(from) => println!("From:         {} (recovered from signature)", book.display(from)),
+        Sender::Declared(from) => println!("From:         {} (declared, unsigned system transaction)", book.display(from)),
+    }
+    match decoded.to {
+        Some(to) => println!("To:           {}", book.display(to)),
+        None => println!("To:           (contract creation)"),
+    }
+    println!("Value:        {} ETH", Amount::from_wei(decoded.value));
+    if let Some(nonce) = decoded.nonce {
+        println!("Nonce:        {}", nonce);
+    }
+    if let Some(gas_limit) = decoded.gas_limit {
+        println!("Gas Limit:    {}", gas_limit);
+    }
+    for (label, fee) in &decoded.fees {
+        println!("{:<13} {} Gwei", format!("{}:", label), Amount::wei_as_gwei(*fee));
+    }
+    for (label, value) in &decoded.extra {
+        println!("{:<13} {}", format!("{}:", label), value);
+    }
+    for (i, auth) in decoded.authorizations.iter().enumerate() {
+        let authority = auth.authority.map_or("(invalid signature)".to_string(), |a| book.display(a));
+        println!(
+            "Auth #{}:      {} delegates to {} (chain {}, nonce {})",
+            i,
+            authority,
+            book.display(auth.address),
+            auth.chain_id,
+            auth.nonce
+        );
+    }
+    if decoded.data.is_empty() {
+        println!("Data:         (none)");
+    } else {
+        println!("Data:         {} bytes", decoded.data.len());
+        match decode_calldata(&abi, &decoded.data) {
+            Some((signature, params)) => {
+                println!("Call:         {}", signature);
+                for (name, token) in params {
+                    let value = match token {
+                        ethers::abi::Token::Address(address) => book.display(address),
+                        token => token.to_string(),
+                    };
+                    println!("   {}: {}", name, value);
+                }
+            }
+            None if decoded.data.len() >= 4 => {
+                println!("Selector:     0x{} (not in the loaded ABI)", ethers::utils::hex::encode(&decoded.data[..4]));
+            }
+            None => {}
+        }
+    }
+    println!("---------------------------------------------------------");
+    Ok(())
+}
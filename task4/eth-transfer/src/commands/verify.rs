@@ -0,0 +1,74 @@
+use crate::message::{check_signer, display_message, message_bytes, parse_signature, recover_message, SignedMessage};
+use crate::typed_data::{load_typed_data, recover_typed_data, typed_data_hashes};
+use super::option_value;
+
+use arb_common::address_book::AddressBook;
+use arb_common::address::parse_address;
+use ethers::types::H160;
+use std::path::{Path, PathBuf};
+
+/// Check who signed a message
+///
+/// Usage:
+/// * `eth-transfer verify <SIGNATURE> [TEXT] [--hex HEX | --file FILE] [--address LABEL|ADDRESS]`
+/// * `eth-transfer verify --signed FILE [--address LABEL|ADDRESS]`
+/// * `eth-transfer verify <SIGNATURE> --typed-data FILE [--address LABEL|ADDRESS]`
+///
+/// Recovers the signer of an EIP-191 personal message, or of EIP-712 typed
+/// data with `--typed-data`. With `--address` (or the address in a
+/// `--signed` file) the command fails unless that account made the
+/// signature.
+pub fn run_verify(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: eth-transfer verify <SIGNATURE> [TEXT] [--hex HEX | --file FILE] [--address LABEL|ADDRESS]\n       \
+                 eth-transfer verify --signed FILE [--address LABEL|ADDRESS]\n       \
+                 eth-transfer verify <SIGNATURE> --typed-data FILE [--address LABEL|ADDRESS]";
+    let book = AddressBook::load_default()?;
+    let expected = match option_value(args, "--address") {
+        // Addresses here are public, so any chain's book entry is fine
+        Some(address) => match book.get(&address) {
+            Some(entry) => Some(entry.address),
+            None => Some(parse_address(&address)?.address),
+        },
+        None => None,
+    };
+
+    if let Some(path) = option_value(args, "--typed-data") {
+        let signature = args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?;
+        let typed = load_typed_data(Path::new(&path))?;
+        let signer = recover_typed_data(&typed, &parse_signature(signature)?)?;
+        println!("Typed Data: {} (digest {:#x})", typed.primary_type, typed_data_hashes(&typed)?.digest);
+        return report_signer(&book, signer, expected);
+    }
+    let (message, signature, signed_by) = match option_value(args, "--signed") {
+        Some(path) => {
+            let signed = SignedMessage::load(Path::new(&path))?;
+            (signed.message()?, parse_signature(&signed.sig)?, Some(parse_address(&signed.address)?.address))
+        }
+        None => {
+            let signature = args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?;
+            let text = args.get(1).filter(|a| !a.starts_with("--"));
+            let file = option_value(args, "--file").map(PathBuf::from);
+            let message = message_bytes(text.map(String::as_str), option_value(args, "--hex").as_deref(), file.as_deref())?;
+            (message, parse_signature(signature)?, None)
+        }
+    };
+
+    println!("Message:   {}", display_message(&message));
+    report_signer(&book, recover_message(&message, &signature)?, expected.or(signed_by))
+}
+
+/// Print a recovered signer, failing if it is not the expected account
+fn report_signer(book: &AddressBook, signer: H160, expected: Option<H160>) -> Result<(), Box<dyn std::error::Error>> {
+    match expected {
+        Some(expected) => {
+            check_signer(signer, expected)?;
+            println!("✅ Valid signature by {}", book.display(signer));
+            Ok(())
+        }
+        None => {
+            println!("Signer:    {}", book.display(signer));
+            println!("   Pass --address to check it against the expected account.");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::audit;
+use super::option_value;
+
+use arb_common::address_book::AddressBook;
+use arb_common::amount::Amount;
+use ethers::types::H256;
+use std::path::PathBuf;
+
+/// Check the signing audit log for edits and truncation
+///
+/// Usage: `eth-transfer verify-log [FILE] [--head HASH] [--list]`
+///
+/// Walks the hash chain of `FILE` (default the `--audit-log` file, `AUDIT_LOG`
+/// or `audit.log`) and compares its end with the head file. `--head` also
+/// checks the last hash against one recorded elsewhere; `--list` prints
+/// every entry.
+pub fn run_verify_log(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.first().filter(|a| !a.starts_with("--")).map(PathBuf::from).unwrap_or_else(audit::audit_path);
+    let expected = match option_value(args, "--head") {
+        Some(head) => Some(head.parse::<H256>().map_err(|_| format!("Invalid --head hash: {}", head))?),
+        None => None,
+    };
+
+    println!("Audit Log: {}", path.display());
+    let entries = audit::verify(&path, expected).map_err(|e| format!("❌ Audit log check failed: {}", e))?;
+    if args.iter().any(|a| a == "--list") {
+        let book = AddressBook::load_default()?;
+        println!("---------------------------------------------------------");
+        for entry in &entries {
+            let decision = serde_json::to_value(&entry.policy)?["decision"].as_str().unwrap_or_default().to_string();
+            println!(
+                "#{} {} chain {} {} → {} {} ETH nonce {} [{}] {}",
+                entry.seq,
+                entry.time,
+                entry.chain_id,
+                book.display(entry.signer),
+                entry.to.map(|to| book.display(to)).unwrap_or_else(|| "(deploy)".to_string()),
+                Amount::from_wei(entry.value),
+                entry.nonce.unwrap_or_default(),
+                decision,
+                entry.tx_hash.map(|h| format!("{:#x}", h)).unwrap_or_else(|| "not signed".to_string())
+            );
+        }
+        println!("---------------------------------------------------------");
+    }
+    println!("✅ {} entr{} intact", entries.len(), if entries.len() == 1 { "y" } else { "ies" });
+    println!("Head:      {:#x}", entries.last().map(|e| e.hash).unwrap_or_default());
+    println!("   Keep a copy of the head hash elsewhere and pass it with --head to detect a rewritten log.");
+    Ok(())
+}
@@ -0,0 +1,478 @@
+//! Raw transaction decoding, including Arbitrum's own transaction types
+//!
+//! Legacy, EIP-2930 and EIP-1559 envelopes are decoded with ethers. EIP-7702
+//! and the Arbitrum types are decoded here from their RLP field lists, in the
+//! order Nitro defines them (`core/types/arb_types.go`). The Arbitrum types
+//! are created by the sequencer or the bridge rather than signed by a user,
+//! so their sender is the one declared in the transaction.
+
+use arb_common::address::checksummed;
+use arb_common::amount::Amount;
+use ethers::abi::{Abi, Token};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, Signature, H160, H256, U256};
+use ethers::utils::keccak256;
+use ethers::utils::rlp::{Decodable, Rlp, RlpStream};
+
+/// ArbOS, the sender of internal transactions
+const ARBOS_ADDRESS: &str = "0x00000000000000000000000000000000000A4B05";
+
+/// Human-readable name of a transaction type
+pub fn type_name(tx_type: u8) -> &'static str {
+    match tx_type {
+        0x00 => "Legacy",
+        0x01 => "EIP-2930 (access list)",
+        0x02 => "EIP-1559 (dynamic fee)",
+        0x04 => "EIP-7702 (set code)",
+        0x64 => "Arbitrum deposit",
+        0x65 => "Arbitrum unsigned",
+        0x66 => "Arbitrum contract",
+        0x68 => "Arbitrum retry",
+        0x69 => "Arbitrum submit retryable",
+        0x6a => "Arbitrum internal",
+        _ => "Unknown",
+    }
+}
+
+/// Who sent a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sender {
+    /// Recovered from the signature
+    Recovered(H160),
+    /// Stated in an unsigned (system) transaction
+    Declared(H160),
+}
+
+/// One EIP-7702 authorization
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authorization {
+    pub chain_id: U256,
+    /// Contract whose code the authority delegates to
+    pub address: H160,
+    pub nonce: U256,
+    /// Account that signed it, `None` if the signature does not recover
+    pub authority: Option<H160>,
+}
+
+/// A decoded raw transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedTx {
+    /// EIP-2718 type byte, 0 for legacy
+    pub tx_type: u8,
+    pub hash: H256,
+    /// `None` for legacy transactions without EIP-155 replay protection
+    pub chain_id: Option<U256>,
+    pub sender: Sender,
+    pub nonce: Option<U256>,
+    /// `None` for contract creation
+    pub to: Option<H160>,
+    pub value: U256,
+    pub data: Bytes,
+    pub gas_limit: Option<U256>,
+    /// Fee fields in wei, by label
+    pub fees: Vec<(&'static str, U256)>,
+    /// Type-specific fields, by label
+    pub extra: Vec<(&'static str, String)>,
+    pub authorizations: Vec<Authorization>,
+}
+
+/// Decode a raw transaction of any supported type
+///
+/// # Arguments
+/// * `raw` - Transaction bytes as broadcast (type byte + RLP, or an RLP list for legacy)
+///
+/// # Returns
+/// * `Result<DecodedTx, Box<dyn std::error::Error>>` - Decoded fields and sender
+pub fn decode_raw(raw: &[u8]) -> Result<DecodedTx, Box<dyn std::error::Error>> {
+    let first = *raw.first().ok_or("Empty transaction")?;
+    match first {
+        0xc0..=0xff | 0x01 | 0x02 => decode_ethers(raw),
+        0x04 => decode_eip7702(raw),
+        0x64..=0x66 | 0x68..=0x6a => decode_arbitrum(raw),
+        _ => Err(format!("Unknown transaction type 0x{:02x}", first).into()),
+    }
+}
+
+/// Legacy, EIP-2930 and EIP-1559
+fn decode_ethers(raw: &[u8]) -> Result<DecodedTx, Box<dyn std::error::Error>> {
+    let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(raw))
+        .map_err(|e| format!("Invalid transaction: {}", e))?;
+    let from = signature.recover(tx.sighash())?;
+
+    let (tx_type, fees, access_list) = match &tx {
+        TypedTransaction::Legacy(tx) => (0x00, vec![("Gas Price", tx.gas_price.unwrap_or_default())], None),
+        TypedTransaction::Eip2930(tx) => (
+            0x01,
+            vec![("Gas Price", tx.tx.gas_price.unwrap_or_default())],
+            Some(tx.access_list.0.len()),
+        ),
+        TypedTransaction::Eip1559(tx) => (
+            0x02,
+            vec![
+                ("Max Fee/Gas", tx.max_fee_per_gas.unwrap_or_default()),
+                ("Max Priority Fee/Gas", tx.max_priority_fee_per_gas.unwrap_or_default()),
+            ],
+            Some(tx.access_list.0.len()),
+        ),
+    };
+    let mut extra = Vec::new();
+    if tx_type == 0x00 && tx.chain_id().is_none() {
+        extra.push(("Replay Protection", "none (pre-EIP-155 signature)".to_string()));
+    }
+    if let Some(entries) = access_list {
+        extra.push(("Access List", format!("{} address(es)", entries)));
+    }
+
+    Ok(DecodedTx {
+        tx_type,
+        hash: H256::from(keccak256(raw)),
+        chain_id: tx.chain_id().map(|c| U256::from(c.as_u64())),
+        sender: Sender::Recovered(from),
+        nonce: tx.nonce().copied(),
+        to: tx.to_addr().copied(),
+        value: tx.value().copied().unwrap_or_default(),
+        data: tx.data().cloned().unwrap_or_default(),
+        gas_limit: tx.gas().copied(),
+        fees,
+        extra,
+        authorizations: Vec::new(),
+    })
+}
+
+/// RLP field list of a typed transaction, checking the field count
+fn typed_fields(raw: &[u8], count: usize) -> Result<Rlp<'_>, Box<dyn std::error::Error>> {
+    let rlp = Rlp::new(&raw[1..]);
+    let found = rlp.item_count().map_err(|e| format!("Invalid {} transaction: {}", type_name(raw[0]), e))?;
+    if found != count {
+        return Err(format!("Invalid {} transaction: expected {} fields, found {}", type_name(raw[0]), count, found).into());
+    }
+    Ok(rlp)
+}
+
+/// Decode one field
+fn field<T: Decodable>(rlp: &Rlp, index: usize, name: &str) -> Result<T, Box<dyn std::error::Error>> {
+    Ok(rlp.val_at(index).map_err(|e| format!("Invalid {} field: {}", name, e))?)
+}
+
+/// Decode a destination that is empty for contract creation
+fn optional_address(rlp: &Rlp, index: usize, name: &str) -> Result<Option<H160>, Box<dyn std::error::Error>> {
+    let item = rlp.at(index).map_err(|e| format!("Invalid {} field: {}", name, e))?;
+    if item.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(item.as_val().map_err(|e| format!("Invalid {} field: {}", name, e))?))
+}
+
+/// keccak256 of `prefix || rlp([the first `count` items of rlp])`
+fn signing_hash(prefix: u8, rlp: &Rlp, count: usize) -> Result<H256, Box<dyn std::error::Error>> {
+    let mut stream = RlpStream::new_list(count);
+    for i in 0..count {
+        stream.append_raw(rlp.at(i)?.as_raw(), 1);
+    }
+    let mut payload = vec![prefix];
+    payload.extend_from_slice(&stream.out());
+    Ok(H256::from(keccak256(payload)))
+}
+
+/// EIP-7702: `[chain_id, nonce, max_priority_fee, max_fee, gas, to, value, data, access_list,
+/// authorization_list, y_parity, r, s]`
+fn decode_eip7702(raw: &[u8]) -> Result<DecodedTx, Box<dyn std::error::Error>> {
+    let rlp = typed_fields(raw, 13)?;
+    let signature = Signature {
+        r: field(&rlp, 11, "r")?,
+        s: field(&rlp, 12, "s")?,
+        v: field::<u64>(&rlp, 10, "y_parity")?,
+    };
+    let from = signature.recover(signing_hash(0x04, &rlp, 10)?)?;
+
+    let mut authorizations = Vec::new();
+    for item in rlp.at(9)?.iter() {
+        if item.item_count()? != 6 {
+            return Err("Invalid authorization: expected 6 fields".into());
+        }
+        let signature = Signature {
+            r: field(&item, 4, "authorization r")?,
+            s: field(&item, 5, "authorization s")?,
+            v: field::<u64>(&item, 3, "authorization y_parity")?,
+        };
+        // The authority signs keccak256(0x05 || rlp([chain_id, address, nonce]))
+        let authority = signature.recover(signing_hash(0x05, &item, 3)?).ok();
+        authorizations.push(Authorization {
+            chain_id: field(&item, 0, "authorization chain_id")?,
+            address: field(&item, 1, "authorization address")?,
+            nonce: field(&item, 2, "authorization nonce")?,
+            authority,
+        });
+    }
+
+    Ok(DecodedTx {
+        tx_type: 0x04,
+        hash: H256::from(keccak256(raw)),
+        chain_id: Some(field(&rlp, 0, "chain_id")?),
+        sender: Sender::Recovered(from),
+        nonce: Some(field(&rlp, 1, "nonce")?),
+        to: Some(field(&rlp, 5, "to")?),
+        value: field(&rlp, 6, "value")?,
+        data: Bytes::from(field::<Vec<u8>>(&rlp, 7, "data")?),
+        gas_limit: Some(field(&rlp, 4, "gas")?),
+        fees: vec![
+            ("Max Fee/Gas", field(&rlp, 3, "max_fee_per_gas")?),
+            ("Max Priority Fee/Gas", field(&rlp, 2, "max_priority_fee_per_gas")?),
+        ],
+        extra: vec![("Access List", format!("{} address(es)", rlp.at(8)?.item_count()?))],
+        authorizations,
+    })
+}
+
+/// Arbitrum system transactions (0x64–0x6a)
+fn decode_arbitrum(raw: &[u8]) -> Result<DecodedTx, Box<dyn std::error::Error>> {
+    let tx_type = raw[0];
+    let mut decoded = DecodedTx {
+        tx_type,
+        hash: H256::from(keccak256(raw)),
+        chain_id: None,
+        sender: Sender::Declared(H160::zero()),
+        nonce: None,
+        to: None,
+        value: U256::zero(),
+        data: Bytes::default(),
+        gas_limit: None,
+        fees: Vec::new(),
+        extra: Vec::new(),
+        authorizations: Vec::new(),
+    };
+
+    match tx_type {
+        // Deposit: [chain_id, l1_request_id, from, to, value]
+        0x64 => {
+            let rlp = typed_fields(raw, 5)?;
+            decoded.chain_id = Some(field(&rlp, 0, "chain_id")?);
+            decoded.extra.push(("L1 Request ID", format!("{:#x}", field::<H256>(&rlp, 1, "l1_request_id")?)));
+            decoded.sender = Sender::Declared(field(&rlp, 2, "from")?);
+            decoded.to = Some(field(&rlp, 3, "to")?);
+            decoded.value = field(&rlp, 4, "value")?;
+        }
+        // Unsigned: [chain_id, from, nonce, gas_fee_cap, gas, to, value, data]
+        0x65 => {
+            let rlp = typed_fields(raw, 8)?;
+            decoded.chain_id = Some(field(&rlp, 0, "chain_id")?);
+            decoded.sender = Sender::Declared(field(&rlp, 1, "from")?);
+            decoded.nonce = Some(field(&rlp, 2, "nonce")?);
+            decoded.fees.push(("Gas Fee Cap", field(&rlp, 3, "gas_fee_cap")?));
+            decoded.gas_limit = Some(field(&rlp, 4, "gas")?);
+            decoded.to = optional_address(&rlp, 5, "to")?;
+            decoded.value = field(&rlp, 6, "value")?;
+            decoded.data = Bytes::from(field::<Vec<u8>>(&rlp, 7, "data")?);
+        }
+        // Contract: [chain_id, request_id, from, gas_fee_cap, gas, to, value, data]
+        0x66 => {
+            let rlp = typed_fields(raw, 8)?;
+            decoded.chain_id = Some(field(&rlp, 0, "chain_id")?);
+            decoded.extra.push(("Request ID", format!("{:#x}", field::<H256>(&rlp, 1, "request_id")?)));
+            decoded.sender = Sender::Declared(field(&rlp, 2, "from")?);
+            decoded.fees.push(("Gas Fee Cap", field(&rlp, 3, "gas_fee_cap")?));
+            decoded.gas_limit = Some(field(&rlp, 4, "gas")?);
+            decoded.to = optional_address(&rlp, 5, "to")?;
+            decoded.value = field(&rlp, 6, "value")?;
+            decoded.data = Bytes::from(field::<Vec<u8>>(&rlp, 7, "data")?);
+        }
+        // Retry: [chain_id, nonce, from, gas_fee_cap, gas, to, value, data, ticket_id, refund_to,
+        // max_refund, submission_fee_refund]
+        0x68 => {
+            let rlp = typed_fields(raw, 12)?;
+            decoded.chain_id = Some(field(&rlp, 0, "chain_id")?);
+            decoded.nonce = Some(field(&rlp, 1, "nonce")?);
+            decoded.sender = Sender::Declared(field(&rlp, 2, "from")?);
+            decoded.fees.push(("Gas Fee Cap", field(&rlp, 3, "gas_fee_cap")?));
+            decoded.gas_limit = Some(field(&rlp, 4, "gas")?);
+            decoded.to = optional_address(&rlp, 5, "to")?;
+            decoded.value = field(&rlp, 6, "value")?;
+            decoded.data = Bytes::from(field::<Vec<u8>>(&rlp, 7, "data")?);
+            decoded.extra.push(("Ticket ID", format!("{:#x}", field::<H256>(&rlp, 8, "ticket_id")?)));
+            decoded.extra.push(("Refund To", checksummed(field(&rlp, 9, "refund_to")?)));
+            decoded.fees.push(("Max Refund", field(&rlp, 10, "max_refund")?));
+            decoded.fees.push(("Submission Fee Refund", field(&rlp, 11, "submission_fee_refund")?));
+        }
+        // Submit retryable: [chain_id, request_id, from, l1_base_fee, deposit_value, gas_fee_cap, gas,
+        // retry_to, retry_value, beneficiary, max_submission_fee, fee_refund_addr, retry_data]
+        0x69 => {
+            let rlp = typed_fields(raw, 13)?;
+            decoded.chain_id = Some(field(&rlp, 0, "chain_id")?);
+            decoded.extra.push(("Request ID", format!("{:#x}", field::<H256>(&rlp, 1, "request_id")?)));
+            decoded.sender = Sender::Declared(field(&rlp, 2, "from")?);
+            decoded.fees.push(("L1 Base Fee", field(&rlp, 3, "l1_base_fee")?));
+            decoded.extra.push(("Deposit Value", format!("{} ETH", Amount::from_wei(field(&rlp, 4, "deposit_value")?))));
+            decoded.fees.push(("Gas Fee Cap", field(&rlp, 5, "gas_fee_cap")?));
+            decoded.gas_limit = Some(field(&rlp, 6, "gas")?);
+            decoded.to = optional_address(&rlp, 7, "retry_to")?;
+            decoded.value = field(&rlp, 8, "retry_value")?;
+            decoded.extra.push(("Beneficiary", checksummed(field(&rlp, 9, "beneficiary")?)));
+            decoded.fees.push(("Max Submission Fee", field(&rlp, 10, "max_submission_fee")?));
+            decoded.extra.push(("Fee Refund To", checksummed(field(&rlp, 11, "fee_refund_addr")?)));
+            decoded.data = Bytes::from(field::<Vec<u8>>(&rlp, 12, "retry_data")?);
+        }
+        // Internal: [chain_id, data], sent by ArbOS at the start of each block
+        0x6a => {
+            let rlp = typed_fields(raw, 2)?;
+            decoded.chain_id = Some(field(&rlp, 0, "chain_id")?);
+            decoded.sender = Sender::Declared(ARBOS_ADDRESS.parse()?);
+            decoded.to = Some(ARBOS_ADDRESS.parse()?);
+            decoded.data = Bytes::from(field::<Vec<u8>>(&rlp, 1, "data")?);
+        }
+        _ => return Err(format!("Unknown transaction type 0x{:02x}", tx_type).into()),
+    }
+    Ok(decoded)
+}
+
+/// Decode calldata against an ABI
+///
+/// # Arguments
+/// * `abi` - Functions to match the selector against
+/// * `data` - Calldata
+///
+/// # Returns
+/// * `Option<(String, Vec<(String, Token)>)>` - Function signature and named arguments,
+///   or `None` if no function matches or the arguments do not decode
+pub fn decode_calldata(abi: &Abi, data: &[u8]) -> Option<(String, Vec<(String, Token)>)> {
+    if data.len() < 4 {
+        return None;
+    }
+    let function = abi.functions().find(|f| f.short_signature() == data[..4])?;
+    let tokens = function.decode_input(&data[4..]).ok()?;
+    let args = function
+        .inputs
+        .iter()
+        .zip(tokens)
+        .enumerate()
+        .map(|(i, (param, token))| {
+            let name = if param.name.is_empty() { format!("arg{}", i) } else { param.name.clone() };
+            (name, token)
+        })
+        .collect();
+    let types: Vec<String> = function.inputs.iter().map(|p| p.kind.to_string()).collect();
+    Some((format!("{}({})", function.name, types.join(",")), args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_wallet;
+    use crate::token::erc20_abi;
+    use ethers::signers::Signer;
+    use ethers::types::{Eip1559TransactionRequest, TransactionRequest};
+
+    #[test]
+    fn test_decode_signed_types() {
+        let wallet = test_wallet();
+        let legacy: TypedTransaction = TransactionRequest::new()
+            .to(H160::repeat_byte(0x22))
+            .value(5u64)
+            .gas(21_000u64)
+            .gas_price(100u64)
+            .nonce(3u64)
+            .chain_id(421614u64)
+            .into();
+        let eip1559: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(H160::repeat_byte(0x22))
+            .value(5u64)
+            .gas(21_000u64)
+            .max_fee_per_gas(200u64)
+            .max_priority_fee_per_gas(10u64)
+            .nonce(4u64)
+            .chain_id(421614u64)
+            .into();
+
+        for (tx, tx_type) in [(legacy, 0x00), (eip1559, 0x02)] {
+            let raw = tx.rlp_signed(&wallet.sign_transaction_sync(&tx).unwrap());
+            let decoded = decode_raw(&raw).unwrap();
+            assert_eq!(decoded.tx_type, tx_type);
+            assert_eq!(decoded.sender, Sender::Recovered(wallet.address()));
+            assert_eq!(decoded.chain_id, Some(U256::from(421614)));
+            assert_eq!(decoded.value, U256::from(5));
+            assert_eq!(decoded.hash, H256::from(keccak256(&raw)));
+        }
+    }
+
+    #[test]
+    fn test_decode_eip7702() {
+        let wallet = test_wallet();
+        let delegate = H160::repeat_byte(0x77);
+
+        // Authorization signed by the same account
+        let mut auth = RlpStream::new_list(3);
+        auth.append(&U256::from(421614)).append(&delegate).append(&U256::from(9));
+        let auth_sig = wallet.sign_hash(H256::from(keccak256([&[0x05u8][..], &auth.out()].concat()))).unwrap();
+
+        let fields = |stream: &mut RlpStream| {
+            stream.append(&421614u64).append(&9u64).append(&10u64).append(&200u64).append(&50_000u64);
+            stream.append(&wallet.address()).append(&0u64).append(&Vec::<u8>::new());
+            stream.begin_list(0);
+            stream.begin_list(1);
+            stream.begin_list(6);
+            stream.append(&U256::from(421614)).append(&delegate).append(&U256::from(9));
+            stream.append(&(auth_sig.v - 27)).append(&auth_sig.r).append(&auth_sig.s);
+        };
+        let mut unsigned = RlpStream::new_list(10);
+        fields(&mut unsigned);
+        let sig = wallet.sign_hash(H256::from(keccak256([&[0x04u8][..], &unsigned.out()].concat()))).unwrap();
+        let mut signed = RlpStream::new_list(13);
+        fields(&mut signed);
+        signed.append(&(sig.v - 27)).append(&sig.r).append(&sig.s);
+        let raw = [&[0x04u8][..], &signed.out()].concat();
+
+        let decoded = decode_raw(&raw).unwrap();
+        assert_eq!(decoded.tx_type, 0x04);
+        assert_eq!(decoded.sender, Sender::Recovered(wallet.address()));
+        assert_eq!(decoded.nonce, Some(U256::from(9)));
+        assert_eq!(
+            decoded.authorizations,
+            vec![Authorization {
+                chain_id: U256::from(421614),
+                address: delegate,
+                nonce: U256::from(9),
+                authority: Some(wallet.address()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decode_arbitrum_types() {
+        let from = H160::repeat_byte(0x11);
+        let to = H160::repeat_byte(0x22);
+
+        let mut deposit = RlpStream::new_list(5);
+        deposit.append(&421614u64).append(&H256::repeat_byte(0xaa)).append(&from).append(&to).append(&1_000u64);
+        let deposit = [&[0x64u8][..], &deposit.out()].concat();
+        let decoded = decode_raw(&deposit).unwrap();
+        assert_eq!(type_name(decoded.tx_type), "Arbitrum deposit");
+        assert_eq!(decoded.sender, Sender::Declared(from));
+        assert_eq!((decoded.to, decoded.value), (Some(to), U256::from(1_000)));
+
+        let data = erc20_abi()
+            .function("transfer")
+            .unwrap()
+            .encode_input(&[Token::Address(to), Token::Uint(U256::from(7))])
+            .unwrap();
+        let mut retryable = RlpStream::new_list(13);
+        retryable.append(&421614u64).append(&H256::repeat_byte(0xbb)).append(&from);
+        retryable.append(&100u64).append(&5_000u64).append(&120u64).append(&90_000u64);
+        retryable.append(&to).append(&0u64).append(&from).append(&300u64).append(&from).append(&data);
+        let decoded = decode_raw(&[&[0x69u8][..], &retryable.out()].concat()).unwrap();
+        assert_eq!(decoded.gas_limit, Some(U256::from(90_000)));
+        assert_eq!(decoded.to, Some(to));
+        assert!(decoded.fees.contains(&("Max Submission Fee", U256::from(300))));
+        let (signature, args) = decode_calldata(&erc20_abi(), &decoded.data).unwrap();
+        assert_eq!(signature, "transfer(address,uint256)");
+        assert_eq!(args[1].1, Token::Uint(U256::from(7)));
+
+        let mut internal = RlpStream::new_list(2);
+        internal.append(&421614u64).append(&vec![0x6b, 0xf6, 0xa4, 0x2d]);
+        let decoded = decode_raw(&[&[0x6au8][..], &internal.out()].concat()).unwrap();
+        assert_eq!(decoded.sender, Sender::Declared(ARBOS_ADDRESS.parse().unwrap()));
+        assert!(decode_calldata(&erc20_abi(), &decoded.data).is_none());
+
+        // Wrong field count and unknown types are rejected
+        assert!(decode_raw(&[&[0x6au8][..], &deposit[1..]].concat()).is_err());
+        assert!(decode_raw(&[0x67, 0xc0]).is_err());
+    }
+}
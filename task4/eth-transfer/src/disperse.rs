@@ -0,0 +1,255 @@
+//! Multi-recipient payouts in one transaction through a disperse contract
+//!
+//! Uses the disperse.app interface: `disperseEther` forwards `msg.value`
+//! to every recipient, `disperseToken` pulls the total with `transferFrom`
+//! (so the contract must be approved first) and pays it out. The contract
+//! source is in `contracts/Disperse.sol`; networks without a deployment can
+//! get one from a compiled artifact.
+
+use crate::batch::BatchRow;
+use crate::token::erc20_abi;
+use arb_common::address::parse_address;
+use arb_common::amount::Amount;
+use ethers::abi::{parse_abi, Abi, Token};
+use ethers::prelude::*;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// disperse.app functions
+const DISPERSE_ABI: &[&str] = &[
+    "function disperseEther(address[] recipients, uint256[] values) external payable",
+    "function disperseToken(address token, address[] recipients, uint256[] values) external",
+];
+
+/// Address disperse.app is deployed at on most networks
+pub const KNOWN_DISPERSE_ADDRESS: &str = "0xD152f549545093347A162Dce210e7293f1452150";
+
+/// Disperse ABI with the ERC20 errors merged in, so token reverts decode
+pub fn disperse_abi() -> Abi {
+    let mut abi = parse_abi(DISPERSE_ABI).expect("Disperse ABI is valid");
+    abi.errors.extend(erc20_abi().errors);
+    abi
+}
+
+/// Disperse contract to use: `--contract`, else `DISPERSE_ADDRESS`, else the known deployment
+pub fn disperse_address(arg: Option<&str>) -> Result<H160, Box<dyn std::error::Error>> {
+    let address = arg
+        .map(String::from)
+        .or_else(|| env::var("DISPERSE_ADDRESS").ok())
+        .unwrap_or_else(|| KNOWN_DISPERSE_ADDRESS.to_string());
+    Ok(parse_address(&address)?.address)
+}
+
+/// Whether there is contract code at an address
+///
+/// # Arguments
+/// * `address` - Contract address
+///
+/// # Returns
+/// * `Result<bool, Box<dyn std::error::Error>>` - True if code is deployed
+pub async fn is_deployed(address: H160) -> Result<bool, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    Ok(!provider.get_code(address, None).await?.is_empty())
+}
+
+/// Creation bytecode from a build artifact
+///
+/// Accepts a JSON artifact with a `bytecode` field (a hex string, or an
+/// object with `object` as solc and Foundry write it) or a plain hex file.
+///
+/// # Arguments
+/// * `path` - Artifact file
+///
+/// # Returns
+/// * `Result<Bytes, Box<dyn std::error::Error>>` - Init code
+pub fn load_init_code(path: &Path) -> Result<Bytes, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path).map_err(|_| format!("Failed to read artifact: {}", path.display()))?;
+    let hex = match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(value) => {
+            let bytecode = value.get("bytecode").ok_or("Artifact has no bytecode field")?;
+            bytecode
+                .as_str()
+                .or_else(|| bytecode.get("object").and_then(|o| o.as_str()))
+                .ok_or("Artifact bytecode is not a hex string")?
+                .to_string()
+        }
+        Err(_) => text.trim().to_string(),
+    };
+    let code: Bytes = hex
+        .parse()
+        .map_err(|e| format!("Invalid bytecode in {}: {}", path.display(), e))?;
+    if code.is_empty() {
+        return Err(format!("Artifact {} has empty bytecode (abstract contract or interface?)", path.display()).into());
+    }
+    Ok(code)
+}
+
+/// One disperse call: a single asset paid to many recipients
+#[derive(Debug, Clone, PartialEq)]
+pub struct DispersePlan {
+    /// ERC20 token, or `None` for ETH
+    pub token: Option<H160>,
+    pub recipients: Vec<H160>,
+    pub values: Vec<U256>,
+    pub total: U256,
+}
+
+/// Turn validated batch rows into a disperse call
+///
+/// # Arguments
+/// * `rows` - Rows from `batch::parse_batch`
+/// * `amounts` - Their parsed amounts
+///
+/// # Returns
+/// * `Result<DispersePlan, String>` - Plan, or an error if the rows mix assets or the total overflows
+pub fn plan(rows: &[BatchRow], amounts: &[Amount]) -> Result<DispersePlan, String> {
+    let token = rows.first().ok_or("no payouts")?.token;
+    if let Some(row) = rows.iter().find(|r| r.token != token) {
+        return Err(format!(
+            "line {}: a disperse transaction pays a single asset; split ETH and each token into separate files",
+            row.line
+        ));
+    }
+    let values: Vec<U256> = amounts.iter().map(Amount::raw).collect();
+    let mut total = U256::zero();
+    for (row, value) in rows.iter().zip(&values) {
+        total = total
+            .checked_add(*value)
+            .ok_or_else(|| format!("line {}: total amount overflows 256 bits", row.line))?;
+    }
+    Ok(DispersePlan {
+        token,
+        recipients: rows.iter().map(|r| r.recipient).collect(),
+        total,
+        values,
+    })
+}
+
+/// Calldata for a plan
+///
+/// # Returns
+/// * `Result<Bytes, Box<dyn std::error::Error>>` - `disperseEther` or `disperseToken` call
+pub fn encode_disperse(plan: &DispersePlan) -> Result<Bytes, Box<dyn std::error::Error>> {
+    let abi = disperse_abi();
+    let recipients = Token::Array(plan.recipients.iter().map(|r| Token::Address(*r)).collect());
+    let values = Token::Array(plan.values.iter().map(|v| Token::Uint(*v)).collect());
+    let data = match plan.token {
+        Some(token) => abi
+            .function("disperseToken")?
+            .encode_input(&[Token::Address(token), recipients, values])?,
+        None => abi.function("disperseEther")?.encode_input(&[recipients, values])?,
+    };
+    Ok(Bytes::from(data))
+}
+
+/// Gas the same payouts would need as separate transfers
+///
+/// # Arguments
+/// * `sender` - Paying account
+/// * `plan` - Payouts
+///
+/// # Returns
+/// * `Result<U256, Box<dyn std::error::Error>>` - Sum of the individual gas estimates
+pub async fn estimate_individual(sender: H160, plan: &DispersePlan) -> Result<U256, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let transfer = erc20_abi();
+
+    let mut total = U256::zero();
+    for (recipient, value) in plan.recipients.iter().zip(&plan.values) {
+        let tx = match plan.token {
+            Some(token) => TransactionRequest::new().from(sender).to(token).data(
+                transfer
+                    .function("transfer")?
+                    .encode_input(&[Token::Address(*recipient), Token::Uint(*value)])?,
+            ),
+            None => TransactionRequest::new().from(sender).to(*recipient).value(*value),
+        };
+        total += provider.estimate_gas(&tx.into(), None).await?;
+    }
+    Ok(total)
+}
+
+/// Rough gas of `disperseToken` while it cannot be simulated
+///
+/// Until the approval is mined the call reverts, so it is derived from the
+/// separate transfers: the 21,000 base cost is paid once, and the token
+/// work is paid once per recipient plus once for pulling the total in.
+///
+/// # Arguments
+/// * `individual` - Gas from `estimate_individual`
+/// * `recipients` - Number of payouts
+///
+/// # Returns
+/// * `U256` - Estimated gas
+pub fn estimate_token_disperse(individual: U256, recipients: usize) -> U256 {
+    let base = U256::from(21_000);
+    if recipients == 0 {
+        return base;
+    }
+    let per_transfer = (individual / recipients).saturating_sub(base);
+    base + per_transfer * (recipients + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::id;
+
+    fn row(line: usize, byte: u8, token: Option<H160>) -> BatchRow {
+        BatchRow { line, recipient: H160::repeat_byte(byte), amount: "1".to_string(), token }
+    }
+
+    #[test]
+    fn test_plan_and_encode() {
+        let rows = vec![row(1, 0x11, None), row(2, 0x22, None)];
+        let amounts = vec![Amount::from_wei(U256::from(5)), Amount::from_wei(U256::from(7))];
+        let plan = plan(&rows, &amounts).unwrap();
+        assert_eq!(plan.total, U256::from(12));
+
+        let data = encode_disperse(&plan).unwrap();
+        assert_eq!(&data[..4], &id("disperseEther(address[],uint256[])"));
+
+        let token = H160::repeat_byte(0x33);
+        let plan = DispersePlan { token: Some(token), ..plan };
+        let data = encode_disperse(&plan).unwrap();
+        assert_eq!(&data[..4], &id("disperseToken(address,address[],uint256[])"));
+        assert_eq!(&data[16..36], token.as_bytes());
+
+        let mixed = vec![row(1, 0x11, None), row(4, 0x22, Some(token))];
+        let err = super::plan(&mixed, &amounts).unwrap_err();
+        assert!(err.starts_with("line 4:"), "{}", err);
+
+        let huge = vec![Amount::from_wei(U256::MAX), Amount::from_wei(U256::one())];
+        assert_eq!(super::plan(&rows, &huge).unwrap_err(), "line 2: total amount overflows 256 bits");
+
+        // Three 50k-gas token transfers: one base cost, four transfers' worth of work
+        assert_eq!(estimate_token_disperse(U256::from(150_000), 3), U256::from(21_000 + 4 * 29_000));
+    }
+
+    #[test]
+    fn test_load_init_code() {
+        let dir = env::temp_dir();
+        let id = std::process::id();
+        let cases = [
+            (format!("disperse-{}-hardhat.json", id), r#"{ "bytecode": "0x6080604052" }"#),
+            (format!("disperse-{}-foundry.json", id), r#"{ "bytecode": { "object": "0x6080604052" } }"#),
+            (format!("disperse-{}.hex", id), "0x6080604052\n"),
+        ];
+        for (name, content) in &cases {
+            let path = dir.join(name);
+            fs::write(&path, content).unwrap();
+            assert_eq!(load_init_code(&path).unwrap(), Bytes::from(vec![0x60, 0x80, 0x60, 0x40, 0x52]), "{}", name);
+            fs::remove_file(&path).ok();
+        }
+
+        let path = dir.join(format!("disperse-{}-empty.json", id));
+        fs::write(&path, r#"{ "bytecode": "0x" }"#).unwrap();
+        assert!(load_init_code(&path).is_err());
+        fs::remove_file(&path).ok();
+    }
+}
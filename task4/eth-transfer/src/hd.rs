@@ -0,0 +1,189 @@
+use ethers::core::rand::thread_rng;
+use ethers::prelude::*;
+use ethers::signers::coins_bip39::{English, Mnemonic};
+use std::env;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// BIP-44 path prefix for Ethereum accounts; the address index is appended
+pub const DERIVATION_PATH_PREFIX: &str = "m/44'/60'/0'/0";
+
+/// Full derivation path for an address index, e.g. `m/44'/60'/0'/0/3`
+pub fn derivation_path(index: u32) -> String {
+    format!("{}/{}", DERIVATION_PATH_PREFIX, index)
+}
+
+/// Collapse whitespace and lowercase a phrase so copy-pasted input validates
+fn normalize_phrase(phrase: &str) -> Zeroizing<String> {
+    Zeroizing::new(
+        phrase
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Generate a new random BIP-39 mnemonic
+///
+/// # Arguments
+/// * `word_count` - 12, 15, 18, 21 or 24 words
+///
+/// # Returns
+/// * `Result<Zeroizing<String>, Box<dyn std::error::Error>>` - The mnemonic phrase
+pub fn generate_mnemonic(word_count: usize) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    let mnemonic = Mnemonic::<English>::new_with_count(&mut thread_rng(), word_count)
+        .map_err(|e| format!("Failed to generate mnemonic: {}", e))?;
+    Ok(Zeroizing::new(mnemonic.to_phrase()))
+}
+
+/// Check that a phrase uses the English word list and has a valid checksum
+///
+/// # Arguments
+/// * `phrase` - The mnemonic phrase
+///
+/// # Returns
+/// * `Result<(), Box<dyn std::error::Error>>` - Ok if the mnemonic is valid
+pub fn validate_mnemonic(phrase: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let phrase = normalize_phrase(phrase);
+    Mnemonic::<English>::new_from_phrase(&phrase)
+        .map(|_| ())
+        .map_err(|e| format!("Invalid mnemonic: {}", e).into())
+}
+
+/// Derive the wallet at `m/44'/60'/0'/0/<index>`
+///
+/// # Arguments
+/// * `phrase` - The mnemonic phrase
+/// * `index` - Address index
+/// * `passphrase` - Optional BIP-39 passphrase ("25th word")
+///
+/// # Returns
+/// * `Result<LocalWallet, Box<dyn std::error::Error>>` - Derived wallet
+pub fn derive_wallet(
+    phrase: &str,
+    index: u32,
+    passphrase: Option<&str>,
+) -> Result<LocalWallet, Box<dyn std::error::Error>> {
+    validate_mnemonic(phrase)?;
+    let phrase = normalize_phrase(phrase);
+
+    let mut builder = MnemonicBuilder::<English>::default()
+        .phrase(phrase.as_str())
+        .derivation_path(&derivation_path(index))?;
+    if let Some(passphrase) = passphrase {
+        builder = builder.password(passphrase);
+    }
+    Ok(builder.build()?)
+}
+
+/// Derive the first `count` addresses
+///
+/// # Arguments
+/// * `phrase` - The mnemonic phrase
+/// * `count` - Number of addresses, starting at index 0
+/// * `passphrase` - Optional BIP-39 passphrase
+///
+/// # Returns
+/// * `Result<Vec<H160>, Box<dyn std::error::Error>>` - Addresses in index order
+pub fn derive_addresses(
+    phrase: &str,
+    count: u32,
+    passphrase: Option<&str>,
+) -> Result<Vec<H160>, Box<dyn std::error::Error>> {
+    (0..count)
+        .map(|index| derive_wallet(phrase, index, passphrase).map(|w| w.address()))
+        .collect()
+}
+
+/// Read the mnemonic from `MNEMONIC_PATH`, or prompt for it
+///
+/// # Returns
+/// * `Result<Zeroizing<String>, Box<dyn std::error::Error>>` - The mnemonic phrase
+pub fn read_mnemonic() -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    match env::var("MNEMONIC_PATH") {
+        Ok(path) => {
+            let phrase = Zeroizing::new(
+                fs::read_to_string(Path::new(&path))
+                    .map_err(|_| format!("Failed to read mnemonic file: {}", path))?,
+            );
+            Ok(normalize_phrase(&phrase))
+        }
+        Err(_) => {
+            let phrase = Zeroizing::new(rpassword::prompt_password("Mnemonic: ")?);
+            Ok(normalize_phrase(&phrase))
+        }
+    }
+}
+
+/// Optional BIP-39 passphrase from the file at `MNEMONIC_PASSPHRASE_FILE`
+pub fn read_mnemonic_passphrase() -> Result<Option<Zeroizing<String>>, Box<dyn std::error::Error>> {
+    match env::var("MNEMONIC_PASSPHRASE_FILE") {
+        Ok(path) => {
+            let contents = Zeroizing::new(
+                fs::read_to_string(&path)
+                    .map_err(|_| format!("Failed to read passphrase file: {}", path))?,
+            );
+            Ok(Some(Zeroizing::new(contents.lines().next().unwrap_or("").to_string())))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Address index to sign from, from `MNEMONIC_INDEX` (default 0)
+pub fn mnemonic_index() -> Result<u32, Box<dyn std::error::Error>> {
+    match env::var("MNEMONIC_INDEX") {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid MNEMONIC_INDEX: {}", value).into()),
+        Err(_) => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Well-known test mnemonic (Hardhat / Anvil default accounts)
+    const TEST_PHRASE: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn test_derive_known_addresses() {
+        let addresses = derive_addresses(TEST_PHRASE, 2, None).unwrap();
+        assert_eq!(
+            format!("{:?}", addresses[0]),
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        assert_eq!(
+            format!("{:?}", addresses[1]),
+            "0x70997970c51812dc3a010c7d01b50e0d17dc79c8"
+        );
+
+        // Extra whitespace and capitals are tolerated
+        let messy = "  Test test test test test test test test test test test   JUNK ";
+        assert_eq!(derive_wallet(messy, 0, None).unwrap().address(), addresses[0]);
+
+        // A BIP-39 passphrase yields different accounts
+        assert_ne!(derive_wallet(TEST_PHRASE, 0, Some("extra")).unwrap().address(), addresses[0]);
+    }
+
+    #[test]
+    fn test_validate_mnemonic() {
+        assert!(validate_mnemonic(TEST_PHRASE).is_ok());
+        // Bad checksum word
+        assert!(validate_mnemonic("test test test test test test test test test test test test").is_err());
+        // Not in the word list
+        assert!(validate_mnemonic("test test test test test test test test test test test xyzzy").is_err());
+    }
+
+    #[test]
+    fn test_generate_mnemonic() {
+        let phrase = generate_mnemonic(24).unwrap();
+        assert_eq!(phrase.split(' ').count(), 24);
+        assert!(validate_mnemonic(&phrase).is_ok());
+        assert!(generate_mnemonic(13).is_err());
+        assert_eq!(derivation_path(7), "m/44'/60'/0'/0/7");
+    }
+}
@@ -0,0 +1,219 @@
+use ethers::core::rand::thread_rng;
+use ethers::prelude::*;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// Where the keystore passphrase comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum PassphraseSource {
+    /// Ask on the terminal without echo
+    Prompt,
+    /// Read the first line of a file
+    File(PathBuf),
+}
+
+impl PassphraseSource {
+    /// `KEYSTORE_PASSWORD_FILE` if set, otherwise an interactive prompt
+    pub fn from_env() -> Self {
+        match env::var("KEYSTORE_PASSWORD_FILE") {
+            Ok(path) => PassphraseSource::File(PathBuf::from(path)),
+            Err(_) => PassphraseSource::Prompt,
+        }
+    }
+}
+
+/// Read a passphrase from the given source
+///
+/// # Arguments
+/// * `source` - Prompt or passphrase file
+/// * `prompt` - Text shown when prompting
+///
+/// # Returns
+/// * `Result<Zeroizing<String>, Box<dyn std::error::Error>>` - Passphrase, wiped on drop
+pub fn read_passphrase(
+    source: &PassphraseSource,
+    prompt: &str,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    match source {
+        PassphraseSource::Prompt => Ok(Zeroizing::new(rpassword::prompt_password(prompt)?)),
+        PassphraseSource::File(path) => {
+            let contents = Zeroizing::new(
+                fs::read_to_string(path)
+                    .map_err(|_| format!("Failed to read passphrase file: {}", path.display()))?,
+            );
+            Ok(Zeroizing::new(first_line(&contents).to_string()))
+        }
+    }
+}
+
+/// Prompt for a new passphrase twice and make sure both entries match
+///
+/// # Arguments
+/// * `source` - Prompt or passphrase file (a file is read once)
+///
+/// # Returns
+/// * `Result<Zeroizing<String>, Box<dyn std::error::Error>>` - New passphrase
+pub fn read_new_passphrase(source: &PassphraseSource) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    let passphrase = read_passphrase(source, "New passphrase: ")?;
+    if passphrase.is_empty() {
+        return Err("Passphrase must not be empty".into());
+    }
+    if *source == PassphraseSource::Prompt {
+        let confirm = read_passphrase(source, "Repeat passphrase: ")?;
+        if *confirm != *passphrase {
+            return Err("Passphrases do not match".into());
+        }
+    }
+    Ok(passphrase)
+}
+
+/// First line of a passphrase file, without the line ending
+fn first_line(contents: &str) -> &str {
+    contents.lines().next().unwrap_or("")
+}
+
+/// Decrypt a Web3 Secret Storage v3 keystore (scrypt or pbkdf2)
+///
+/// # Arguments
+/// * `path` - Path to the keystore JSON file
+/// * `source` - Where to read the passphrase from
+///
+/// # Returns
+/// * `Result<LocalWallet, Box<dyn std::error::Error>>` - Wallet; its signing key is zeroized on drop
+pub fn decrypt_keystore(
+    path: &Path,
+    source: &PassphraseSource,
+) -> Result<LocalWallet, Box<dyn std::error::Error>> {
+    let prompt = format!("Passphrase for {}: ", path.display());
+    let passphrase = read_passphrase(source, &prompt)?;
+    LocalWallet::decrypt_keystore(path, passphrase.as_bytes())
+        .map_err(|e| format!("Failed to decrypt keystore {}: {}", path.display(), e).into())
+}
+
+/// Create a new random key and store it as an encrypted keystore
+///
+/// # Arguments
+/// * `dir` - Directory to write the keystore into
+/// * `name` - Optional file name (defaults to a UUID)
+/// * `source` - Where to read the new passphrase from
+///
+/// # Returns
+/// * `Result<(H160, PathBuf), Box<dyn std::error::Error>>` - Address and keystore path
+pub fn create_keystore(
+    dir: &Path,
+    name: Option<&str>,
+    source: &PassphraseSource,
+) -> Result<(H160, PathBuf), Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+    let passphrase = read_new_passphrase(source)?;
+    let (wallet, uuid) = LocalWallet::new_keystore(dir, &mut thread_rng(), passphrase.as_bytes(), name)?;
+    Ok((wallet.address(), dir.join(name.unwrap_or(&uuid))))
+}
+
+/// Re-encrypt a keystore under a new passphrase
+///
+/// The new keystore is written next to the old one and then renamed over
+/// it, so a failure part-way never leaves the key without a valid file.
+///
+/// # Arguments
+/// * `path` - Keystore to update
+/// * `old` - Source of the current passphrase
+/// * `new` - Source of the new passphrase
+///
+/// # Returns
+/// * `Result<H160, Box<dyn std::error::Error>>` - Address of the key
+pub fn change_password(
+    path: &Path,
+    old: &PassphraseSource,
+    new: &PassphraseSource,
+) -> Result<H160, Box<dyn std::error::Error>> {
+    let wallet = decrypt_keystore(path, old)?;
+    let passphrase = read_new_passphrase(new)?;
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid keystore path")?;
+    let tmp_name = format!(".{}.tmp", file_name);
+
+    let secret = Zeroizing::new(wallet.signer().to_bytes());
+    LocalWallet::encrypt_keystore(dir, &mut thread_rng(), &secret[..], passphrase.as_bytes(), Some(&tmp_name))?;
+    fs::rename(dir.join(&tmp_name), path)?;
+
+    Ok(wallet.address())
+}
+
+/// Parse a plaintext hex private key
+///
+/// Only reachable when the user opted in with `ALLOW_PLAINTEXT_KEY=1`.
+///
+/// # Arguments
+/// * `key` - Hex private key, with or without 0x prefix
+///
+/// # Returns
+/// * `Result<LocalWallet, Box<dyn std::error::Error>>` - Wallet
+pub fn wallet_from_plaintext(key: &str) -> Result<LocalWallet, Box<dyn std::error::Error>> {
+    let clean_key = key.trim().strip_prefix("0x").unwrap_or(key.trim());
+    let bytes = Zeroizing::new(
+        ethers::utils::hex::decode(clean_key).map_err(|_| "Invalid private key format".to_string())?,
+    );
+    if bytes.len() != 32 {
+        return Err("Invalid private key format".into());
+    }
+    LocalWallet::from_bytes(&bytes).map_err(|_| "Failed to create wallet from private key".into())
+}
+
+/// Whether plaintext keys from `PRIVATE_KEY` are allowed
+pub fn plaintext_key_allowed() -> bool {
+    matches!(
+        env::var("ALLOW_PLAINTEXT_KEY").as_deref(),
+        Ok("1") | Ok("true") | Ok("yes")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TEST_KEY;
+
+    #[test]
+    fn test_keystore_roundtrip_and_change_password() {
+        let dir = env::temp_dir().join(format!("eth-transfer-keystore-{}", std::process::id()));
+        let old_file = dir.join("old.txt");
+        let new_file = dir.join("new.txt");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&old_file, "correct horse\n").unwrap();
+        fs::write(&new_file, "battery staple\n").unwrap();
+        let old = PassphraseSource::File(old_file);
+        let new = PassphraseSource::File(new_file);
+
+        let (address, path) = create_keystore(&dir, Some("key.json"), &old).unwrap();
+        assert_eq!(decrypt_keystore(&path, &old).unwrap().address(), address);
+
+        assert_eq!(change_password(&path, &old, &new).unwrap(), address);
+        assert!(decrypt_keystore(&path, &old).is_err());
+        assert_eq!(decrypt_keystore(&path, &new).unwrap().address(), address);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_wallet_from_plaintext() {
+        let wallet = wallet_from_plaintext(TEST_KEY).unwrap();
+        assert_eq!(
+            format!("{:?}", wallet.address()),
+            "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23"
+        );
+        assert!(wallet_from_plaintext("0x1234").is_err());
+        assert!(wallet_from_plaintext("not a key").is_err());
+    }
+
+    #[test]
+    fn test_first_line() {
+        assert_eq!(first_line("secret\r\nignored"), "secret");
+        assert_eq!(first_line(""), "");
+    }
+}
@@ -1,159 +1,66 @@
+mod accounts;
+mod audit;
+mod batch;
+mod decode;
+mod disperse;
+mod hd;
+mod keystore;
+mod message;
+mod migrate;
+mod nonce;
+mod offline;
+mod policy;
+mod token;
+mod tracker;
+mod typed_data;
 mod transfer;
+#[cfg(test)]
+mod test_support;
 
-use transfer::{
-    validate_address,
-    get_balance,
-    transfer_eth,
-    estimate_transfer_fee,
-    get_transaction_receipt,
-};
+mod commands;
 
-use ethers::types::U64;
-use std::thread;
-use std::time::Duration;
+use std::env;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load environment variables
     dotenv::dotenv().ok();
 
-    println!("=========================================================");
-    println!("         Arbitrum Sepolia ETH Transfer");
-    println!("=========================================================\n");
-
-    // Define addresses
-    let sender_addr_str = "0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439";
-    let receiver_addr_str = "0x7292dD72151DaCFBbE76305db1C8Ab1928E922E4";
-
-    println!("📋 Transaction Details:");
-    println!("   From: {}", sender_addr_str);
-    println!("   To:   {}", receiver_addr_str);
-    println!("   Network: Arbitrum Sepolia (Chain ID: 421614)\n");
-
-    // Validate addresses
-    println!("🔍 Validating addresses...");
-    let sender_address = validate_address(sender_addr_str)?;
-    let receiver_address = validate_address(receiver_addr_str)?;
-    println!("   ✅ Both addresses are valid\n");
-
-    // Check sender balance
-    println!("💰 Checking sender balance...");
-    let sender_balance = get_balance(sender_address).await?;
-    println!("   Sender Balance: {} ETH\n", sender_balance);
-
-    // Parse balance to check if sufficient
-    let sender_balance_float: f64 = sender_balance.parse()
-        .map_err(|_| "Failed to parse balance")?;
-
-    // Amount to transfer
-    let transfer_amount = "0.0001"; // 0.0001 ETH
-
-    // Check if balance is sufficient
-    if sender_balance_float < 0.001 {
-        println!("⚠️  Warning: Sender balance is very low ({} ETH)", sender_balance);
-        println!("   You may need more ETH to cover gas fees.\n");
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // Global: sign past transfer policy violations, logging the reason
+    if let Some(i) = args.iter().position(|a| a == "--override-policy") {
+        let reason = args.get(i + 1).filter(|r| !r.starts_with("--")).cloned();
+        let reason = reason.ok_or("--override-policy needs a reason")?;
+        args.drain(i..i + 2);
+        policy::set_override(reason);
     }
-
-    println!("💸 Transfer Amount: {} ETH\n", transfer_amount);
-
-    // Estimate gas fee
-    println!("⛽ Estimating gas fee...");
-    let gas_info = estimate_transfer_fee().await?;
-    println!("   {}", gas_info);
-    println!();
-
-    // Confirm before proceeding
-    println!("⚠️  Ready to send transaction:");
-    println!("   From:    {}", sender_addr_str);
-    println!("   To:      {}", receiver_addr_str);
-    println!("   Amount:  {} ETH", transfer_amount);
-    println!();
-    println!("   Press Ctrl+C to cancel, or wait 3 seconds to proceed...");
-    thread::sleep(Duration::from_secs(3));
-    println!();
-
-    // Execute transfer
-    println!("🚀 Executing transfer...\n");
-    match transfer_eth(sender_address, receiver_address, transfer_amount).await {
-        Ok(tx_hash) => {
-            println!("---------------------------------------------------------");
-            println!("✅ Transaction Submitted Successfully!");
-            println!("---------------------------------------------------------");
-            println!("Transaction Hash: {}", tx_hash);
-            println!("---------------------------------------------------------");
-            println!();
-            println!("🔗 View on Arbitrum Sepolia Explorer:");
-            println!("   https://sepolia.arbiscan.io/tx/{}", tx_hash);
-            println!();
-
-            // Wait for transaction confirmation
-            println!("⏳ Waiting for transaction confirmation (up to 60 seconds)...");
-            let mut retries = 0;
-            let max_retries = 12; // 12 * 5 = 60 seconds
-
-            while retries < max_retries {
-                thread::sleep(Duration::from_secs(5));
-
-                match get_transaction_receipt(&tx_hash).await {
-                    Ok(Some(receipt)) => {
-                        println!();
-                        println!("---------------------------------------------------------");
-                        println!("✅ Transaction Confirmed!");
-                        println!("---------------------------------------------------------");
-                        println!("Block Number: {}", receipt.block_number.unwrap_or_default());
-                        println!("Gas Used: {}", receipt.gas_used.unwrap_or_default());
-                        println!("Status: {}", if receipt.status.unwrap_or_default() == U64::from(1) {
-                            "Success ✓"
-                        } else {
-                            "Failed ✗"
-                        });
-                        println!("---------------------------------------------------------");
-                        println!();
-
-                        // Check final balances
-                        println!("📊 Final Balances:");
-                        let new_sender_balance = get_balance(sender_address).await?;
-                        let new_receiver_balance = get_balance(receiver_address).await?;
-                        println!("   Sender:   {} ETH", new_sender_balance);
-                        println!("   Receiver: {} ETH", new_receiver_balance);
-                        println!();
-
-                        println!("🎉 Transfer completed successfully!");
-                        break;
-                    }
-                    Ok(None) => {
-                        retries += 1;
-                        print!(".");
-                        // Flush stdout to show the dots
-                        use std::io::Write;
-                        std::io::stdout().flush().ok();
-                    }
-                    Err(e) => {
-                        println!();
-                        println!("⚠️  Error checking receipt: {}", e);
-                        println!("   Transaction may still be pending. Check the explorer link above.");
-                        break;
-                    }
-                }
-            }
-
-            if retries >= max_retries {
-                println!();
-                println!("⏱️  Transaction not confirmed within 60 seconds.");
-                println!("   It may still be processing. Check the explorer link above.");
-            }
-        }
-        Err(e) => {
-            eprintln!("❌ Transaction failed: {}", e);
-            println!();
-            println!("Troubleshooting tips:");
-            println!("1. Check that PRIVATE_KEY is correctly set in .env file");
-            println!("2. Ensure the sender address has sufficient ETH balance");
-            println!("3. Verify the RPC endpoint is accessible");
-            println!("4. Check that you're using the correct private key for the sender address");
-            return Err(e);
+    // Global: audit log file, instead of AUDIT_LOG
+    if let Some(i) = args.iter().position(|a| a == "--audit-log") {
+        let path = args.get(i + 1).filter(|p| !p.starts_with("--")).cloned();
+        let path = path.ok_or("--audit-log needs a file")?;
+        args.drain(i..i + 2);
+        audit::set_log_path(path.into());
+    }
+    match args.first().map(String::as_str) {
+        Some("keystore") => commands::keystore::run_keystore(&args[1..]),
+        Some("hd") => commands::hd::run_hd(&args[1..]).await,
+        Some("accounts") => commands::accounts::run_accounts(&args[1..]),
+        Some("addressbook") => commands::address_book::run_address_book(&args[1..]),
+        Some("send") => commands::transfer::run_transfer(&args[1..]).await,
+        Some("sweep") => {
+            let mut sweep_args = args[1..].to_vec();
+            sweep_args.push("--max".to_string());
+            commands::transfer::run_transfer(&sweep_args).await
         }
+        Some("token") => commands::token::run_token(&args[1..]).await,
+        Some("tx") => commands::tx::run_tx(&args[1..]).await,
+        Some("nonce") => commands::nonce::run_nonce(&args[1..]).await,
+        Some("batch") => commands::batch::run_batch(&args[1..]).await,
+        Some("disperse") => commands::disperse::run_disperse(&args[1..]).await,
+        Some("migrate") => commands::migrate::run_migrate(&args[1..]).await,
+        Some("sign") => commands::sign::run_sign(&args[1..]).await,
+        Some("verify") => commands::verify::run_verify(&args[1..]),
+        Some("verify-log") => commands::verify_log::run_verify_log(&args[1..]),
+        _ => commands::transfer::run_transfer(&args).await,
     }
-
-    Ok(())
 }
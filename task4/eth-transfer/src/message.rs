@@ -0,0 +1,225 @@
+//! EIP-191 personal message signing and verification
+//!
+//! Messages are signed with the `"\x19Ethereum Signed Message:\n" + len`
+//! prefix (`personal_sign`), so a signature can never be replayed as a
+//! transaction. Used to prove control of an account without moving funds.
+
+use arb_common::address::checksummed;
+use ethers::types::{Signature, H160, H256};
+use ethers::utils::hash_message;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Message bytes from exactly one of text, hex or a file
+///
+/// # Arguments
+/// * `text` - UTF-8 message
+/// * `hex` - Message as `0x`-prefixed hex, for binary payloads
+/// * `file` - File whose contents are the message
+///
+/// # Returns
+/// * `Result<Vec<u8>, Box<dyn std::error::Error>>` - Bytes to sign or verify
+pub fn message_bytes(text: Option<&str>, hex: Option<&str>, file: Option<&Path>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match (text, hex, file) {
+        (Some(text), None, None) => Ok(text.as_bytes().to_vec()),
+        (None, Some(hex), None) => {
+            let digits = hex.strip_prefix("0x").ok_or("Hex messages must start with 0x")?;
+            Ok(ethers::utils::hex::decode(digits).map_err(|e| format!("Invalid hex message: {}", e))?)
+        }
+        (None, None, Some(path)) => Ok(fs::read(path).map_err(|_| format!("Failed to read {}", path.display()))?),
+        (None, None, None) => Err("No message given: pass the text, --hex HEX or --file FILE".into()),
+        _ => Err("Pass only one of the text, --hex or --file".into()),
+    }
+}
+
+/// The message as text, if it is printable UTF-8
+fn printable_text(message: &[u8]) -> Option<&str> {
+    std::str::from_utf8(message)
+        .ok()
+        .filter(|text| !text.chars().any(|c| c.is_control() && c != '\n' && c != '\t'))
+}
+
+/// Message for display: the text if it is printable UTF-8, else hex
+pub fn display_message(message: &[u8]) -> String {
+    match printable_text(message) {
+        Some(text) => text.to_string(),
+        None => format!("0x{}", ethers::utils::hex::encode(message)),
+    }
+}
+
+/// Parse a 65-byte `r || s || v` signature; `v` may be 0/1 or 27/28
+pub fn parse_signature(signature: &str) -> Result<Signature, Box<dyn std::error::Error>> {
+    let signature: Signature = signature
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid signature (expected 65 bytes of hex): {}", e))?;
+    if !matches!(signature.v, 0 | 1 | 27 | 28) {
+        return Err(format!("Invalid signature recovery byte v = {} (expected 0, 1, 27 or 28)", signature.v).into());
+    }
+    Ok(signature)
+}
+
+/// Signature as `0x`-prefixed hex with `v` as 27/28
+pub fn format_signature(signature: &Signature) -> String {
+    let mut signature = *signature;
+    if signature.v < 27 {
+        signature.v += 27;
+    }
+    format!("0x{}", signature)
+}
+
+/// Recover the account that signed a personal message
+///
+/// # Arguments
+/// * `message` - Signed bytes, without the EIP-191 prefix
+/// * `signature` - Signature to check
+///
+/// # Returns
+/// * `Result<H160, Box<dyn std::error::Error>>` - Signer address
+pub fn recover_message(message: &[u8], signature: &Signature) -> Result<H160, Box<dyn std::error::Error>> {
+    Ok(signature.recover(message_hash(message))?)
+}
+
+/// EIP-191 hash that is actually signed
+pub fn message_hash(message: &[u8]) -> H256 {
+    hash_message(message)
+}
+
+/// Check a recovered signer against the expected account
+///
+/// # Returns
+/// * `Result<(), Box<dyn std::error::Error>>` - An error naming both addresses if they differ
+pub fn check_signer(signer: H160, expected: H160) -> Result<(), Box<dyn std::error::Error>> {
+    if signer != expected {
+        return Err(format!(
+            "Signature was made by {}, not {}",
+            checksummed(signer),
+            checksummed(expected)
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// How `msg` in a signed message file encodes the signed bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageEncoding {
+    /// `msg` is the signed text itself, even if it looks like hex
+    #[default]
+    Text,
+    /// `msg` is `0x`-prefixed hex of binary bytes
+    Hex,
+}
+
+/// Signed message in the `{address, msg, sig, version}` form explorers and wallets exchange
+///
+/// `encoding` is added so text such as `"0xdeadbeef"` is not mistaken for
+/// hex; files from other tools lack it and hold text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedMessage {
+    pub address: String,
+    pub msg: String,
+    pub sig: String,
+    pub version: String,
+    #[serde(default)]
+    pub encoding: MessageEncoding,
+}
+
+impl SignedMessage {
+    /// Record of a signature over `message`
+    pub fn new(address: H160, message: &[u8], signature: &Signature) -> Self {
+        let (msg, encoding) = match printable_text(message) {
+            Some(text) => (text.to_string(), MessageEncoding::Text),
+            None => (format!("0x{}", ethers::utils::hex::encode(message)), MessageEncoding::Hex),
+        };
+        SignedMessage {
+            address: checksummed(address),
+            msg,
+            sig: format_signature(signature),
+            version: "2".to_string(),
+            encoding,
+        }
+    }
+
+    /// Read a signed message file
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = fs::read_to_string(path).map_err(|_| format!("Failed to read {}", path.display()))?;
+        Ok(serde_json::from_str(&text).map_err(|e| format!("{} is not a signed message file: {}", path.display(), e))?)
+    }
+
+    /// Message bytes, decoded according to `encoding`
+    pub fn message(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        match self.encoding {
+            MessageEncoding::Text => Ok(self.msg.as_bytes().to_vec()),
+            MessageEncoding::Hex => message_bytes(None, Some(&self.msg), None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_wallet;
+    use ethers::signers::Signer;
+
+    #[test]
+    fn test_message_bytes() {
+        assert_eq!(message_bytes(Some("hi"), None, None).unwrap(), b"hi".to_vec());
+        assert_eq!(message_bytes(None, Some("0x00ff"), None).unwrap(), vec![0x00, 0xff]);
+        assert!(message_bytes(None, Some("00ff"), None).is_err());
+        assert!(message_bytes(None, None, None).is_err());
+        assert!(message_bytes(Some("hi"), Some("0x00"), None).is_err());
+
+        assert_eq!(display_message(b"I own this wallet\n"), "I own this wallet\n");
+        assert_eq!(display_message(&[0x00, 0xff]), "0x00ff");
+    }
+
+    #[tokio::test]
+    async fn test_sign_and_verify() {
+        let wallet = test_wallet();
+        let message = b"Partner check 2026-10-18";
+        let signature = wallet.sign_message(message).await.unwrap();
+
+        let formatted = format_signature(&signature);
+        let parsed = parse_signature(&formatted).unwrap();
+        assert_eq!(recover_message(message, &parsed).unwrap(), wallet.address());
+        assert!(check_signer(recover_message(message, &parsed).unwrap(), wallet.address()).is_ok());
+        assert!(check_signer(recover_message(b"something else", &parsed).unwrap(), wallet.address()).is_err());
+        assert!(check_signer(wallet.address(), H160::repeat_byte(0x11)).is_err());
+
+        // v as 0/1 recovers the same signer
+        let mut compact = signature;
+        compact.v -= 27;
+        let compact = format!("0x{}", compact);
+        assert_eq!(recover_message(message, &parse_signature(&compact).unwrap()).unwrap(), wallet.address());
+        assert!(parse_signature("0x1234").is_err());
+
+        let signed = SignedMessage::new(wallet.address(), message, &signature);
+        let json = serde_json::to_string(&signed).unwrap();
+        let loaded: SignedMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.message().unwrap(), message.to_vec());
+        assert_eq!(loaded.sig, formatted);
+    }
+
+    #[tokio::test]
+    async fn test_signed_message_encoding() {
+        let wallet = test_wallet();
+        // Text that happens to look like hex stays text
+        for message in [b"0xdeadbeef".to_vec(), vec![0xde, 0xad, 0xbe, 0xef]] {
+            let signature = wallet.sign_message(&message).await.unwrap();
+            let signed = SignedMessage::new(wallet.address(), &message, &signature);
+            let loaded: SignedMessage = serde_json::from_str(&serde_json::to_string(&signed).unwrap()).unwrap();
+            assert_eq!(loaded.msg, "0xdeadbeef");
+            assert_eq!(loaded.message().unwrap(), message);
+            assert_eq!(recover_message(&loaded.message().unwrap(), &signature).unwrap(), wallet.address());
+        }
+
+        // Files from other tools carry no encoding and hold text
+        let external: SignedMessage =
+            serde_json::from_str(r#"{"address":"0x00","msg":"0x1234","sig":"0x00","version":"2"}"#).unwrap();
+        assert_eq!(external.encoding, MessageEncoding::Text);
+        assert_eq!(external.message().unwrap(), b"0x1234".to_vec());
+    }
+}
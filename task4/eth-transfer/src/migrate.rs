@@ -0,0 +1,202 @@
+//! Key rotation: move every asset from one account to another
+//!
+//! Tokens are moved first, while the old account still has ETH for gas,
+//! then the ETH is swept. Every step is written to a JSON report.
+
+use arb_common::address::checksummed;
+use arb_common::address_book::AddressBook;
+use ethers::types::{H160, H256};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Tokens to migrate: `--tokens`, else `MIGRATE_TOKENS`, as comma-separated labels or addresses
+///
+/// # Arguments
+/// * `arg` - Value of `--tokens`
+/// * `book` - Address book for labels
+/// * `chain_id` - Connected chain
+///
+/// # Returns
+/// * `Result<Vec<H160>, Box<dyn std::error::Error>>` - Token addresses without duplicates
+pub fn migration_tokens(arg: Option<&str>, book: &AddressBook, chain_id: u64) -> Result<Vec<H160>, Box<dyn std::error::Error>> {
+    let list = match arg.map(String::from).or_else(|| env::var("MIGRATE_TOKENS").ok()) {
+        Some(list) => list,
+        None => return Ok(Vec::new()),
+    };
+    let mut tokens = Vec::new();
+    for item in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let token = book.resolve(item, chain_id)?.address;
+        if !tokens.contains(&token) {
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+/// Outcome of one migration step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum StepStatus {
+    /// Mined successfully
+    Confirmed,
+    /// Deliberately not attempted
+    Skipped { reason: String },
+    /// Rejected, reverted, or not confirmed
+    Failed { error: String },
+}
+
+impl std::fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepStatus::Confirmed => write!(f, "confirmed"),
+            StepStatus::Skipped { reason } => write!(f, "skipped: {}", reason),
+            StepStatus::Failed { error } => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+/// What happened to one asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationStep {
+    /// Symbol, e.g. "ETH"
+    pub asset: String,
+    /// Checksummed token address, `None` for ETH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Amount moved (or attempted), in display units
+    pub amount: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    #[serde(flatten)]
+    pub status: StepStatus,
+}
+
+/// Balance left on the old account after the migration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemainingBalance {
+    pub asset: String,
+    pub amount: String,
+}
+
+/// Record of a migration run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    /// Checksummed old account
+    pub from: String,
+    /// Checksummed new account
+    pub to: String,
+    pub chain_id: u64,
+    /// Unix time the run started
+    pub started_at: u64,
+    pub steps: Vec<MigrationStep>,
+    /// Balances found on the old account afterwards
+    pub remaining: Vec<RemainingBalance>,
+    /// True if nothing movable is left on the old account
+    pub empty: bool,
+}
+
+impl MigrationReport {
+    /// Empty report for a run starting now
+    pub fn new(from: H160, to: H160, chain_id: u64) -> Self {
+        MigrationReport {
+            from: checksummed(from),
+            to: checksummed(to),
+            chain_id,
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            steps: Vec::new(),
+            remaining: Vec::new(),
+            empty: false,
+        }
+    }
+
+    /// Whether every step that moved something was confirmed
+    pub fn all_confirmed(&self) -> bool {
+        self.steps.iter().all(|s| !matches!(s.status, StepStatus::Failed { .. }))
+    }
+
+    /// Default report file: `migration-<from>-<started_at>.json`
+    pub fn default_path(&self) -> PathBuf {
+        PathBuf::from(format!("migration-{}-{}.json", self.from, self.started_at))
+    }
+
+    /// Write the report as JSON
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arb_common::address_book::AddressEntry;
+    use std::str::FromStr;
+
+    const TOKEN: &str = "0x812dD1c3EB07bb1F5f93540350EF9aF838AB0528";
+
+    #[test]
+    fn test_migration_tokens() {
+        let mut book = AddressBook::default();
+        book.add(AddressEntry {
+            label: "test-token".to_string(),
+            address: H160::from_str(TOKEN).unwrap(),
+            notes: None,
+            chains: vec![421614],
+        })
+        .unwrap();
+
+        let tokens = migration_tokens(Some(&format!("test-token, {},", TOKEN)), &book, 421614).unwrap();
+        assert_eq!(tokens, vec![H160::from_str(TOKEN).unwrap()]);
+        // The label is restricted to Arbitrum Sepolia
+        assert!(migration_tokens(Some("test-token"), &book, 1).is_err());
+        assert!(migration_tokens(Some("unknown"), &book, 421614).is_err());
+    }
+
+    #[test]
+    fn test_report() {
+        let mut report = MigrationReport::new(H160::repeat_byte(0x11), H160::repeat_byte(0x22), 421614);
+        report.steps.push(MigrationStep {
+            asset: "ETH".to_string(),
+            token: None,
+            amount: "0.5".to_string(),
+            tx_hash: Some(H256::repeat_byte(0xab)),
+            status: StepStatus::Confirmed,
+        });
+        assert!(report.all_confirmed());
+        assert!(report.default_path().to_string_lossy().starts_with("migration-0x1111"));
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(!json.contains("\"token\""));
+        assert!(json.contains("\"status\":\"confirmed\""));
+        assert_eq!(serde_json::from_str::<MigrationReport>(&json).unwrap(), report);
+
+        report.steps.push(MigrationStep {
+            asset: "TT".to_string(),
+            token: Some(TOKEN.to_string()),
+            amount: "1".to_string(),
+            tx_hash: None,
+            status: StepStatus::Skipped { reason: "dust".to_string() },
+        });
+        assert!(report.all_confirmed());
+
+        report.steps.push(MigrationStep {
+            asset: "TT".to_string(),
+            token: Some(TOKEN.to_string()),
+            amount: "1".to_string(),
+            tx_hash: None,
+            status: StepStatus::Failed { error: "reverted".to_string() },
+        });
+        assert!(!report.all_confirmed());
+        assert_eq!(report.steps[2].status.to_string(), "failed: reverted");
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"status\":\"failed\",\"error\":\"reverted\""));
+        assert_eq!(serde_json::from_str::<MigrationReport>(&json).unwrap(), report);
+    }
+}
@@ -0,0 +1,372 @@
+//! Local nonce management for pipelined sends
+//!
+//! Nonces are reserved locally, so several transactions from one account can
+//! be submitted without waiting for each to mine. The node's pending
+//! transaction count is the source of truth: the manager syncs with it on
+//! first use and again whenever a send fails with "nonce too low". A send
+//! that the node reports as "already known" is treated as submitted.
+
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// Fresh nonces tried before giving up on a send
+const NONCE_RETRIES: usize = 3;
+
+/// Callback with the nonce and hash of a signed transaction, before broadcast
+pub type OnSigned<'a> = dyn FnMut(U256, H256) -> Result<(), Box<dyn std::error::Error>> + 'a;
+
+/// What a rejected send means for the nonce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendErrorKind {
+    /// The nonce is already used, on chain or by another pending transaction
+    NonceTooLow,
+    /// The node already has this exact transaction
+    AlreadyKnown,
+    /// Anything else; the nonce was not consumed
+    Other,
+}
+
+/// Classify a send error by its message
+pub fn classify_send_error(message: &str) -> SendErrorKind {
+    let message = message.to_lowercase();
+    if message.contains("already known") || message.contains("known transaction") {
+        SendErrorKind::AlreadyKnown
+    } else if message.contains("nonce too low")
+        || message.contains("nonce has already been used")
+        || message.contains("replacement transaction underpriced")
+    {
+        SendErrorKind::NonceTooLow
+    } else {
+        SendErrorKind::Other
+    }
+}
+
+#[derive(Debug, Default)]
+struct NonceState {
+    /// Next nonce to hand out; `None` until synced with the node
+    next: Option<U256>,
+    /// Nonces the node accepted a transaction for
+    submitted: BTreeSet<U256>,
+}
+
+/// Hands out nonces for one account and submits transactions through a signing client
+pub struct NonceManager<M> {
+    client: M,
+    address: H160,
+    state: Mutex<NonceState>,
+}
+
+impl<M: Middleware> NonceManager<M> {
+    /// Wrap a signing client (usually a `SignerMiddleware`) for `address`
+    pub fn new(client: M, address: H160) -> Self {
+        NonceManager {
+            client,
+            address,
+            state: Mutex::new(NonceState::default()),
+        }
+    }
+
+    /// The sending account
+    pub fn address(&self) -> H160 {
+        self.address
+    }
+
+    /// Number of transactions from the account, mined or pending
+    async fn transaction_count(&self, block: BlockNumber) -> Result<U256, Box<dyn std::error::Error>> {
+        let count = self
+            .client
+            .get_transaction_count(self.address, Some(block.into()))
+            .await
+            .map_err(|e| format!("Failed to get transaction count: {}", e))?;
+        Ok(count)
+    }
+
+    /// Mined and pending nonce counts from the node
+    ///
+    /// # Returns
+    /// * `Result<(U256, U256), Box<dyn std::error::Error>>` - Latest and pending transaction counts
+    pub async fn node_counts(&self) -> Result<(U256, U256), Box<dyn std::error::Error>> {
+        let latest = self.transaction_count(BlockNumber::Latest).await?;
+        let pending = self.transaction_count(BlockNumber::Pending).await?;
+        Ok((latest, pending))
+    }
+
+    /// Resync with the node's pending count
+    ///
+    /// Never moves backwards, so nonces already handed out for in-flight
+    /// sends are not reused.
+    ///
+    /// # Returns
+    /// * `Result<U256, Box<dyn std::error::Error>>` - Next nonce to be reserved
+    pub async fn sync(&self) -> Result<U256, Box<dyn std::error::Error>> {
+        let pending = self.transaction_count(BlockNumber::Pending).await?;
+        let mut state = self.state.lock().unwrap();
+        let next = state.next.map_or(pending, |next| next.max(pending));
+        state.next = Some(next);
+        Ok(next)
+    }
+
+    /// Reserve the next nonce
+    pub async fn reserve(&self) -> Result<U256, Box<dyn std::error::Error>> {
+        if self.state.lock().unwrap().next.is_none() {
+            self.sync().await?;
+        }
+        let mut state = self.state.lock().unwrap();
+        let nonce = state.next.unwrap_or_default();
+        state.next = Some(nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Give back a nonce that was not used; only the latest reservation can be returned
+    fn release(&self, nonce: U256) {
+        let mut state = self.state.lock().unwrap();
+        if state.next == Some(nonce + 1) {
+            state.next = Some(nonce);
+        }
+    }
+
+    /// Record nonces already submitted elsewhere, e.g. by an earlier run
+    ///
+    /// Later reservations continue after the highest one, and the nonces
+    /// below it that are not recorded count as gaps.
+    pub fn record_submitted(&self, nonces: &[U256]) {
+        let mut state = self.state.lock().unwrap();
+        for nonce in nonces {
+            state.submitted.insert(*nonce);
+            if state.next.is_none_or(|next| next <= *nonce) {
+                state.next = Some(*nonce + 1);
+            }
+        }
+    }
+
+    /// Fill, sign and send a transaction with the nonce it already has
+    ///
+    /// `on_signed` sees the nonce and hash before the transaction is broadcast.
+    /// Once the node has it, its spending goes into the policy ledger.
+    async fn submit(&self, mut tx: TypedTransaction, on_signed: &mut OnSigned<'_>) -> Result<H256, Box<dyn std::error::Error>> {
+        self.client.fill_transaction(&mut tx, None).await.map_err(|e| e.to_string())?;
+        let signature = self
+            .client
+            .sign_transaction(&tx, self.address)
+            .await
+            .map_err(|e| e.to_string())?;
+        let raw = tx.rlp_signed(&signature);
+        let hash = H256::from(keccak256(&raw));
+        on_signed(tx.nonce().copied().unwrap_or_default(), hash)?;
+
+        let result = self.client.send_raw_transaction(raw).await.map(|_| ());
+        match result {
+            Ok(()) => {}
+            // The node already has this exact transaction, so it has this hash
+            Err(e) if classify_send_error(&e.to_string()) == SendErrorKind::AlreadyKnown => {}
+            Err(e) => return Err(e.to_string().into()),
+        }
+        crate::policy::record_sent(self.address, &tx);
+        Ok(hash)
+    }
+
+    /// Send a transaction with the next local nonce
+    ///
+    /// Any nonce already set on `tx` is replaced. On "nonce too low" the
+    /// manager resyncs and retries with a fresh nonce; other errors return
+    /// the nonce so the next send reuses it.
+    ///
+    /// # Arguments
+    /// * `tx` - Transaction from the managed account
+    ///
+    /// # Returns
+    /// * `Result<(U256, H256), Box<dyn std::error::Error>>` - Nonce used and transaction hash
+    pub async fn send(&self, tx: TypedTransaction) -> Result<(U256, H256), Box<dyn std::error::Error>> {
+        self.send_with(tx, &mut |_, _| Ok(())).await
+    }
+
+    /// Like `send`, calling `on_signed` with the nonce and hash of every
+    /// attempt before it is broadcast
+    ///
+    /// Recording the hash there means a crash mid-send never leaves a
+    /// broadcast transaction without a record. An error from `on_signed`
+    /// aborts the send.
+    pub async fn send_with(
+        &self,
+        mut tx: TypedTransaction,
+        on_signed: &mut OnSigned<'_>,
+    ) -> Result<(U256, H256), Box<dyn std::error::Error>> {
+        tx.set_from(self.address);
+        for _ in 0..NONCE_RETRIES {
+            let nonce = self.reserve().await?;
+            tx.set_nonce(nonce);
+            match self.submit(tx.clone(), on_signed).await {
+                Ok(hash) => {
+                    self.state.lock().unwrap().submitted.insert(nonce);
+                    return Ok((nonce, hash));
+                }
+                Err(e) if classify_send_error(&e.to_string()) == SendErrorKind::NonceTooLow => {
+                    self.sync().await?;
+                }
+                Err(e) => {
+                    self.release(nonce);
+                    return Err(e);
+                }
+            }
+        }
+        Err(format!("No usable nonce found after {} attempts", NONCE_RETRIES).into())
+    }
+
+    /// Nonces the node is missing below the ones handed out locally
+    ///
+    /// Transactions above a gap stay queued until it is filled. The node's
+    /// pending count is always a gap when it is below the local next nonce;
+    /// nonces above it are gaps unless a send for them succeeded.
+    ///
+    /// # Returns
+    /// * `Result<Vec<U256>, Box<dyn std::error::Error>>` - Missing nonces, lowest first
+    pub async fn gaps(&self) -> Result<Vec<U256>, Box<dyn std::error::Error>> {
+        let pending = self.transaction_count(BlockNumber::Pending).await?;
+        let state = self.state.lock().unwrap();
+        let mut gaps = Vec::new();
+        let mut nonce = pending;
+        while state.next.is_some_and(|next| nonce < next) {
+            if nonce == pending || !state.submitted.contains(&nonce) {
+                gaps.push(nonce);
+            }
+            nonce += U256::one();
+        }
+        Ok(gaps)
+    }
+
+    /// Fill every gap with a 0-value transfer to the sender itself
+    ///
+    /// # Returns
+    /// * `Result<Vec<(U256, H256)>, Box<dyn std::error::Error>>` - Filled nonces and their transaction hashes
+    pub async fn fill_gaps(&self) -> Result<Vec<(U256, H256)>, Box<dyn std::error::Error>> {
+        let mut filled = Vec::new();
+        for nonce in self.gaps().await? {
+            let tx: TypedTransaction = TransactionRequest::new()
+                .from(self.address)
+                .to(self.address)
+                .value(0)
+                .nonce(nonce)
+                .into();
+            match self.submit(tx, &mut |_, _| Ok(())).await {
+                Ok(hash) => {
+                    self.state.lock().unwrap().submitted.insert(nonce);
+                    filled.push((nonce, hash));
+                }
+                // Mined or replaced in the meantime
+                Err(e) if classify_send_error(&e.to_string()) == SendErrorKind::NonceTooLow => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_wallet;
+    use ethers::providers::{JsonRpcError, MockProvider, MockResponse};
+
+    fn manager() -> (NonceManager<SignerMiddleware<Provider<MockProvider>, LocalWallet>>, MockProvider, LocalWallet) {
+        let (provider, mock) = Provider::mocked();
+        let wallet = test_wallet();
+        let address = wallet.address();
+        let client = SignerMiddleware::new(provider, wallet.clone());
+        (NonceManager::new(client, address), mock, wallet)
+    }
+
+    fn transfer(from: H160) -> TypedTransaction {
+        TransactionRequest::new()
+            .from(from)
+            .to(H160::repeat_byte(0x22))
+            .value(1000)
+            .gas(100_000)
+            .gas_price(100_000_000)
+            .chain_id(421614u64)
+            .into()
+    }
+
+    fn rpc_error(message: &str) -> MockResponse {
+        MockResponse::Error(JsonRpcError { code: -32000, message: message.to_string(), data: None })
+    }
+
+    #[test]
+    fn test_classify_send_error() {
+        assert_eq!(classify_send_error("nonce too low: address 0x.., tx: 5 state: 6"), SendErrorKind::NonceTooLow);
+        assert_eq!(classify_send_error("replacement transaction underpriced"), SendErrorKind::NonceTooLow);
+        assert_eq!(classify_send_error("already known"), SendErrorKind::AlreadyKnown);
+        assert_eq!(classify_send_error("insufficient funds for gas * price + value"), SendErrorKind::Other);
+    }
+
+    /// Hash of `tx` signed by `wallet` with `nonce`
+    async fn signed_hash(wallet: &LocalWallet, tx: &TypedTransaction, nonce: u64) -> H256 {
+        let mut tx = tx.clone();
+        tx.set_nonce(nonce);
+        let signature = wallet.sign_transaction(&tx).await.unwrap();
+        H256::from(keccak256(tx.rlp_signed(&signature)))
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_sends_recover_from_nonce_too_low() {
+        let (manager, mock, wallet) = manager();
+        let tx = transfer(wallet.address());
+        let (first, second) = (signed_hash(&wallet, &tx, 5).await, signed_hash(&wallet, &tx, 8).await);
+
+        // Served last-in first-out: sync to 5, send, "nonce too low" for 6,
+        // resync to 8, send
+        mock.push::<H256, _>(second).unwrap();
+        mock.push::<U256, _>(U256::from(8)).unwrap();
+        mock.push_response(rpc_error("nonce too low"));
+        mock.push::<H256, _>(first).unwrap();
+        mock.push::<U256, _>(U256::from(5)).unwrap();
+
+        let mut signed = Vec::new();
+        assert_eq!(manager.send(tx.clone()).await.unwrap(), (U256::from(5), first));
+        let sent = manager
+            .send_with(tx, &mut |nonce, hash| {
+                signed.push((nonce, hash));
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(sent, (U256::from(8), second));
+        // Every attempt is reported before it is broadcast
+        assert_eq!(signed.len(), 2);
+        assert_eq!(signed[0].0, U256::from(6));
+    }
+
+    #[tokio::test]
+    async fn test_already_known_returns_hash() {
+        let (manager, mock, wallet) = manager();
+        mock.push_response(rpc_error("already known"));
+        mock.push::<U256, _>(U256::from(3)).unwrap();
+
+        let tx = transfer(wallet.address());
+        let (nonce, hash) = manager.send(tx.clone()).await.unwrap();
+        assert_eq!(nonce, U256::from(3));
+        assert_eq!(hash, signed_hash(&wallet, &tx, 3).await);
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_releases_nonce() {
+        let (manager, mock, wallet) = manager();
+        mock.push_response(rpc_error("insufficient funds for gas * price + value"));
+        mock.push::<U256, _>(U256::from(4)).unwrap();
+
+        assert!(manager.send(transfer(wallet.address())).await.is_err());
+        assert_eq!(manager.reserve().await.unwrap(), U256::from(4));
+    }
+
+    #[tokio::test]
+    async fn test_gaps() {
+        let (manager, mock, _) = manager();
+        manager.record_submitted(&[U256::from(7), U256::from(9)]);
+
+        mock.push::<U256, _>(U256::from(6)).unwrap();
+        assert_eq!(manager.gaps().await.unwrap(), vec![U256::from(6), U256::from(8)]);
+        assert_eq!(manager.reserve().await.unwrap(), U256::from(10));
+    }
+}
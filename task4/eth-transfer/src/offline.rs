@@ -0,0 +1,236 @@
+//! Offline signing: build on a networked machine, sign air-gapped, broadcast later
+//!
+//! `tx build` writes an [`UnsignedTransaction`] file with everything the
+//! signer needs (nonce, fees, gas, chain ID), so `tx sign` never touches the
+//! network. The signed result is the raw transaction as hex, which `tx
+//! broadcast` (or any other tool) can submit.
+
+use crate::accounts::AccountSigner;
+use arb_common::address::checksummed;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::{keccak256, rlp::Rlp};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Version of the unsigned transaction file format
+pub const UNSIGNED_FORMAT_VERSION: u32 = 1;
+
+/// A transaction ready to be signed on another machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    pub version: u32,
+    pub chain_id: u64,
+    /// Account that has to sign it
+    pub from: H160,
+    /// Fully populated transaction
+    pub tx: TypedTransaction,
+    /// Base fee when it was built, for judging whether the fees went stale
+    pub base_fee: U256,
+    /// Unix time it was built
+    pub created_at: u64,
+}
+
+impl UnsignedTransaction {
+    /// Wrap a populated transaction
+    ///
+    /// # Arguments
+    /// * `tx` - Transaction with sender, nonce, gas, gas price and chain ID set
+    /// * `base_fee` - Base fee it was priced against
+    ///
+    /// # Returns
+    /// * `Result<Self, Box<dyn std::error::Error>>` - File contents, or an error naming a missing field
+    pub fn new(tx: TypedTransaction, base_fee: U256) -> Result<Self, Box<dyn std::error::Error>> {
+        let unsigned = UnsignedTransaction {
+            version: UNSIGNED_FORMAT_VERSION,
+            chain_id: tx.chain_id().ok_or("Transaction has no chain ID")?.as_u64(),
+            from: *tx.from().ok_or("Transaction has no sender")?,
+            tx,
+            base_fee,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        unsigned.validate()?;
+        Ok(unsigned)
+    }
+
+    /// Check the file is complete and self-consistent, so signing needs no network
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.version != UNSIGNED_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported unsigned transaction version {} (expected {})",
+                self.version, UNSIGNED_FORMAT_VERSION
+            )
+            .into());
+        }
+        if self.tx.from() != Some(&self.from) {
+            return Err("Transaction sender does not match the file's 'from'".into());
+        }
+        if self.tx.chain_id().map(|c| c.as_u64()) != Some(self.chain_id) {
+            return Err("Transaction chain ID does not match the file's 'chain_id'".into());
+        }
+        if self.tx.nonce().is_none() {
+            return Err("Transaction has no nonce".into());
+        }
+        if self.tx.gas().is_none() {
+            return Err("Transaction has no gas limit".into());
+        }
+        if self.tx.gas_price().is_none() {
+            return Err("Transaction has no gas price".into());
+        }
+        let cost = self
+            .tx
+            .gas_price()
+            .unwrap_or_default()
+            .checked_mul(self.tx.gas().copied().unwrap_or_default())
+            .and_then(|fee| fee.checked_add(self.tx.value().copied().unwrap_or_default()));
+        if cost.is_none() {
+            return Err("Transaction fee and value overflow 256 bits".into());
+        }
+        Ok(())
+    }
+
+    /// Highest fee the transaction can cost (gas price × gas limit)
+    pub fn max_fee(&self) -> U256 {
+        self.tx.gas_price().unwrap_or_default().saturating_mul(self.tx.gas().copied().unwrap_or_default())
+    }
+
+    /// Read and validate an unsigned transaction file
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = fs::read_to_string(path).map_err(|_| format!("Failed to read {}", path.display()))?;
+        let mut unsigned: UnsignedTransaction = serde_json::from_str(&text)
+            .map_err(|e| format!("{} is not an unsigned transaction file: {}", path.display(), e))?;
+        // Legacy transactions do not serialize their chain ID; the file carries it
+        if unsigned.tx.chain_id().is_none() {
+            unsigned.tx.set_chain_id(unsigned.chain_id);
+        }
+        unsigned.validate()?;
+        Ok(unsigned)
+    }
+
+    /// Write the file as JSON
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Sign an unsigned transaction without any network access
+///
+/// The transaction is broadcast from another machine, so its spending goes
+/// into this machine's policy ledger as soon as it is signed.
+///
+/// # Arguments
+/// * `unsigned` - Transaction from `tx build`
+/// * `signer` - Signer for `unsigned.from`
+///
+/// # Returns
+/// * `Result<Bytes, Box<dyn std::error::Error>>` - Raw signed transaction
+pub async fn sign_offline(unsigned: &UnsignedTransaction, signer: &AccountSigner) -> Result<Bytes, Box<dyn std::error::Error>> {
+    if signer.address() != unsigned.from {
+        return Err(format!(
+            "Signer is {}, but the transaction must be signed by {}",
+            checksummed(signer.address()),
+            checksummed(unsigned.from)
+        )
+        .into());
+    }
+    let signer = signer.clone().with_chain_id(unsigned.chain_id);
+    let signature = signer.sign_transaction(&unsigned.tx).await?;
+    crate::policy::record_sent(unsigned.from, &unsigned.tx);
+    Ok(unsigned.tx.rlp_signed(&signature))
+}
+
+/// Read raw signed bytes from a hex string or a file holding one
+pub fn read_raw_transaction(input: &str) -> Result<Bytes, Box<dyn std::error::Error>> {
+    let hex = if input.starts_with("0x") {
+        input.to_string()
+    } else {
+        fs::read_to_string(input).map_err(|_| format!("Failed to read {}", input))?
+    };
+    Ok(hex.trim().parse::<Bytes>().map_err(|e| format!("Invalid transaction hex: {}", e))?)
+}
+
+/// Decode a raw signed transaction and recover its sender
+///
+/// # Arguments
+/// * `raw` - Signed transaction bytes
+///
+/// # Returns
+/// * `Result<(TypedTransaction, H160, H256), Box<dyn std::error::Error>>` - Transaction, sender and hash
+pub fn decode_signed(raw: &Bytes) -> Result<(TypedTransaction, H160, H256), Box<dyn std::error::Error>> {
+    let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(raw))
+        .map_err(|e| format!("Not a signed transaction: {}", e))?;
+    let from = signature.recover(tx.sighash())?;
+    Ok((tx, from, H256::from(keccak256(raw))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{test_wallet, use_temp_audit_log};
+
+    fn unsigned_for(from: H160) -> UnsignedTransaction {
+        let tx: TypedTransaction = TransactionRequest::new()
+            .from(from)
+            .to(H160::repeat_byte(0x22))
+            .value(1_000u64)
+            .gas(100_000u64)
+            .gas_price(120_000_000u64)
+            .nonce(7u64)
+            .chain_id(421614u64)
+            .into();
+        UnsignedTransaction::new(tx, U256::from(100_000_000u64)).unwrap()
+    }
+
+    #[test]
+    fn test_unsigned_file() {
+        let unsigned = unsigned_for(H160::repeat_byte(0x11));
+        assert_eq!(unsigned.chain_id, 421614);
+        assert_eq!(unsigned.max_fee(), U256::from(12_000_000_000_000u64));
+
+        let path = std::env::temp_dir().join(format!("unsigned-{}.json", std::process::id()));
+        unsigned.save(&path).unwrap();
+        assert_eq!(UnsignedTransaction::load(&path).unwrap(), unsigned);
+
+        // An edited sender no longer matches the transaction
+        let mut edited = unsigned.clone();
+        edited.from = H160::repeat_byte(0x33);
+        edited.save(&path).unwrap();
+        assert!(UnsignedTransaction::load(&path).is_err());
+        fs::remove_file(&path).ok();
+
+        let incomplete: TypedTransaction = TransactionRequest::new().from(unsigned.from).chain_id(421614u64).into();
+        assert!(UnsignedTransaction::new(incomplete, U256::zero()).is_err());
+
+        // A crafted file with an absurd fee is refused rather than overflowing
+        let mut crafted = unsigned.clone();
+        crafted.tx.set_gas(U256::MAX);
+        assert_eq!(crafted.max_fee(), U256::MAX);
+        assert!(crafted.validate().unwrap_err().to_string().contains("overflow"));
+    }
+
+    #[tokio::test]
+    async fn test_sign_offline_and_decode() {
+        use_temp_audit_log();
+        let wallet = test_wallet();
+        let signer = AccountSigner::Local(wallet.clone());
+        let unsigned = unsigned_for(wallet.address());
+
+        let raw = sign_offline(&unsigned, &signer).await.unwrap();
+        let (tx, from, hash) = decode_signed(&raw).unwrap();
+        assert_eq!(from, wallet.address());
+        assert_eq!(hash, H256::from(keccak256(&raw)));
+        assert_eq!(tx.nonce(), Some(&U256::from(7)));
+        assert_eq!(tx.chain_id(), Some(U64::from(421614)));
+        assert_eq!(read_raw_transaction(&format!("{}", raw)).unwrap(), raw);
+
+        // Signing for another account is refused
+        let other = unsigned_for(H160::repeat_byte(0x11));
+        assert!(sign_offline(&other, &signer).await.is_err());
+    }
+}
@@ -0,0 +1,920 @@
+//! Transfer policy: limits, allow/deny lists and fee caps enforced before signing
+//!
+//! The policy is a local JSON file (`POLICY_FILE`, default `policy.json`);
+//! without a default `policy.json` nothing is restricted, while a missing
+//! `POLICY_FILE` is an error. Every transaction an `AccountSigner`
+//! signs is checked against it, whatever command built the transaction.
+//!
+//! ```json
+//! {
+//!   "allowed_chains": [421614],
+//!   "max_fee": "0.001",
+//!   "eth": { "max_per_tx": "0.5", "daily_limit": "2" },
+//!   "tokens": { "0x812dD1c3EB07bb1F5f93540350EF9aF838AB0528": { "decimals": 18, "max_per_tx": "100" } },
+//!   "accounts": { "treasury": { "eth": { "daily_limit": "10" } } },
+//!   "allowlist": ["alice", "0x7292dD72151DaCFBbE76305db1C8Ab1928E922E4"],
+//!   "denylist": []
+//! }
+//! ```
+//!
+//! Amounts are in ETH or token units. Daily limits cover the last 24 hours
+//! per sending account and are tracked in a ledger (`POLICY_LEDGER`, default
+//! `policy-ledger.json`) with one entry per account and nonce. Entries are
+//! written once a transaction is broadcast (or signed offline), not when it
+//! is signed, so an attempt the node rejects, such as one retried with a
+//! fresh nonce after "nonce too low", is never counted; a replacement of the
+//! same nonce replaces its entry instead of adding to it. A violation can be overridden with `--override-policy
+//! REASON`; every override is appended to `POLICY_OVERRIDE_LOG` (default
+//! `policy-overrides.log`).
+
+use crate::disperse::disperse_abi;
+use crate::token::erc20_abi;
+use arb_common::address::{checksummed, parse_address};
+use arb_common::address_book::AddressBook;
+use arb_common::amount::Amount;
+use ethers::abi::{parse_abi, Token};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{H160, U256};
+use ethers::utils::get_contract_address;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Window daily limits apply to
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Override reason from `--override-policy`, set once by `main`
+static OVERRIDE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Allow signing past policy violations for the rest of the process
+pub fn set_override(reason: String) {
+    *OVERRIDE.lock().unwrap() = Some(reason);
+}
+
+/// Per-transaction and daily limits for one asset, as written in the file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LimitsFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_tx: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_limit: Option<String>,
+}
+
+/// Limits for a token; amounts are in token units, so its decimals are given here
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenLimitsFile {
+    pub decimals: u8,
+    #[serde(flatten)]
+    pub limits: LimitsFile,
+}
+
+/// Limits for one sending account, replacing the defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountLimitsFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eth: Option<LimitsFile>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tokens: BTreeMap<String, LimitsFile>,
+}
+
+/// The policy file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicyFile {
+    /// Chain IDs transactions may be signed for; empty allows any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_chains: Vec<u64>,
+    /// Largest maximum fee (gas limit × fee cap) of one transaction, in ETH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eth: Option<LimitsFile>,
+    /// Token address or address book label to its limits
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tokens: BTreeMap<String, TokenLimitsFile>,
+    /// Account address or label to limits replacing the defaults
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub accounts: BTreeMap<String, AccountLimitsFile>,
+    /// Recipients that may be paid; empty allows any not denied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowlist: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denylist: Vec<String>,
+}
+
+/// Limits in raw units
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub max_per_tx: Option<U256>,
+    pub daily_limit: Option<U256>,
+}
+
+/// A parsed policy with addresses resolved and amounts in raw units
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Policy {
+    pub allowed_chains: Vec<u64>,
+    pub max_fee: Option<U256>,
+    pub eth: Limits,
+    pub tokens: HashMap<H160, (u8, Limits)>,
+    pub accounts: HashMap<H160, (Option<Limits>, HashMap<H160, Limits>)>,
+    pub allowlist: BTreeSet<H160>,
+    pub denylist: BTreeSet<H160>,
+}
+
+/// Resolve a label from the address book or parse an address
+fn resolve(book: &AddressBook, input: &str) -> Result<H160, Box<dyn std::error::Error>> {
+    match book.get(input) {
+        Some(entry) => Ok(entry.address),
+        None => Ok(parse_address(input).map_err(|e| format!("Policy: '{}': {}", input, e))?.address),
+    }
+}
+
+/// Parse limits written in units with `decimals`
+fn parse_limits(limits: &LimitsFile, decimals: u8) -> Result<Limits, Box<dyn std::error::Error>> {
+    let parse = |value: &Option<String>| -> Result<Option<U256>, Box<dyn std::error::Error>> {
+        value
+            .as_deref()
+            .map(|v| Amount::parse(v, decimals).map(|a| a.raw()).map_err(|e| format!("Policy: amount '{}': {}", v, e).into()))
+            .transpose()
+    };
+    Ok(Limits { max_per_tx: parse(&limits.max_per_tx)?, daily_limit: parse(&limits.daily_limit)? })
+}
+
+impl Policy {
+    /// Parse a policy file, resolving labels with the address book
+    pub fn from_file(file: &PolicyFile, book: &AddressBook) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut tokens = HashMap::new();
+        for (token, limits) in &file.tokens {
+            tokens.insert(resolve(book, token)?, (limits.decimals, parse_limits(&limits.limits, limits.decimals)?));
+        }
+        let mut accounts = HashMap::new();
+        for (account, limits) in &file.accounts {
+            let eth = limits.eth.as_ref().map(|l| parse_limits(l, 18)).transpose()?;
+            let mut account_tokens = HashMap::new();
+            for (token, token_limits) in &limits.tokens {
+                let token = resolve(book, token)?;
+                let (decimals, _) = tokens
+                    .get(&token)
+                    .ok_or_else(|| format!("Policy: account '{}' limits token {} that has no decimals in 'tokens'", account, checksummed(token)))?;
+                account_tokens.insert(token, parse_limits(token_limits, *decimals)?);
+            }
+            accounts.insert(resolve(book, account)?, (eth, account_tokens));
+        }
+        Ok(Policy {
+            allowed_chains: file.allowed_chains.clone(),
+            max_fee: file
+                .max_fee
+                .as_deref()
+                .map(|f| Amount::parse_ether(f).map(|a| a.raw()))
+                .transpose()?,
+            eth: file.eth.as_ref().map(|l| parse_limits(l, 18)).transpose()?.unwrap_or_default(),
+            tokens,
+            accounts,
+            allowlist: file.allowlist.iter().map(|a| resolve(book, a)).collect::<Result<_, _>>()?,
+            denylist: file.denylist.iter().map(|a| resolve(book, a)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Load the policy from `POLICY_FILE` or `policy.json`
+    ///
+    /// A `POLICY_FILE` that does not exist is an error, so a mistyped path
+    /// never turns the policy off; only a missing default `policy.json`
+    /// means there is no policy.
+    ///
+    /// # Returns
+    /// * `Result<Option<Policy>, Box<dyn std::error::Error>>` - `None` if there is no policy file
+    pub fn load_default() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        match env::var("POLICY_FILE") {
+            Ok(path) => Policy::load(Path::new(&path), true),
+            Err(_) => Policy::load(Path::new("policy.json"), false),
+        }
+    }
+
+    /// Load a policy file
+    ///
+    /// # Arguments
+    /// * `path` - Policy file
+    /// * `required` - Whether a missing file is an error rather than no policy
+    ///
+    /// # Returns
+    /// * `Result<Option<Policy>, Box<dyn std::error::Error>>` - `None` if the file is missing and not required
+    pub fn load(path: &Path, required: bool) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            if required {
+                return Err(format!(
+                    "Policy file {} (POLICY_FILE) does not exist; refusing to sign without it",
+                    path.display()
+                )
+                .into());
+            }
+            return Ok(None);
+        }
+        let text = fs::read_to_string(path).map_err(|_| format!("Failed to read policy {}", path.display()))?;
+        let file: PolicyFile =
+            serde_json::from_str(&text).map_err(|e| format!("Invalid policy {}: {}", path.display(), e))?;
+        Ok(Some(Policy::from_file(&file, &AddressBook::load_default()?)?))
+    }
+
+    /// Limits that apply to `asset` sent from `from`
+    fn limits_for(&self, from: H160, asset: Option<H160>) -> Limits {
+        let account = self.accounts.get(&from);
+        match asset {
+            None => account.and_then(|(eth, _)| *eth).unwrap_or(self.eth),
+            Some(token) => account
+                .and_then(|(_, tokens)| tokens.get(&token).copied())
+                .or_else(|| self.tokens.get(&token).map(|(_, limits)| *limits))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Display an amount of `asset`
+    fn format_amount(&self, asset: Option<H160>, amount: U256) -> String {
+        match asset {
+            None => format!("{} ETH", Amount::from_wei(amount)),
+            Some(token) => match self.tokens.get(&token) {
+                Some((decimals, _)) => format!("{} of token {}", Amount::new(amount, *decimals), checksummed(token)),
+                None => format!("{} raw units of token {}", amount, checksummed(token)),
+            },
+        }
+    }
+
+    /// Everything a transaction violates
+    ///
+    /// # Arguments
+    /// * `from` - Signing account
+    /// * `chain_id` - Chain the transaction is signed for
+    /// * `tx` - Transaction about to be signed
+    /// * `spent` - What `from` already spent in the last 24 hours, per asset, not counting this nonce
+    ///
+    /// # Returns
+    /// * `Vec<String>` - One message per violation, empty if allowed
+    pub fn check(&self, from: H160, chain_id: u64, tx: &TypedTransaction, spent: &BTreeMap<Option<H160>, U256>) -> Vec<String> {
+        let mut violations = Vec::new();
+        if !self.allowed_chains.is_empty() && !self.allowed_chains.contains(&chain_id) {
+            violations.push(format!("chain {} is not in allowed_chains {:?}", chain_id, self.allowed_chains));
+        }
+        let max_fee = max_fee(tx);
+        if let Some(limit) = self.max_fee.filter(|limit| max_fee > *limit) {
+            violations.push(format!(
+                "maximum fee {} ETH exceeds max_fee {} ETH",
+                Amount::from_wei(max_fee),
+                Amount::from_wei(limit)
+            ));
+        }
+
+        if let Some(contract) = opaque_call(tx).filter(|c| !self.allowlist.contains(c)) {
+            violations.push(format!(
+                "call to {} has calldata the policy cannot decode and the contract is not on the allowlist",
+                checksummed(contract)
+            ));
+        }
+
+        let outflows = outflows(from, tx);
+        for outflow in &outflows {
+            // Moving funds between one's own addresses is never restricted
+            if outflow.recipient == from {
+                continue;
+            }
+            if self.denylist.contains(&outflow.recipient) {
+                violations.push(format!("recipient {} is on the denylist", checksummed(outflow.recipient)));
+            } else if !self.allowlist.is_empty() && !self.allowlist.contains(&outflow.recipient) {
+                violations.push(format!("recipient {} is not on the allowlist", checksummed(outflow.recipient)));
+            }
+        }
+
+        let mut totals: BTreeMap<Option<H160>, U256> = BTreeMap::new();
+        for outflow in outflows.iter().filter(|o| o.recipient != from) {
+            // Approvals are checked per transaction; the spend is counted when it happens
+            if !outflow.approval {
+                let total = totals.entry(outflow.asset).or_default();
+                *total = total.saturating_add(outflow.amount);
+            }
+            let limits = self.limits_for(from, outflow.asset);
+            if let Some(limit) = limits.max_per_tx.filter(|limit| outflow.amount > *limit) {
+                violations.push(format!(
+                    "{} {} exceeds max_per_tx {}",
+                    if outflow.approval { "approval of" } else { "payment of" },
+                    self.format_amount(outflow.asset, outflow.amount),
+                    self.format_amount(outflow.asset, limit)
+                ));
+            }
+        }
+        for (asset, total) in &totals {
+            let limits = self.limits_for(from, *asset);
+            let already = spent.get(asset).copied().unwrap_or_default();
+            if let Some(limit) = limits.daily_limit.filter(|limit| already.saturating_add(*total) > *limit) {
+                violations.push(format!(
+                    "{} already sent in the last 24 hours plus {} exceeds daily_limit {}",
+                    self.format_amount(*asset, already),
+                    self.format_amount(*asset, *total),
+                    self.format_amount(*asset, limit)
+                ));
+            }
+        }
+        violations
+    }
+}
+
+/// Highest fee a transaction can cost
+pub fn max_fee(tx: &TypedTransaction) -> U256 {
+    let fee_cap = match tx {
+        TypedTransaction::Eip1559(tx) => tx.max_fee_per_gas.unwrap_or_default(),
+        _ => tx.gas_price().unwrap_or_default(),
+    };
+    // Saturates, so absurd values from a crafted transaction exceed any cap instead of panicking
+    fee_cap.saturating_mul(tx.gas().copied().unwrap_or_default())
+}
+
+/// Value leaving the sender in one transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outflow {
+    /// Token, or `None` for ETH
+    pub asset: Option<H160>,
+    pub recipient: H160,
+    pub amount: U256,
+    /// An ERC20 approval rather than a payment
+    pub approval: bool,
+}
+
+/// ERC20 functions that move or approve tokens besides `transfer` and `approve`
+const ERC20_SPENDING_ABI: &[&str] = &[
+    "function transferFrom(address from, address to, uint256 amount) external returns (bool)",
+    "function increaseAllowance(address spender, uint256 addedValue) external returns (bool)",
+];
+
+/// Token and disperse movements encoded in calldata sent to `to`
+///
+/// # Returns
+/// * `Option<(Vec<Outflow>, bool)>` - Outflows and whether the call forwards its
+///   ETH value to them, or `None` if the calldata is not a call the policy knows
+fn decode_call(to: H160, data: &[u8]) -> Option<(Vec<Outflow>, bool)> {
+    let erc20 = erc20_abi();
+    let spending = parse_abi(ERC20_SPENDING_ABI).expect("ERC20 spending ABI is valid");
+    let disperse = disperse_abi();
+    let call = |abi: &ethers::abi::Abi, name: &str| -> Option<Vec<Token>> {
+        let function = abi.function(name).ok()?;
+        if data.len() < 4 || data[..4] != function.short_signature() {
+            return None;
+        }
+        function.decode_input(&data[4..]).ok()
+    };
+    let pairs = |recipients: &Token, values: &Token| -> Vec<(H160, U256)> {
+        match (recipients, values) {
+            (Token::Array(recipients), Token::Array(values)) => recipients
+                .iter()
+                .zip(values)
+                .filter_map(|(r, v)| Some((r.clone().into_address()?, v.clone().into_uint()?)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+    let token = |recipient: H160, amount: U256, approval: bool| Outflow { asset: Some(to), recipient, amount, approval };
+
+    if let Some([Token::Address(recipient), Token::Uint(amount)]) = call(&erc20, "transfer").as_deref() {
+        return Some((vec![token(*recipient, *amount, false)], false));
+    }
+    if let Some([Token::Address(_), Token::Address(recipient), Token::Uint(amount)]) =
+        call(&spending, "transferFrom").as_deref()
+    {
+        return Some((vec![token(*recipient, *amount, false)], false));
+    }
+    if let Some([Token::Address(spender), Token::Uint(amount)]) = call(&erc20, "approve").as_deref() {
+        return Some((vec![token(*spender, *amount, true)], false));
+    }
+    if let Some([Token::Address(spender), Token::Uint(amount)]) = call(&spending, "increaseAllowance").as_deref() {
+        return Some((vec![token(*spender, *amount, true)], false));
+    }
+    if let Some([recipients, values]) = call(&disperse, "disperseEther").as_deref() {
+        let flows = pairs(recipients, values)
+            .into_iter()
+            .map(|(recipient, amount)| Outflow { asset: None, recipient, amount, approval: false })
+            .collect();
+        return Some((flows, true));
+    }
+    if let Some([Token::Address(asset), recipients, values]) = call(&disperse, "disperseToken").as_deref() {
+        let flows = pairs(recipients, values)
+            .into_iter()
+            .map(|(recipient, amount)| Outflow { asset: Some(*asset), recipient, amount, approval: false })
+            .collect();
+        return Some((flows, false));
+    }
+    None
+}
+
+/// Payments and approvals a transaction makes
+///
+/// ERC20 `transfer`/`transferFrom`/`approve`/`increaseAllowance` and
+/// disperse calls are decoded so their real recipients are checked. ETH
+/// sent along with any other call counts as a payment to the contract, and
+/// ETH sent with a contract creation as a payment to the new contract.
+///
+/// # Arguments
+/// * `from` - Signing account, which a created contract's address derives from
+/// * `tx` - Transaction with its nonce set
+pub fn outflows(from: H160, tx: &TypedTransaction) -> Vec<Outflow> {
+    let value = tx.value().copied().unwrap_or_default();
+    let eth = |recipient: H160| Outflow { asset: None, recipient, amount: value, approval: false };
+    let Some(to) = tx.to_addr().copied() else {
+        let created = get_contract_address(from, tx.nonce().copied().unwrap_or_default());
+        return if value.is_zero() { Vec::new() } else { vec![eth(created)] };
+    };
+    let data = tx.data().map(|d| d.to_vec()).unwrap_or_default();
+
+    let (mut flows, forwards_value) = decode_call(to, &data).unwrap_or_default();
+    if !value.is_zero() && !forwards_value {
+        flows.push(eth(to));
+    }
+    flows
+}
+
+/// Contract a transaction calls with calldata the policy cannot decode
+///
+/// What such a call moves (a `permit`, a multicall, a router swap...) is
+/// unknown, so the policy only lets it through to allowlisted contracts.
+pub fn opaque_call(tx: &TypedTransaction) -> Option<H160> {
+    let to = tx.to_addr().copied()?;
+    let data = tx.data().filter(|d| !d.is_empty())?;
+    decode_call(to, data).is_none().then_some(to)
+}
+
+/// One sent transaction's spending
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub from: H160,
+    pub nonce: U256,
+    pub chain_id: u64,
+    /// Unix time it was sent
+    pub signed_at: u64,
+    /// Asset (`None` for ETH) and amount
+    pub spent: Vec<(Option<H160>, U256)>,
+}
+
+impl LedgerEntry {
+    /// Entry for a transaction's payments, leaving out approvals and payments to `from` itself
+    pub fn new(from: H160, chain_id: u64, tx: &TypedTransaction, now: u64) -> Self {
+        let spent = outflows(from, tx)
+            .into_iter()
+            .filter(|o| o.recipient != from && !o.approval)
+            .map(|o| (o.asset, o.amount))
+            .collect();
+        LedgerEntry { from, nonce: tx.nonce().copied().unwrap_or_default(), chain_id, signed_at: now, spent }
+    }
+}
+
+/// Spending over the last 24 hours, for daily limits
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ledger {
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl Ledger {
+    /// Read the ledger; a missing file is an empty ledger
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Ledger::default());
+        }
+        let text = fs::read_to_string(path).map_err(|_| format!("Failed to read {}", path.display()))?;
+        Ok(serde_json::from_str(&text).map_err(|e| format!("Invalid policy ledger {}: {}", path.display(), e))?)
+    }
+
+    /// Write the ledger, dropping entries older than a day
+    pub fn save(&mut self, path: &Path, now: u64) -> Result<(), Box<dyn std::error::Error>> {
+        self.entries.retain(|e| e.signed_at.saturating_add(DAY_SECS) > now);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// What `from` spent on `chain_id` in the 24 hours before `now`, leaving out `nonce`
+    pub fn spent(&self, from: H160, chain_id: u64, nonce: U256, now: u64) -> BTreeMap<Option<H160>, U256> {
+        let mut spent: BTreeMap<Option<H160>, U256> = BTreeMap::new();
+        for entry in &self.entries {
+            if entry.from == from && entry.chain_id == chain_id && entry.nonce != nonce && entry.signed_at.saturating_add(DAY_SECS) > now {
+                for (asset, amount) in &entry.spent {
+                    let total = spent.entry(*asset).or_default();
+                    *total = total.saturating_add(*amount);
+                }
+            }
+        }
+        spent
+    }
+
+    /// Record a sent transaction, replacing any entry for the same nonce
+    pub fn record(&mut self, entry: LedgerEntry) {
+        self.entries
+            .retain(|e| !(e.from == entry.from && e.chain_id == entry.chain_id && e.nonce == entry.nonce));
+        self.entries.push(entry);
+    }
+}
+
+/// Outcome of checking a transaction against the policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum PolicyDecision {
+    /// No policy file
+    NoPolicy,
+    Allowed,
+    /// Violations signed anyway with `--override-policy`
+    Overridden { reason: String, violations: Vec<String> },
+    Denied { violations: Vec<String> },
+}
+
+impl PolicyDecision {
+    /// Error for a denied transaction, `None` if it may be signed
+    pub fn denial(&self, from: H160) -> Option<String> {
+        let PolicyDecision::Denied { violations } = self else {
+            return None;
+        };
+        let list: Vec<String> = violations.iter().map(|v| format!("  - {}", v)).collect();
+        Some(format!(
+            "Policy violation for {}:\n{}\nPass --override-policy \"REASON\" to sign anyway (overrides are logged)",
+            checksummed(from),
+            list.join("\n")
+        ))
+    }
+}
+
+/// Ledger path: `POLICY_LEDGER` or `policy-ledger.json`
+fn ledger_path() -> PathBuf {
+    PathBuf::from(env::var("POLICY_LEDGER").unwrap_or_else(|_| "policy-ledger.json".to_string()))
+}
+
+/// Seconds since the Unix epoch
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+impl Policy {
+    /// Decide on a transaction, given the spending recorded in `ledger`
+    ///
+    /// Violations deny the transaction unless `--override-policy` was given,
+    /// in which case the override is logged and signing goes ahead.
+    ///
+    /// # Arguments
+    /// * `from` - Signing account
+    /// * `chain_id` - Chain the transaction is signed for
+    /// * `tx` - Transaction with its nonce set
+    /// * `ledger` - Spending sent so far
+    /// * `now` - Unix time, for the daily window
+    ///
+    /// # Returns
+    /// * `Result<PolicyDecision, Box<dyn std::error::Error>>` - Decision; only `Denied` forbids signing
+    pub fn decide(
+        &self,
+        from: H160,
+        chain_id: u64,
+        tx: &TypedTransaction,
+        ledger: &Ledger,
+        now: u64,
+    ) -> Result<PolicyDecision, Box<dyn std::error::Error>> {
+        let nonce = tx.nonce().copied().unwrap_or_default();
+        let violations = self.check(from, chain_id, tx, &ledger.spent(from, chain_id, nonce, now));
+        if violations.is_empty() {
+            return Ok(PolicyDecision::Allowed);
+        }
+        let Some(reason) = OVERRIDE.lock().unwrap().clone() else {
+            return Ok(PolicyDecision::Denied { violations });
+        };
+        log_override(from, chain_id, tx, &violations, &reason, now)?;
+        let list: Vec<String> = violations.iter().map(|v| format!("  - {}", v)).collect();
+        println!("⚠️  Policy overridden ({}):\n{}", reason, list.join("\n"));
+        Ok(PolicyDecision::Overridden { reason, violations })
+    }
+}
+
+/// Check a transaction against the local policy before it is signed
+///
+/// Nothing is written to the ledger here: the transaction may never reach
+/// the node. `record_sent` adds it once it has.
+///
+/// # Arguments
+/// * `from` - Signing account
+/// * `chain_id` - Chain the transaction is signed for
+/// * `tx` - Transaction with its nonce set
+///
+/// # Returns
+/// * `Result<PolicyDecision, Box<dyn std::error::Error>>` - Decision; only `Denied` forbids signing
+pub fn enforce(from: H160, chain_id: u64, tx: &TypedTransaction) -> Result<PolicyDecision, Box<dyn std::error::Error>> {
+    let Some(policy) = Policy::load_default()? else {
+        return Ok(PolicyDecision::NoPolicy);
+    };
+    policy.decide(from, chain_id, tx, &Ledger::load(&ledger_path())?, unix_now())
+}
+
+/// Add a transaction the node accepted to the ledger, for daily limits
+///
+/// The transaction has already left, so a ledger that cannot be written
+/// is reported rather than failing the send.
+///
+/// # Arguments
+/// * `from` - Sending account
+/// * `tx` - Transaction as sent, with its nonce and chain ID set
+pub fn record_sent(from: H160, tx: &TypedTransaction) {
+    if let Err(e) = record_in_ledger(from, tx) {
+        println!("⚠️  Sent, but not added to the policy ledger: {}", e);
+    }
+}
+
+/// Write a transaction's ledger entry, if there is a policy to count it against
+fn record_in_ledger(from: H160, tx: &TypedTransaction) -> Result<(), Box<dyn std::error::Error>> {
+    if Policy::load_default()?.is_none() {
+        return Ok(());
+    }
+    let (path, now) = (ledger_path(), unix_now());
+    let chain_id = tx.chain_id().map(|id| id.as_u64()).unwrap_or_default();
+    let mut ledger = Ledger::load(&path)?;
+    ledger.record(LedgerEntry::new(from, chain_id, tx, now));
+    ledger.save(&path, now)
+}
+
+/// Append an override to the override log
+fn log_override(
+    from: H160,
+    chain_id: u64,
+    tx: &TypedTransaction,
+    violations: &[String],
+    reason: &str,
+    now: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = env::var("POLICY_OVERRIDE_LOG").unwrap_or_else(|_| "policy-overrides.log".to_string());
+    let record = serde_json::json!({
+        "time": now,
+        "from": checksummed(from),
+        "chain_id": chain_id,
+        "nonce": tx.nonce(),
+        "to": tx.to_addr().map(|a| checksummed(*a)),
+        "violations": violations,
+        "reason": reason,
+    });
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open override log {}: {}", path, e))?;
+    writeln!(file, "{}", record)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nonce::NonceManager;
+    use crate::test_support::test_wallet;
+    use crate::token::{encode_token_write, TokenWrite};
+    use ethers::middleware::SignerMiddleware;
+    use ethers::providers::{JsonRpcError, MockResponse, Provider};
+    use ethers::signers::Signer;
+    use ethers::types::{TransactionRequest, H256};
+
+    const TOKEN: &str = "0x812dD1c3EB07bb1F5f93540350EF9aF838AB0528";
+    const ALICE: &str = "0x7292dD72151DaCFBbE76305db1C8Ab1928E922E4";
+
+    fn policy() -> Policy {
+        let file: PolicyFile = serde_json::from_value(serde_json::json!({
+            "allowed_chains": [421614],
+            "max_fee": "0.001",
+            "eth": { "max_per_tx": "0.5", "daily_limit": "1" },
+            "tokens": { TOKEN: { "decimals": 6, "max_per_tx": "100" } },
+            "accounts": { "0x1111111111111111111111111111111111111111": { "eth": { "daily_limit": "5" } } },
+            "allowlist": [ALICE, TOKEN],
+            "denylist": ["0x2222222222222222222222222222222222222222"]
+        }))
+        .unwrap();
+        Policy::from_file(&file, &AddressBook::default()).unwrap()
+    }
+
+    fn send(to: H160, eth: &str) -> TypedTransaction {
+        TransactionRequest::new()
+            .to(to)
+            .value(Amount::parse_ether(eth).unwrap().raw())
+            .gas(100_000u64)
+            .gas_price(1_000_000_000u64)
+            .nonce(1u64)
+            .into()
+    }
+
+    #[test]
+    fn test_check_limits_and_lists() {
+        let policy = policy();
+        let from = H160::repeat_byte(0x99);
+        let alice: H160 = ALICE.parse().unwrap();
+        let none = BTreeMap::new();
+
+        assert!(policy.check(from, 421614, &send(alice, "0.4"), &none).is_empty());
+        // Sending to oneself is never restricted by lists or limits
+        assert!(policy.check(from, 421614, &send(from, "3"), &none).is_empty());
+
+        let violations = policy.check(from, 1, &send(H160::repeat_byte(0x22), "0.6"), &none);
+        assert_eq!(violations.len(), 3, "{:?}", violations);
+        assert!(violations[0].starts_with("chain 1"));
+        assert!(violations[1].contains("denylist"));
+        assert!(violations[2].contains("max_per_tx 0.5 ETH"));
+        assert!(policy.check(from, 421614, &send(H160::repeat_byte(0x33), "0.1"), &none)[0].contains("not on the allowlist"));
+
+        // Daily limit counts what was already spent
+        let spent = BTreeMap::from([(None, Amount::parse_ether("0.8").unwrap().raw())]);
+        assert!(policy.check(from, 421614, &send(alice, "0.3"), &spent)[0].contains("daily_limit 1 ETH"));
+        // ...and an account can have its own limit
+        assert!(policy.check(H160::repeat_byte(0x11), 421614, &send(alice, "0.3"), &spent).is_empty());
+
+        // Fee cap: 100k gas × 20 Gwei = 0.002 ETH
+        let mut expensive = send(alice, "0.1");
+        expensive.set_gas_price(20_000_000_000u64);
+        assert!(policy.check(from, 421614, &expensive, &none)[0].starts_with("maximum fee 0.002 ETH"));
+    }
+
+    #[test]
+    fn test_token_outflows() {
+        let policy = policy();
+        let from = H160::repeat_byte(0x99);
+        let token: H160 = TOKEN.parse().unwrap();
+        let alice: H160 = ALICE.parse().unwrap();
+        let call = |write: TokenWrite, amount: u64| -> TypedTransaction {
+            let data = encode_token_write(&erc20_abi(), &write, U256::from(amount)).unwrap();
+            TransactionRequest::new().to(token).data(data).gas(100_000u64).gas_price(1u64).into()
+        };
+
+        let transfer = call(TokenWrite::Transfer { to: alice }, 50_000_000);
+        assert_eq!(
+            outflows(from, &transfer),
+            vec![Outflow { asset: Some(token), recipient: alice, amount: U256::from(50_000_000), approval: false }]
+        );
+        assert!(policy.check(from, 421614, &transfer, &BTreeMap::new()).is_empty());
+
+        let too_much = call(TokenWrite::Transfer { to: alice }, 150_000_000);
+        assert!(policy.check(from, 421614, &too_much, &BTreeMap::new())[0].contains("payment of 150 of token"));
+        let unlimited = call(TokenWrite::Approve { spender: alice }, u64::MAX);
+        assert!(policy.check(from, 421614, &unlimited, &BTreeMap::new())[0].starts_with("approval of"));
+        let elsewhere = call(TokenWrite::Transfer { to: H160::repeat_byte(0x44) }, 1);
+        assert!(policy.check(from, 421614, &elsewhere, &BTreeMap::new())[0].contains("not on the allowlist"));
+    }
+
+    #[test]
+    fn test_transfer_from_and_unknown_calls() {
+        let policy = policy();
+        let from = H160::repeat_byte(0x99);
+        let token: H160 = TOKEN.parse().unwrap();
+        let spending = parse_abi(ERC20_SPENDING_ABI).unwrap();
+        let call = |to: H160, data: Vec<u8>| -> TypedTransaction {
+            TransactionRequest::new().to(to).data(data).gas(100_000u64).gas_price(1u64).into()
+        };
+
+        let owner = H160::repeat_byte(0x77);
+        let args = [Token::Address(owner), Token::Address(H160::repeat_byte(0x44)), Token::Uint(U256::from(500_000_000u64))];
+        let transfer_from = call(token, spending.function("transferFrom").unwrap().encode_input(&args).unwrap());
+        assert_eq!(
+            outflows(from, &transfer_from),
+            vec![Outflow { asset: Some(token), recipient: H160::repeat_byte(0x44), amount: U256::from(500_000_000u64), approval: false }]
+        );
+        let violations = policy.check(from, 421614, &transfer_from, &BTreeMap::new());
+        assert_eq!(violations.len(), 2, "{:?}", violations);
+        assert!(violations[0].contains("not on the allowlist"));
+        assert!(violations[1].contains("payment of 500 of token"));
+
+        let args = [Token::Address(H160::repeat_byte(0x44)), Token::Uint(U256::from(1))];
+        let increase = call(token, spending.function("increaseAllowance").unwrap().encode_input(&args).unwrap());
+        assert!(outflows(from, &increase)[0].approval);
+        assert!(policy.check(from, 421614, &increase, &BTreeMap::new())[0].contains("not on the allowlist"));
+
+        // A selector the policy does not know is only allowed to allowlisted contracts
+        let unknown = call(H160::repeat_byte(0x55), vec![0xde, 0xad, 0xbe, 0xef, 0, 1]);
+        assert!(outflows(from, &unknown).is_empty());
+        assert_eq!(opaque_call(&unknown), Some(H160::repeat_byte(0x55)));
+        assert!(policy.check(from, 421614, &unknown, &BTreeMap::new())[0].contains("cannot decode"));
+        let allowlisted = call(token, vec![0xde, 0xad, 0xbe, 0xef]);
+        assert!(policy.check(from, 421614, &allowlisted, &BTreeMap::new()).is_empty());
+
+        // ETH sent with a contract creation goes to the new contract
+        let create: TypedTransaction = TransactionRequest::new()
+            .data(vec![0x60, 0x00])
+            .value(Amount::parse_ether("0.6").unwrap().raw())
+            .nonce(3u64)
+            .gas(100_000u64)
+            .gas_price(1u64)
+            .into();
+        assert_eq!(opaque_call(&create), None);
+        let created = outflows(from, &create);
+        assert_eq!(created[0].recipient, get_contract_address(from, 3u64));
+        assert!(policy.check(from, 421614, &create, &BTreeMap::new())[1].contains("max_per_tx 0.5 ETH"));
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let path = env::temp_dir().join(format!("policy-missing-{}.json", std::process::id()));
+        assert_eq!(Policy::load(&path, false).unwrap(), None);
+        assert!(Policy::load(&path, true).unwrap_err().to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn test_overflow_is_denied() {
+        let policy = policy();
+        let from = H160::repeat_byte(0x99);
+        let alice: H160 = ALICE.parse().unwrap();
+
+        // Fee cap × gas limit beyond 2^256
+        let mut tx = send(alice, "0.1");
+        tx.set_gas(U256::MAX);
+        tx.set_gas_price(U256::MAX);
+        assert_eq!(max_fee(&tx), U256::MAX);
+        assert!(policy.check(from, 421614, &tx, &BTreeMap::new())[0].starts_with("maximum fee"));
+
+        // Disperse amounts that add up past 2^256
+        let data = disperse_abi()
+            .function("disperseEther")
+            .unwrap()
+            .encode_input(&[
+                Token::Array(vec![Token::Address(alice), Token::Address(alice)]),
+                Token::Array(vec![Token::Uint(U256::MAX), Token::Uint(U256::MAX)]),
+            ])
+            .unwrap();
+        let disperse: TypedTransaction = TransactionRequest::new()
+            .to(H160::repeat_byte(0x55))
+            .data(data)
+            .gas(100_000u64)
+            .gas_price(1u64)
+            .into();
+        let spent = BTreeMap::from([(None, U256::MAX)]);
+        let violations = policy.check(from, 421614, &disperse, &spent);
+        assert!(violations.iter().any(|v| v.contains("daily_limit")), "{:?}", violations);
+    }
+
+    #[test]
+    fn test_ledger() {
+        let from = H160::repeat_byte(0x99);
+        let mut ledger = Ledger::default();
+        let entry = |nonce: u64, eth: u64, signed_at: u64| LedgerEntry {
+            from,
+            nonce: U256::from(nonce),
+            chain_id: 421614,
+            signed_at,
+            spent: vec![(None, U256::from(eth))],
+        };
+        ledger.record(entry(1, 10, 1_000));
+        ledger.record(entry(2, 20, 1_000));
+        // A replacement for nonce 2 replaces its entry
+        ledger.record(entry(2, 5, 2_000));
+        assert_eq!(ledger.spent(from, 421614, U256::from(9), 2_000)[&None], U256::from(15));
+        // The nonce being signed is not counted against itself
+        assert_eq!(ledger.spent(from, 421614, U256::from(2), 2_000)[&None], U256::from(10));
+        // Entries age out after a day
+        assert_eq!(ledger.spent(from, 421614, U256::from(9), 1_000 + DAY_SECS)[&None], U256::from(5));
+        assert!(ledger.spent(from, 1, U256::from(9), 2_000).is_empty());
+
+        let path = env::temp_dir().join(format!("policy-ledger-{}.json", std::process::id()));
+        ledger.save(&path, 1_000 + DAY_SECS).unwrap();
+        assert_eq!(Ledger::load(&path).unwrap().entries, vec![entry(2, 5, 2_000)]);
+        fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_nonce_too_low_retry_is_counted_once() {
+        let policy = policy();
+        let wallet = test_wallet();
+        let from = wallet.address();
+        let alice: H160 = ALICE.parse().unwrap();
+        let (provider, mock) = Provider::mocked();
+        let manager = NonceManager::new(SignerMiddleware::new(provider, wallet), from);
+
+        // Another run already sent 0.5 ETH with nonce 5
+        let mut other = send(alice, "0.5");
+        other.set_nonce(5u64);
+        let mut ledger = Ledger::default();
+        ledger.record(LedgerEntry::new(from, 421614, &other, 1_000));
+
+        // Served last-in first-out: sync to 5, "nonce too low", resync to 6, send
+        mock.push::<H256, _>(H256::zero()).unwrap();
+        mock.push::<U256, _>(U256::from(6)).unwrap();
+        mock.push_response(MockResponse::Error(JsonRpcError { code: -32000, message: "nonce too low".to_string(), data: None }));
+        mock.push::<U256, _>(U256::from(5)).unwrap();
+
+        // Every attempt is checked as it would be by the signer
+        let mut tx = send(alice, "0.4");
+        tx.set_chain_id(421614u64);
+        let mut decisions = Vec::new();
+        let (nonce, _) = manager
+            .send_with(tx.clone(), &mut |nonce, _| {
+                let mut attempt = tx.clone();
+                attempt.set_nonce(nonce);
+                decisions.push(policy.decide(from, 421614, &attempt, &ledger, 1_000)?);
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(nonce, U256::from(6));
+        assert_eq!(decisions, vec![PolicyDecision::Allowed, PolicyDecision::Allowed]);
+
+        // Only the accepted attempt is recorded, next to the other run's entry
+        tx.set_nonce(nonce);
+        ledger.record(LedgerEntry::new(from, 421614, &tx, 1_000));
+        assert_eq!(ledger.entries.len(), 2);
+        assert_eq!(ledger.spent(from, 421614, U256::from(7), 1_000)[&None], Amount::parse_ether("0.9").unwrap().raw());
+        let mut next = send(alice, "0.2");
+        next.set_nonce(7u64);
+        assert!(matches!(policy.decide(from, 421614, &next, &ledger, 1_000).unwrap(), PolicyDecision::Denied { .. }));
+    }
+}
@@ -0,0 +1,26 @@
+//! Fixtures shared by the unit tests
+
+use crate::audit;
+use ethers::signers::{LocalWallet, Signer};
+use std::env;
+use std::path::PathBuf;
+use std::sync::Once;
+
+/// Private key of the test account, 0x2c7536E3605D9C16a7a3D7b1898e529396a65c23
+pub(crate) const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+/// The test account, signing for Arbitrum Sepolia
+pub(crate) fn test_wallet() -> LocalWallet {
+    TEST_KEY.parse::<LocalWallet>().unwrap().with_chain_id(421614u64)
+}
+
+/// File in the temp directory, unique to this test run
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("eth-transfer-{}-{}", std::process::id(), name))
+}
+
+/// Send the audit log to a temporary file, for tests that sign through an `AccountSigner`
+pub(crate) fn use_temp_audit_log() {
+    static SET: Once = Once::new();
+    SET.call_once(|| audit::set_log_path(temp_path("audit.log")));
+}
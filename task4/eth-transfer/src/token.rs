@@ -0,0 +1,216 @@
+use crate::accounts::AccountSigner;
+use crate::transfer::{prepare_call, PreparedTransfer};
+use arb_common::amount::Amount;
+use ethers::abi::{parse_abi, Abi, Token};
+use ethers::prelude::*;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// ERC20 functions used here plus the OpenZeppelin v5 custom errors
+const ERC20_ABI: &[&str] = &[
+    "function decimals() external view returns (uint8)",
+    "function symbol() external view returns (string)",
+    "function balanceOf(address account) external view returns (uint256)",
+    "function allowance(address owner, address spender) external view returns (uint256)",
+    "function transfer(address to, uint256 amount) external returns (bool)",
+    "function approve(address spender, uint256 amount) external returns (bool)",
+    "error ERC20InsufficientBalance(address sender, uint256 balance, uint256 needed)",
+    "error ERC20InvalidSender(address sender)",
+    "error ERC20InvalidReceiver(address receiver)",
+    "error ERC20InsufficientAllowance(address spender, uint256 allowance, uint256 needed)",
+    "error ERC20InvalidApprover(address approver)",
+    "error ERC20InvalidSpender(address spender)",
+];
+
+/// A state-changing ERC20 call
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenWrite {
+    Transfer { to: H160 },
+    Approve { spender: H160 },
+}
+
+/// Built-in ERC20 ABI
+pub fn erc20_abi() -> Abi {
+    parse_abi(ERC20_ABI).expect("ERC20 ABI is valid")
+}
+
+/// Load a contract ABI from a JSON file (a bare ABI array or a build artifact with an `abi` field)
+///
+/// The built-in ERC20 functions and errors are merged in, so a token-specific
+/// ABI only needs to add its own custom errors.
+///
+/// # Arguments
+/// * `path` - ABI JSON file
+///
+/// # Returns
+/// * `Result<Abi, Box<dyn std::error::Error>>` - Combined ABI
+pub fn load_abi(path: &Path) -> Result<Abi, Box<dyn std::error::Error>> {
+    let json = fs::read_to_string(path).map_err(|_| format!("Failed to read ABI file: {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&json)?;
+    let abi_value = value.get("abi").cloned().unwrap_or(value);
+    let mut abi: Abi = serde_json::from_value(abi_value)
+        .map_err(|e| format!("Invalid ABI in {}: {}", path.display(), e))?;
+
+    let builtin = erc20_abi();
+    for (name, functions) in builtin.functions {
+        abi.functions.entry(name).or_insert(functions);
+    }
+    for (name, errors) in builtin.errors {
+        abi.errors.entry(name).or_insert(errors);
+    }
+    Ok(abi)
+}
+
+/// ABI for token calls: `--abi` file if given, else `TOKEN_ABI_PATH`, else the built-in ERC20 ABI
+pub fn token_abi(path: Option<&str>) -> Result<Abi, Box<dyn std::error::Error>> {
+    match path.map(String::from).or_else(|| env::var("TOKEN_ABI_PATH").ok()) {
+        Some(path) => load_abi(Path::new(&path)),
+        None => Ok(erc20_abi()),
+    }
+}
+
+/// Read a token's symbol and decimals
+///
+/// # Arguments
+/// * `token` - Token contract address
+///
+/// # Returns
+/// * `Result<(String, u8), Box<dyn std::error::Error>>` - Symbol and decimals
+pub async fn get_token_metadata(token: H160) -> Result<(String, u8), Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let contract = BaseContract::from(erc20_abi());
+
+    let call = |data: Bytes| TransactionRequest::new().to(token).data(data);
+    let output = provider.call(&call(contract.encode("decimals", ())?).into(), None).await?;
+    let decimals: u8 = contract.decode_output("decimals", output)?;
+    let output = provider.call(&call(contract.encode("symbol", ())?).into(), None).await?;
+    let symbol: String = contract.decode_output("symbol", output)?;
+
+    Ok((symbol, decimals))
+}
+
+/// Read a token balance
+///
+/// # Arguments
+/// * `token` - Token contract address
+/// * `owner` - Account to query
+///
+/// # Returns
+/// * `Result<U256, Box<dyn std::error::Error>>` - Raw balance in the token's smallest unit
+pub async fn get_token_balance(token: H160, owner: H160) -> Result<U256, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let contract = BaseContract::from(erc20_abi());
+
+    let tx = TransactionRequest::new().to(token).data(contract.encode("balanceOf", owner)?);
+    let output = provider.call(&tx.into(), None).await?;
+    Ok(contract.decode_output("balanceOf", output)?)
+}
+
+/// Read how much a spender may move on an owner's behalf
+///
+/// # Arguments
+/// * `token` - Token contract address
+/// * `owner` - Token holder
+/// * `spender` - Approved spender
+///
+/// # Returns
+/// * `Result<U256, Box<dyn std::error::Error>>` - Raw allowance
+pub async fn get_token_allowance(token: H160, owner: H160, spender: H160) -> Result<U256, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let contract = BaseContract::from(erc20_abi());
+
+    let tx = TransactionRequest::new().to(token).data(contract.encode("allowance", (owner, spender))?);
+    let output = provider.call(&tx.into(), None).await?;
+    Ok(contract.decode_output("allowance", output)?)
+}
+
+/// Calldata for a token write
+///
+/// # Arguments
+/// * `abi` - Token ABI
+/// * `write` - Transfer or approve
+/// * `amount` - Raw amount in the token's smallest unit
+///
+/// # Returns
+/// * `Result<Bytes, Box<dyn std::error::Error>>` - Encoded call
+pub fn encode_token_write(abi: &Abi, write: &TokenWrite, amount: U256) -> Result<Bytes, Box<dyn std::error::Error>> {
+    let (name, target) = match write {
+        TokenWrite::Transfer { to } => ("transfer", *to),
+        TokenWrite::Approve { spender } => ("approve", *spender),
+    };
+    let data = abi
+        .function(name)?
+        .encode_input(&[Token::Address(target), Token::Uint(amount)])?;
+    Ok(Bytes::from(data))
+}
+
+/// Build a token transfer or approval
+///
+/// The call is simulated against pending state while estimating gas, so a
+/// call that would revert fails here with its decoded reason.
+///
+/// # Arguments
+/// * `signer` - Signer for the token holder
+/// * `token` - Token contract address
+/// * `write` - Transfer or approve
+/// * `amount` - Human-readable amount, e.g. "12.5"
+/// * `abi` - Token ABI, used for encoding and custom errors
+///
+/// # Returns
+/// * `Result<(PreparedTransfer, Amount), Box<dyn std::error::Error>>` - Transaction and the parsed amount
+pub async fn prepare_token_write(
+    signer: &AccountSigner,
+    token: H160,
+    write: &TokenWrite,
+    amount: &str,
+    abi: Abi,
+) -> Result<(PreparedTransfer, Amount), Box<dyn std::error::Error>> {
+    let (_, decimals) = get_token_metadata(token).await?;
+    let amount = Amount::parse(amount, decimals)?;
+    let data = encode_token_write(&abi, write, amount.raw())?;
+    let prepared = prepare_call(signer, token, U256::zero(), Some(data), None, Some(abi)).await?;
+    Ok((prepared, amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_token_write() {
+        let abi = erc20_abi();
+        let to = H160::repeat_byte(0x11);
+        let data = encode_token_write(&abi, &TokenWrite::Transfer { to }, U256::from(1_000_000)).unwrap();
+        // transfer(address,uint256)
+        assert_eq!(&data[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(&data[16..36], to.as_bytes());
+
+        let data = encode_token_write(&abi, &TokenWrite::Approve { spender: to }, U256::MAX).unwrap();
+        // approve(address,uint256)
+        assert_eq!(&data[..4], &[0x09, 0x5e, 0xa7, 0xb3]);
+    }
+
+    #[test]
+    fn test_load_abi_merges_builtin_errors() {
+        let path = env::temp_dir().join(format!("eth-transfer-abi-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{ "abi": [ { "type": "error", "name": "TransfersPaused", "inputs": [] } ] }"#,
+        )
+        .unwrap();
+
+        let abi = load_abi(&path).unwrap();
+        assert!(abi.error("TransfersPaused").is_ok());
+        assert!(abi.error("ERC20InsufficientBalance").is_ok());
+        assert!(abi.function("transfer").is_ok());
+
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,449 @@
+//! Transaction confirmation tracking
+//!
+//! Polls a transaction hash until it has the requested number of
+//! confirmations, the timeout expires, or it is found to have been dropped
+//! or replaced. RPC errors while polling are reported and retried on the
+//! next poll instead of aborting the wait.
+
+use ethers::prelude::*;
+use std::env;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// Confirmations to wait for when nothing is configured
+const DEFAULT_CONFIRMATIONS: u64 = 1;
+
+/// Timeout when nothing is configured, in seconds
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+/// Poll interval when nothing is configured, in seconds
+const DEFAULT_POLL_SECS: u64 = 2;
+
+/// Polls in a row without the node knowing the transaction before it counts as dropped
+const DEFAULT_MISSING_POLLS: u32 = 5;
+
+/// How long and how deep to wait
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackerConfig {
+    /// Blocks required, counting the inclusion block
+    pub confirmations: u64,
+    /// Total time to wait before giving up
+    pub timeout: Duration,
+    /// Delay between polls
+    pub poll_interval: Duration,
+    /// Consecutive "not found" polls before the transaction counts as dropped
+    pub missing_polls: u32,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        TrackerConfig {
+            confirmations: DEFAULT_CONFIRMATIONS,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            poll_interval: Duration::from_secs(DEFAULT_POLL_SECS),
+            missing_polls: DEFAULT_MISSING_POLLS,
+        }
+    }
+}
+
+impl TrackerConfig {
+    /// Tracker settings from command-line flags, falling back to the environment
+    ///
+    /// `--confirmations N`, `--timeout SECS` and `--poll SECS` override
+    /// `TX_CONFIRMATIONS`, `TX_TIMEOUT_SECS` and `TX_POLL_SECS`.
+    ///
+    /// # Arguments
+    /// * `args` - Command-line arguments
+    ///
+    /// # Returns
+    /// * `Result<TrackerConfig, Box<dyn std::error::Error>>` - Settings, or an error for a malformed value
+    pub fn from_args(args: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let setting = |flag: &str, var: &str| -> Result<Option<u64>, Box<dyn std::error::Error>> {
+            let value = args
+                .iter()
+                .position(|a| a == flag)
+                .and_then(|i| args.get(i + 1))
+                .cloned()
+                .or_else(|| env::var(var).ok());
+            match value {
+                Some(v) => Ok(Some(
+                    v.trim()
+                        .parse::<u64>()
+                        .map_err(|_| format!("Invalid value for {} / {}: {}", flag, var, v))?,
+                )),
+                None => Ok(None),
+            }
+        };
+
+        let mut config = TrackerConfig::default();
+        if let Some(confirmations) = setting("--confirmations", "TX_CONFIRMATIONS")? {
+            if confirmations == 0 {
+                return Err("Confirmations must be at least 1".into());
+            }
+            config.confirmations = confirmations;
+        }
+        if let Some(secs) = setting("--timeout", "TX_TIMEOUT_SECS")? {
+            config.timeout = Duration::from_secs(secs);
+        }
+        if let Some(secs) = setting("--poll", "TX_POLL_SECS")? {
+            if secs == 0 {
+                return Err("Poll interval must be at least 1 second".into());
+            }
+            config.poll_interval = Duration::from_secs(secs);
+        }
+        Ok(config)
+    }
+}
+
+/// How a tracked transaction ended up
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxStatus {
+    /// Mined with enough confirmations; check `receipt.status` for success
+    Confirmed { receipt: Box<TransactionReceipt>, confirmations: u64 },
+    /// Another transaction from the same sender used its nonce
+    Replaced { nonce: U256 },
+    /// The node no longer knows the transaction and its nonce is unused
+    Dropped,
+    /// Still pending, or not yet deep enough, when the timeout expired
+    TimedOut { block: Option<U64>, confirmations: u64 },
+}
+
+/// Progress reported while waiting
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackEvent {
+    /// Known to the node but not mined yet
+    Pending,
+    /// Not (or no longer) known to the node
+    NotFound,
+    /// Mined, waiting for more blocks
+    Included { block: U64, confirmations: u64 },
+    /// The inclusion block was reorganised away
+    Reorged { block: U64 },
+    /// A poll failed; the next one is tried anyway
+    RpcError(String),
+}
+
+/// Blocks on top of and including the inclusion block
+pub fn confirmations(inclusion_block: U64, latest_block: U64) -> u64 {
+    if latest_block < inclusion_block {
+        0
+    } else {
+        (latest_block - inclusion_block).as_u64() + 1
+    }
+}
+
+/// What has been learnt about the transaction so far
+#[derive(Debug, Default)]
+struct TrackState {
+    /// Sender and nonce, once the transaction has been seen
+    sender: Option<(H160, U256)>,
+    /// Block it was last seen in
+    block: Option<U64>,
+    confirmations: u64,
+    missing: u32,
+}
+
+/// One round of polling; `Ok(None)` means keep waiting
+async fn poll<M: Middleware>(
+    client: &M,
+    tx_hash: H256,
+    config: &TrackerConfig,
+    state: &mut TrackState,
+    on_event: &mut impl FnMut(&TrackEvent),
+) -> Result<Option<TxStatus>, M::Error> {
+    if let Some(receipt) = client.get_transaction_receipt(tx_hash).await? {
+        if let Some(block) = receipt.block_number {
+            let latest = client.get_block_number().await?;
+            let depth = confirmations(block, latest);
+            state.block = Some(block);
+            state.confirmations = depth;
+            state.missing = 0;
+            if depth >= config.confirmations {
+                return Ok(Some(TxStatus::Confirmed { receipt: Box::new(receipt), confirmations: depth }));
+            }
+            on_event(&TrackEvent::Included { block, confirmations: depth });
+            return Ok(None);
+        }
+    }
+
+    if let Some(block) = state.block.take() {
+        state.confirmations = 0;
+        on_event(&TrackEvent::Reorged { block });
+    }
+
+    match client.get_transaction(tx_hash).await? {
+        Some(tx) => {
+            state.sender = Some((tx.from, tx.nonce));
+            state.missing = 0;
+            on_event(&TrackEvent::Pending);
+        }
+        None => {
+            state.missing += 1;
+            if let Some((from, nonce)) = state.sender {
+                let mined = client.get_transaction_count(from, Some(BlockNumber::Latest.into())).await?;
+                if mined > nonce {
+                    return Ok(Some(TxStatus::Replaced { nonce }));
+                }
+            }
+            if state.missing >= config.missing_polls {
+                return Ok(Some(TxStatus::Dropped));
+            }
+            on_event(&TrackEvent::NotFound);
+        }
+    }
+    Ok(None)
+}
+
+/// Wait for a transaction to be confirmed
+///
+/// # Arguments
+/// * `client` - Provider or middleware
+/// * `tx_hash` - Transaction to track
+/// * `config` - Depth, timeout and poll interval
+/// * `on_event` - Called with progress after every poll that does not finish the wait
+///
+/// # Returns
+/// * `TxStatus` - Confirmed, replaced, dropped or timed out; RPC errors never end the wait early
+pub async fn wait_for_confirmations<M: Middleware>(
+    client: &M,
+    tx_hash: H256,
+    config: &TrackerConfig,
+    mut on_event: impl FnMut(&TrackEvent),
+) -> TxStatus {
+    let deadline = Instant::now() + config.timeout;
+    let mut state = TrackState::default();
+
+    loop {
+        match poll(client, tx_hash, config, &mut state, &mut on_event).await {
+            Ok(Some(status)) => return status,
+            Ok(None) => {}
+            Err(e) => on_event(&TrackEvent::RpcError(e.to_string())),
+        }
+        if Instant::now() + config.poll_interval > deadline {
+            return TxStatus::TimedOut { block: state.block, confirmations: state.confirmations };
+        }
+        sleep(config.poll_interval).await;
+    }
+}
+
+/// One round of polling for a set of transactions sharing a nonce
+async fn poll_any<M: Middleware>(
+    client: &M,
+    tx_hashes: &[H256],
+    sender: H160,
+    nonce: U256,
+    config: &TrackerConfig,
+    state: &mut TrackState,
+    on_event: &mut impl FnMut(&TrackEvent),
+) -> Result<Option<TxStatus>, M::Error> {
+    let mut mined = None;
+    for hash in tx_hashes {
+        if let Some(receipt) = client.get_transaction_receipt(*hash).await? {
+            if receipt.block_number.is_some() {
+                mined = Some(receipt);
+                break;
+            }
+        }
+    }
+
+    match mined {
+        Some(receipt) => {
+            let block = receipt.block_number.unwrap_or_default();
+            let latest = client.get_block_number().await?;
+            let depth = confirmations(block, latest);
+            state.block = Some(block);
+            state.confirmations = depth;
+            if depth >= config.confirmations {
+                return Ok(Some(TxStatus::Confirmed { receipt: Box::new(receipt), confirmations: depth }));
+            }
+            on_event(&TrackEvent::Included { block, confirmations: depth });
+        }
+        None => {
+            if let Some(block) = state.block.take() {
+                state.confirmations = 0;
+                on_event(&TrackEvent::Reorged { block });
+            }
+            let used = client.get_transaction_count(sender, Some(BlockNumber::Latest.into())).await?;
+            if used > nonce {
+                // The nonce is used; give our versions one more look before
+                // blaming a transaction we do not know about
+                for hash in tx_hashes {
+                    if client.get_transaction_receipt(*hash).await?.is_some() {
+                        return Ok(None);
+                    }
+                }
+                return Ok(Some(TxStatus::Replaced { nonce }));
+            }
+            on_event(&TrackEvent::Pending);
+        }
+    }
+    Ok(None)
+}
+
+/// Wait for whichever of several transactions with the same nonce is confirmed
+///
+/// Used after a speed-up or cancel, when either the original or a
+/// replacement can land. The confirmed receipt's `transaction_hash` names
+/// the winner.
+///
+/// # Arguments
+/// * `client` - Provider or middleware
+/// * `tx_hashes` - Original and replacement transactions
+/// * `sender` - Account that sent them
+/// * `nonce` - Nonce they share
+/// * `config` - Depth, timeout and poll interval
+/// * `on_event` - Called with progress after every poll that does not finish the wait
+///
+/// # Returns
+/// * `TxStatus` - Confirmed, replaced by an unknown transaction, or timed out
+pub async fn wait_for_any<M: Middleware>(
+    client: &M,
+    tx_hashes: &[H256],
+    sender: H160,
+    nonce: U256,
+    config: &TrackerConfig,
+    mut on_event: impl FnMut(&TrackEvent),
+) -> TxStatus {
+    let deadline = Instant::now() + config.timeout;
+    let mut state = TrackState::default();
+
+    loop {
+        match poll_any(client, tx_hashes, sender, nonce, config, &mut state, &mut on_event).await {
+            Ok(Some(status)) => return status,
+            Ok(None) => {}
+            Err(e) => on_event(&TrackEvent::RpcError(e.to_string())),
+        }
+        if Instant::now() + config.poll_interval > deadline {
+            return TxStatus::TimedOut { block: state.block, confirmations: state.confirmations };
+        }
+        sleep(config.poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::{JsonRpcError, MockResponse};
+
+    fn fast_config() -> TrackerConfig {
+        TrackerConfig {
+            confirmations: 1,
+            timeout: Duration::from_secs(5),
+            poll_interval: Duration::from_millis(1),
+            missing_polls: 2,
+        }
+    }
+
+    #[test]
+    fn test_confirmations() {
+        assert_eq!(confirmations(U64::from(100), U64::from(100)), 1);
+        assert_eq!(confirmations(U64::from(100), U64::from(104)), 5);
+        // Node behind the block we saw the receipt in
+        assert_eq!(confirmations(U64::from(100), U64::from(99)), 0);
+    }
+
+    #[test]
+    fn test_config_from_args() {
+        let args: Vec<String> = ["--confirmations", "3", "--timeout", "30", "--poll", "1"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let config = TrackerConfig::from_args(&args).unwrap();
+        assert_eq!(config.confirmations, 3);
+        assert_eq!(config.timeout, Duration::from_secs(30));
+        assert_eq!(config.poll_interval, Duration::from_secs(1));
+
+        let args = vec!["--confirmations".to_string(), "0".to_string()];
+        assert!(TrackerConfig::from_args(&args).is_err());
+        let args = vec!["--timeout".to_string(), "soon".to_string()];
+        assert!(TrackerConfig::from_args(&args).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_depth_through_rpc_errors() {
+        let (provider, mock) = Provider::mocked();
+        let receipt = TransactionReceipt {
+            block_number: Some(U64::from(100)),
+            status: Some(U64::from(1)),
+            ..Default::default()
+        };
+        let config = TrackerConfig { confirmations: 3, ..fast_config() };
+
+        // Responses are served last-in first-out: an RPC error, then one
+        // confirmation, then three
+        mock.push::<U64, _>(U64::from(102)).unwrap();
+        mock.push::<TransactionReceipt, _>(receipt.clone()).unwrap();
+        mock.push::<U64, _>(U64::from(100)).unwrap();
+        mock.push::<TransactionReceipt, _>(receipt.clone()).unwrap();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32000,
+            message: "upstream timeout".to_string(),
+            data: None,
+        }));
+
+        let mut events = Vec::new();
+        let status = wait_for_confirmations(&provider, H256::zero(), &config, |e| events.push(e.clone())).await;
+        assert_eq!(status, TxStatus::Confirmed { receipt: Box::new(receipt), confirmations: 3 });
+        assert!(matches!(events[0], TrackEvent::RpcError(_)));
+        assert_eq!(events[1], TrackEvent::Included { block: U64::from(100), confirmations: 1 });
+    }
+
+    #[tokio::test]
+    async fn test_detects_replacement() {
+        let (provider, mock) = Provider::mocked();
+        let tx = Transaction { from: H160::repeat_byte(0x11), nonce: U256::from(7), ..Default::default() };
+
+        // Pending first, then gone with the sender's nonce already used
+        mock.push::<U256, _>(U256::from(8)).unwrap();
+        mock.push(serde_json::Value::Null).unwrap();
+        mock.push(serde_json::Value::Null).unwrap();
+        mock.push::<Transaction, _>(tx).unwrap();
+        mock.push(serde_json::Value::Null).unwrap();
+
+        let status = wait_for_confirmations(&provider, H256::zero(), &fast_config(), |_| {}).await;
+        assert_eq!(status, TxStatus::Replaced { nonce: U256::from(7) });
+    }
+
+    #[tokio::test]
+    async fn test_detects_dropped() {
+        let (provider, mock) = Provider::mocked();
+        for _ in 0..4 {
+            mock.push(serde_json::Value::Null).unwrap();
+        }
+
+        let status = wait_for_confirmations(&provider, H256::zero(), &fast_config(), |_| {}).await;
+        assert_eq!(status, TxStatus::Dropped);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_any_reports_the_version_that_landed() {
+        let (provider, mock) = Provider::mocked();
+        let (original, replacement) = (H256::repeat_byte(0x01), H256::repeat_byte(0x02));
+        let receipt = TransactionReceipt {
+            transaction_hash: replacement,
+            block_number: Some(U64::from(50)),
+            status: Some(U64::from(1)),
+            ..Default::default()
+        };
+
+        // Served last-in first-out: neither mined and the nonce unused, then
+        // the replacement mined
+        mock.push::<U64, _>(U64::from(50)).unwrap();
+        mock.push::<TransactionReceipt, _>(receipt.clone()).unwrap();
+        mock.push(serde_json::Value::Null).unwrap();
+        mock.push::<U256, _>(U256::from(4)).unwrap();
+        mock.push(serde_json::Value::Null).unwrap();
+        mock.push(serde_json::Value::Null).unwrap();
+
+        let status = wait_for_any(
+            &provider,
+            &[original, replacement],
+            H160::repeat_byte(0x11),
+            U256::from(4),
+            &fast_config(),
+            |_| {},
+        )
+        .await;
+        assert_eq!(status, TxStatus::Confirmed { receipt: Box::new(receipt), confirmations: 1 });
+    }
+}
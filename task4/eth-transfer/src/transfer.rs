@@ -1,29 +1,52 @@
+use crate::accounts::AccountSigner;
+use crate::hd::{derive_wallet, mnemonic_index, read_mnemonic, read_mnemonic_passphrase};
+use crate::keystore::{decrypt_keystore, plaintext_key_allowed, wallet_from_plaintext, PassphraseSource};
+use crate::nonce::NonceManager;
+use arb_common::address::{checksummed, parse_address, ParsedAddress};
+use arb_common::amount::Amount;
+use arb_common::revert::simulate_call;
+use ethers::abi::Abi;
 use ethers::prelude::*;
-use ethers::utils::{format_units, parse_ether};
+use ethers::types::transaction::eip2718::TypedTransaction;
 use std::env;
-use std::str::FromStr;
+use std::path::Path;
+use zeroize::Zeroizing;
 
 /// Validate an Ethereum address format
 ///
+/// Mixed-case input must match its EIP-55 checksum. The zero address and
+/// precompiles are rejected since they are never meant to receive funds.
+/// Input without a checksum is accepted with a warning the caller shows.
+///
 /// # Arguments
 /// * `address_str` - The address string to validate
 ///
 /// # Returns
-/// * `Result<H160, Box<dyn std::error::Error>>` - Valid address or error
-pub fn validate_address(address_str: &str) -> Result<H160, Box<dyn std::error::Error>> {
-    // Remove 0x prefix if present
-    let clean_address = address_str.strip_prefix("0x").unwrap_or(address_str);
+/// * `Result<ParsedAddress, Box<dyn std::error::Error>>` - Valid address with its warnings, or error
+pub fn validate_address(address_str: &str) -> Result<ParsedAddress, Box<dyn std::error::Error>> {
+    let parsed = parse_address(address_str)?;
+    check_recipient(&parsed)?;
+    Ok(parsed)
+}
 
-    // Check if address is valid length (40 hex chars + optional 0x = 42)
-    if clean_address.len() != 40 {
-        return Err(format!("Invalid address length: {}", address_str).into());
+/// Reject recipients that would lose the funds (zero address, precompiles)
+///
+/// # Arguments
+/// * `parsed` - Parsed recipient address
+///
+/// # Returns
+/// * `Result<(), Box<dyn std::error::Error>>` - Ok if the address can receive funds
+pub fn check_recipient(parsed: &ParsedAddress) -> Result<(), Box<dyn std::error::Error>> {
+    if parsed.is_unusable_recipient() {
+        let reason = parsed
+            .warnings
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(format!("Refusing to use {} as a recipient: {}", checksummed(parsed.address), reason).into());
     }
-
-    // Try to parse the address
-    let address = H160::from_str(address_str)
-        .map_err(|_| format!("Invalid address format: {}", address_str))?;
-
-    Ok(address)
+    Ok(())
 }
 
 /// Get the current gas price from Arbitrum Sepolia testnet
@@ -40,26 +63,50 @@ pub async fn get_gas_price() -> Result<U256, Box<dyn std::error::Error>> {
     Ok(gas_price)
 }
 
-/// Create a wallet from private key environment variable
+/// Get the chain ID of the configured RPC endpoint
+///
+/// # Returns
+/// * `Result<u64, Box<dyn std::error::Error>>` - Chain ID
+pub async fn get_chain_id() -> Result<u64, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    Ok(provider.get_chainid().await?.as_u64())
+}
+
+/// Create the signing wallet
+///
+/// Uses the encrypted keystore at `KEYSTORE_PATH`, with the passphrase from
+/// `KEYSTORE_PASSWORD_FILE` or a prompt. Otherwise, if `MNEMONIC_PATH` is set,
+/// derives the account at `m/44'/60'/0'/0/<MNEMONIC_INDEX>`. A plaintext `PRIVATE_KEY` is only
+/// accepted when `ALLOW_PLAINTEXT_KEY=1` is set. The wallet's signing key is
+/// zeroized when it is dropped after signing.
 ///
 /// # Returns
 /// * `Result<LocalWallet, Box<dyn std::error::Error>>` - Wallet with private key
 pub fn get_wallet() -> Result<LocalWallet, Box<dyn std::error::Error>> {
-    let private_key_str = env::var("PRIVATE_KEY")
-        .map_err(|_| "PRIVATE_KEY environment variable not set".to_string())?;
-
-    // Remove 0x prefix if present
-    let clean_key = private_key_str.strip_prefix("0x").unwrap_or(&private_key_str);
+    if let Ok(path) = env::var("KEYSTORE_PATH") {
+        return decrypt_keystore(Path::new(&path), &PassphraseSource::from_env());
+    }
+    if env::var("MNEMONIC_PATH").is_ok() {
+        let phrase = read_mnemonic()?;
+        let passphrase = read_mnemonic_passphrase()?;
+        return derive_wallet(&phrase, mnemonic_index()?, passphrase.as_ref().map(|p| p.as_str()));
+    }
 
-    // Parse private key
-    let private_key = H256::from_str(clean_key)
-        .map_err(|_| "Invalid private key format".to_string())?;
+    let private_key_str = Zeroizing::new(env::var("PRIVATE_KEY").map_err(|_| {
+        "No signer configured: set KEYSTORE_PATH, MNEMONIC_PATH, or PRIVATE_KEY with ALLOW_PLAINTEXT_KEY=1"
+            .to_string()
+    })?);
+    if !plaintext_key_allowed() {
+        return Err("PRIVATE_KEY is set but plaintext keys are disabled: \
+             use KEYSTORE_PATH, or set ALLOW_PLAINTEXT_KEY=1 to opt in"
+            .into());
+    }
 
     // Create wallet (will use chain ID from provider later)
-    let wallet = LocalWallet::from_bytes(&private_key.0)
-        .map_err(|_| "Failed to create wallet from private key".to_string())?;
-
-    Ok(wallet)
+    wallet_from_plaintext(&private_key_str)
 }
 
 /// Get ETH balance of an address
@@ -68,59 +115,350 @@ pub fn get_wallet() -> Result<LocalWallet, Box<dyn std::error::Error>> {
 /// * `address` - The address to check balance for
 ///
 /// # Returns
-/// * `Result<String, Box<dyn std::error::Error>>` - Balance formatted in ETH
-pub async fn get_balance(address: H160) -> Result<String, Box<dyn std::error::Error>> {
+/// * `Result<Amount, Box<dyn std::error::Error>>` - Balance in ETH
+pub async fn get_balance(address: H160) -> Result<Amount, Box<dyn std::error::Error>> {
     let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
         .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
 
     let provider = Provider::<Http>::try_from(rpc_url)?;
     let balance = provider.get_balance(address, None).await?;
-    let balance_eth = format_units(balance, "ether")?;
 
-    Ok(balance_eth)
+    Ok(Amount::from_wei(balance))
+}
+
+/// A fully built transfer, ready to be signed, simulated or sent
+#[derive(Debug, Clone)]
+pub struct PreparedTransfer {
+    /// Signer bound to the connected chain ID
+    pub signer: AccountSigner,
+    /// Transaction with nonce, gas and chain ID filled in
+    pub tx: TypedTransaction,
+    pub base_fee: U256,
+    pub gas_price: U256,
+    /// How `gas_price` was derived, e.g. "base_fee + 20% + 0.01 Gwei"
+    pub fee_basis: String,
+    pub gas_limit: U256,
+    /// Amount in wei
+    pub value: U256,
+    /// ABI of the called contract, used to decode custom revert errors
+    pub abi: Option<Abi>,
+}
+
+impl PreparedTransfer {
+    /// Highest fee the transaction can cost (gas price × gas limit)
+    pub fn max_fee(&self) -> U256 {
+        self.gas_price * self.gas_limit
+    }
+
+    /// Amount plus the maximum fee
+    pub fn total_cost(&self) -> U256 {
+        self.value + self.max_fee()
+    }
 }
 
-/// Transfer ETH from sender to receiver on Arbitrum Sepolia
+/// Build an ETH transfer from the signer's account on Arbitrum Sepolia
 ///
 /// # Arguments
-/// * `from_address` - Sender address
+/// * `signer` - Signer for the sending account; the sender is its address
 /// * `to_address` - Receiver address
 /// * `amount_ether` - Amount to transfer in ETH (as string, e.g., "0.001")
 ///
 /// # Returns
-/// * `Result<String, Box<dyn std::error::Error>>` - Transaction hash
-pub async fn transfer_eth(
-    from_address: H160,
+/// * `Result<PreparedTransfer, Box<dyn std::error::Error>>` - Transaction and its fees
+pub async fn prepare_transfer(
+    signer: &AccountSigner,
     to_address: H160,
     amount_ether: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
+) -> Result<PreparedTransfer, Box<dyn std::error::Error>> {
+    // Parse amount exactly (rejects more than 18 decimal places)
+    let amount_wei = Amount::parse_ether(amount_ether)?.raw();
+
+    // Standard gas limit for ETH transfer
+    let gas_limit = U256::from(100_000); // Use 100k to be safe on L2
+
+    prepare_call(signer, to_address, amount_wei, None, Some(gas_limit), None).await
+}
+
+/// Arbitrum's NodeInterface, a virtual contract answering gas questions over `eth_call`
+const NODE_INTERFACE: &str = "0x00000000000000000000000000000000000000C8";
+
+/// Default margin on the base fee and L1 gas of a sweep, in percent
+pub const SWEEP_HEADROOM_PERCENT: u64 = 10;
+
+/// Gas components of a sweep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SweepFees {
+    /// L2 execution gas
+    pub l2_gas: U256,
+    /// Gas charged for posting the transaction's data to L1
+    pub l1_gas: U256,
+    /// Gas limit: L2 gas plus the L1 gas with headroom
+    pub gas_limit: U256,
+    /// Base fee plus headroom
+    pub max_fee_per_gas: U256,
+}
+
+impl SweepFees {
+    /// Fee components with `headroom_percent` added to the L1 gas and the base fee
+    ///
+    /// The L1 part is priced in L2 gas at the base fee of the block the
+    /// transaction lands in, so it is the part that moves if the base fee
+    /// does. Unused gas is refunded, so headroom only costs while in flight.
+    /// The inputs come from the node, so a fee that overflows is an error.
+    pub fn new(base_fee: U256, l2_gas: U256, l1_gas: U256, headroom_percent: u64) -> Result<Self, Box<dyn std::error::Error>> {
+        let bump = |fee: U256| bump_fee(fee, headroom_percent).ok();
+        let overflow = || format!("Sweep fee overflows 256 bits (base fee {} wei, L2 gas {}, L1 gas {})", base_fee, l2_gas, l1_gas);
+        let gas_limit = bump(l1_gas).and_then(|l1| l2_gas.checked_add(l1)).ok_or_else(overflow)?;
+        let max_fee_per_gas = bump(base_fee).ok_or_else(overflow)?;
+        let fees = SweepFees { l2_gas, l1_gas, gas_limit, max_fee_per_gas };
+        fees.max_fee().map_err(|_| overflow())?;
+        Ok(fees)
+    }
+
+    /// Most the transaction can be charged
+    pub fn max_fee(&self) -> Result<U256, Box<dyn std::error::Error>> {
+        Ok(self.gas_limit.checked_mul(self.max_fee_per_gas).ok_or("Sweep fee overflows 256 bits")?)
+    }
+}
+
+/// Amount left to send once the maximum fee is set aside
+///
+/// # Arguments
+/// * `balance` - Sender balance in wei
+/// * `fees` - Sweep fee components
+///
+/// # Returns
+/// * `Result<U256, Box<dyn std::error::Error>>` - Amount in wei, or an error if the balance does not cover the fee
+pub fn sweep_amount(balance: U256, fees: &SweepFees) -> Result<U256, Box<dyn std::error::Error>> {
+    let max_fee = fees.max_fee()?;
+    match balance.checked_sub(max_fee) {
+        Some(amount) if !amount.is_zero() => Ok(amount),
+        _ => Err(format!(
+            "Balance {} ETH does not cover the maximum fee of {} ETH; nothing to sweep",
+            Amount::from_wei(balance),
+            Amount::from_wei(max_fee)
+        )
+        .into()),
+    }
+}
+
+/// Split `gasEstimateComponents` output into L2 and L1 gas
+///
+/// # Returns
+/// * `Option<(U256, U256)>` - L2 and L1 gas, or `None` if the node reports more L1 gas than the total
+pub fn split_gas_estimate(total: u64, l1: u64) -> Option<(U256, U256)> {
+    total.checked_sub(l1).map(|l2| (U256::from(l2), U256::from(l1)))
+}
+
+/// Build a transfer of the signer's entire balance minus the fee
+///
+/// The gas is split into its L2 and L1 components with Arbitrum's
+/// `NodeInterface.gasEstimateComponents` (falling back to a plain estimate
+/// elsewhere). Fails while the account has pending transactions, since
+/// those would spend from the balance being swept.
+///
+/// # Arguments
+/// * `signer` - Signer for the account to empty
+/// * `to_address` - Receiver address
+/// * `headroom_percent` - Margin on the base fee and L1 gas
+///
+/// # Returns
+/// * `Result<(PreparedTransfer, SweepFees), Box<dyn std::error::Error>>` - Transfer and its fee breakdown
+pub async fn prepare_sweep(
+    signer: &AccountSigner,
+    to_address: H160,
+    headroom_percent: u64,
+) -> Result<(PreparedTransfer, SweepFees), Box<dyn std::error::Error>> {
     let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
         .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
-
-    // Create provider
     let provider = Provider::<Http>::try_from(rpc_url)?;
 
-    // Get wallet and connect with chain ID
-    let wallet = get_wallet()?;
     let chain_id = provider.get_chainid().await?;
-
-    // Arbitrum Sepolia chain ID is 421614
     if chain_id.as_u64() != 421614 {
         return Err(format!("Not connected to Arbitrum Sepolia. Chain ID: {}", chain_id).into());
     }
+    let signer = signer.clone().with_chain_id(chain_id.as_u64());
+    let from_address = signer.address();
 
-    let wallet = wallet.with_chain_id(chain_id.as_u64());
-
-    // Verify the wallet address matches the sender
-    let wallet_address = wallet.address();
-    if wallet_address != from_address {
+    let mined = provider.get_transaction_count(from_address, Some(BlockNumber::Latest.into())).await?;
+    let nonce = provider.get_transaction_count(from_address, Some(BlockNumber::Pending.into())).await?;
+    if nonce != mined {
         return Err(format!(
-            "Wallet address {:?} does not match sender address {:?}",
-            wallet_address, from_address
+            "{} has {} pending transaction(s); wait for them before sweeping",
+            checksummed(from_address),
+            nonce - mined
         )
         .into());
     }
 
+    let block = provider.get_block(BlockNumber::Latest).await?
+        .ok_or("Failed to get block")?;
+    let base_fee = block.base_fee_per_gas.unwrap_or_else(|| U256::from(10_000_000u64));
+
+    // Gas does not depend on the value for a plain transfer, so 1 wei stands in
+    let node_interface = BaseContract::from(ethers::abi::parse_abi(&[
+        "function gasEstimateComponents(address to, bool contractCreation, bytes data) external payable \
+         returns (uint64 gasEstimate, uint64 gasEstimateForL1, uint256 baseFee, uint256 l1BaseFeeEstimate)",
+    ])?);
+    let call = TransactionRequest::new()
+        .from(from_address)
+        .to(NODE_INTERFACE.parse::<H160>()?)
+        .value(1)
+        .data(node_interface.encode("gasEstimateComponents", (to_address, false, Bytes::default()))?);
+    let components = match provider.call(&call.into(), None).await {
+        Ok(output) => {
+            let (total, l1, _, _): (u64, u64, U256, U256) =
+                node_interface.decode_output("gasEstimateComponents", output)?;
+            split_gas_estimate(total, l1)
+        }
+        Err(_) => None,
+    };
+    let (l2_gas, l1_gas) = match components {
+        Some(components) => components,
+        None => {
+            let probe = TransactionRequest::new().from(from_address).to(to_address).value(1);
+            (provider.estimate_gas(&probe.into(), None).await?, U256::zero())
+        }
+    };
+
+    let fees = SweepFees::new(base_fee, l2_gas, l1_gas, headroom_percent)?;
+    let balance = provider.get_balance(from_address, None).await?;
+    let value = sweep_amount(balance, &fees)?;
+
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(to_address)
+        .value(value)
+        .from(from_address)
+        .gas(fees.gas_limit)
+        .gas_price(fees.max_fee_per_gas)
+        .nonce(nonce)
+        .chain_id(chain_id.as_u64())
+        .into();
+
+    let prepared = PreparedTransfer {
+        signer,
+        tx,
+        base_fee,
+        gas_price: fees.max_fee_per_gas,
+        fee_basis: format!("base_fee + {}% headroom", headroom_percent),
+        gas_limit: fees.gas_limit,
+        value,
+        abi: None,
+    };
+    Ok((prepared, fees))
+}
+
+/// Build a transaction from the signer's account on Arbitrum Sepolia
+///
+/// # Arguments
+/// * `signer` - Signer for the sending account; the sender is its address
+/// * `to_address` - Receiver or contract address
+/// * `value` - ETH value in wei
+/// * `data` - Calldata for contract calls
+/// * `gas_limit` - Fixed gas limit, or `None` to estimate it (+20%)
+/// * `abi` - ABI of the called contract, for decoding reverts
+///
+/// # Returns
+/// * `Result<PreparedTransfer, Box<dyn std::error::Error>>` - Transaction and its fees
+pub async fn prepare_call(
+    signer: &AccountSigner,
+    to_address: H160,
+    value: U256,
+    data: Option<Bytes>,
+    gas_limit: Option<U256>,
+    abi: Option<Abi>,
+) -> Result<PreparedTransfer, Box<dyn std::error::Error>> {
+    prepare_request(signer, Some(to_address), value, data, gas_limit, abi).await
+}
+
+/// Build a contract deployment from the signer's account on Arbitrum Sepolia
+///
+/// # Arguments
+/// * `signer` - Signer for the deploying account
+/// * `init_code` - Contract creation bytecode, including encoded constructor arguments
+///
+/// # Returns
+/// * `Result<PreparedTransfer, Box<dyn std::error::Error>>` - Deployment with estimated gas
+pub async fn prepare_deploy(
+    signer: &AccountSigner,
+    init_code: Bytes,
+) -> Result<PreparedTransfer, Box<dyn std::error::Error>> {
+    prepare_request(signer, None, U256::zero(), Some(init_code), None, None).await
+}
+
+/// Shared body of `prepare_call` and `prepare_deploy`; `to_address` is `None` for deployments
+async fn prepare_request(
+    signer: &AccountSigner,
+    to_address: Option<H160>,
+    value: U256,
+    data: Option<Bytes>,
+    gas_limit: Option<U256>,
+    abi: Option<Abi>,
+) -> Result<PreparedTransfer, Box<dyn std::error::Error>> {
+    let unsigned = build_request(signer.address(), to_address, value, data, gas_limit, abi.as_ref()).await?;
+
+    // Connect the signer with the chain ID
+    let signer = signer.clone().with_chain_id(unsigned.chain_id);
+
+    Ok(PreparedTransfer {
+        signer,
+        tx: unsigned.tx,
+        base_fee: unsigned.base_fee,
+        gas_price: unsigned.gas_price,
+        fee_basis: "base_fee + 20% + 0.01 Gwei".to_string(),
+        gas_limit: unsigned.gas_limit,
+        value,
+        abi,
+    })
+}
+
+/// An unsigned transaction with the fee data it was built from
+#[derive(Debug, Clone)]
+pub struct UnsignedRequest {
+    /// Transaction with nonce, gas and chain ID filled in
+    pub tx: TypedTransaction,
+    pub chain_id: u64,
+    pub base_fee: U256,
+    pub gas_price: U256,
+    pub gas_limit: U256,
+}
+
+/// Build a transaction for an account on Arbitrum Sepolia without its key
+///
+/// Fetches the chain ID, fees and pending nonce for `from_address`; no
+/// signer is involved, so the result can be signed elsewhere.
+///
+/// # Arguments
+/// * `from_address` - Sending account
+/// * `to_address` - Receiver or contract address, `None` for deployments
+/// * `value` - ETH value in wei
+/// * `data` - Calldata for contract calls
+/// * `gas_limit` - Fixed gas limit, or `None` to estimate it (+20%)
+/// * `abi` - ABI of the called contract, for decoding reverts
+///
+/// # Returns
+/// * `Result<UnsignedRequest, Box<dyn std::error::Error>>` - Transaction and its fees
+pub async fn build_request(
+    from_address: H160,
+    to_address: Option<H160>,
+    value: U256,
+    data: Option<Bytes>,
+    gas_limit: Option<U256>,
+    abi: Option<&Abi>,
+) -> Result<UnsignedRequest, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+
+    // Create provider
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+
+    let chain_id = provider.get_chainid().await?;
+
+    // Arbitrum Sepolia chain ID is 421614
+    if chain_id.as_u64() != 421614 {
+        return Err(format!("Not connected to Arbitrum Sepolia. Chain ID: {}", chain_id).into());
+    }
+
     // Get current block to find base fee for EIP-1559
     let block = provider.get_block(BlockNumber::Latest).await?
         .ok_or("Failed to get block")?;
@@ -133,55 +471,350 @@ pub async fn transfer_eth(
     let max_priority_fee = U256::from(10_000_000u64); // 0.01 Gwei tip
     let gas_price = base_fee + (base_fee / 5) + max_priority_fee; // base_fee + 20% + tip
 
-    // Standard gas limit for ETH transfer
-    let gas_limit = U256::from(100_000); // Use 100k to be safe on L2
-
-    // Parse amount
-    let amount_wei = parse_ether(amount_ether)?;
+    // Pending nonce so the transaction can be signed without sending it
+    let nonce = provider
+        .get_transaction_count(from_address, Some(BlockNumber::Pending.into()))
+        .await?;
 
     // Build transaction with gas_price (will be converted to EIP-1559 by the network)
-    let tx = TransactionRequest::new()
-        .to(to_address)
-        .value(amount_wei)
+    let mut request = TransactionRequest::new()
+        .value(value)
         .from(from_address)
-        .gas(gas_limit)
-        .gas_price(gas_price);
+        .gas_price(gas_price)
+        .nonce(nonce)
+        .chain_id(chain_id.as_u64());
+    if let Some(to_address) = to_address {
+        request = request.to(to_address);
+    }
+    if let Some(data) = data {
+        request = request.data(data);
+    }
+    let mut tx: TypedTransaction = request.into();
+
+    let gas_limit = match gas_limit {
+        Some(limit) => limit,
+        None => {
+            // Simulate first so a revert is reported with its reason, not as a gas error
+            simulate_call(&provider, &tx, abi).await?;
+            let estimate = provider.estimate_gas(&tx, None).await?;
+            estimate + estimate / 5
+        }
+    };
+    tx.set_gas(gas_limit);
+
+    Ok(UnsignedRequest {
+        tx,
+        chain_id: chain_id.as_u64(),
+        base_fee,
+        gas_price,
+        gas_limit,
+    })
+}
+
+/// Sign a prepared transfer without sending it
+///
+/// # Arguments
+/// * `prepared` - Transfer from `prepare_transfer`
+///
+/// # Returns
+/// * `Result<Bytes, Box<dyn std::error::Error>>` - Raw signed transaction
+pub async fn sign_transfer(prepared: &PreparedTransfer) -> Result<Bytes, Box<dyn std::error::Error>> {
+    let signature = prepared.signer.sign_transaction(&prepared.tx).await?;
+    Ok(prepared.tx.rlp_signed(&signature))
+}
+
+/// Simulate a prepared transfer against pending state
+///
+/// Runs `eth_call` and `eth_estimateGas` from the sender, so a transfer that
+/// would revert or that the sender cannot afford fails here without being sent.
+/// Reverts are reported with their decoded reason.
+///
+/// # Arguments
+/// * `prepared` - Transfer from `prepare_transfer`
+///
+/// # Returns
+/// * `Result<U256, Box<dyn std::error::Error>>` - Gas the transaction would use
+pub async fn simulate_transfer(prepared: &PreparedTransfer) -> Result<U256, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
 
+    simulate_call(&provider, &prepared.tx, prepared.abi.as_ref()).await?;
+    let gas_used = provider
+        .estimate_gas(&prepared.tx, None)
+        .await
+        .map_err(|e| format!("Gas estimation failed: {}", e))?;
+    Ok(gas_used)
+}
+
+/// Signing client used to broadcast transfers
+pub type TransferClient = SignerMiddleware<Provider<Http>, AccountSigner>;
+
+/// Nonce manager for a signer on Arbitrum Sepolia
+///
+/// One manager per account lets several transactions be sent back to back
+/// without waiting for each to mine.
+///
+/// # Arguments
+/// * `signer` - Signer bound to the chain ID
+///
+/// # Returns
+/// * `Result<NonceManager<TransferClient>, Box<dyn std::error::Error>>` - Manager for the signer's account
+pub fn nonce_manager(signer: &AccountSigner) -> Result<NonceManager<TransferClient>, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let client = SignerMiddleware::new(provider, signer.clone());
+    Ok(NonceManager::new(client, signer.address()))
+}
+
+/// Simulate a prepared transfer and send it with a managed nonce
+///
+/// The nonce from `prepare_call` is replaced by the manager's, so pipelined
+/// sends from the same account do not collide.
+///
+/// # Arguments
+/// * `manager` - Nonce manager for the transfer's signer
+/// * `prepared` - Transfer from `prepare_transfer` or `prepare_call`
+///
+/// # Returns
+/// * `Result<(U256, H256), Box<dyn std::error::Error>>` - Nonce used and transaction hash
+pub async fn send_prepared(
+    manager: &NonceManager<TransferClient>,
+    prepared: &PreparedTransfer,
+) -> Result<(U256, H256), Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+
+    // Abort before signing if the transaction would revert
+    simulate_call(&provider, &prepared.tx, prepared.abi.as_ref()).await?;
+    manager.send(prepared.tx.clone()).await
+}
+
+/// Sign and broadcast a prepared transfer
+///
+/// The transaction is simulated against pending state first; if it would
+/// revert, nothing is sent and the decoded revert reason is returned.
+///
+/// # Arguments
+/// * `prepared` - Transfer from `prepare_transfer`
+///
+/// # Returns
+/// * `Result<String, Box<dyn std::error::Error>>` - Transaction hash
+pub async fn send_transfer(prepared: &PreparedTransfer) -> Result<String, Box<dyn std::error::Error>> {
     // Send transaction
     println!("Sending transaction...");
-    println!("Base fee: {} Gwei", format_units(base_fee, "gwei")?);
-    println!("Gas price: {} Gwei (base_fee + 20% + 0.01 Gwei)", format_units(gas_price, "gwei")?);
-    println!("Gas limit: {}", gas_limit);
-    let client = SignerMiddleware::new(provider.clone(), wallet);
-    let pending_tx = client.send_transaction(tx, None).await?;
-
-    // Get the transaction hash - in ethers v2, PendingTransaction implements Deref to TxHash
-    let tx_hash = *pending_tx;
+    println!("Base fee: {} Gwei", Amount::wei_as_gwei(prepared.base_fee));
+    println!("Gas price: {} Gwei ({})", Amount::wei_as_gwei(prepared.gas_price), prepared.fee_basis);
+    println!("Gas limit: {}", prepared.gas_limit);
+    let manager = nonce_manager(&prepared.signer)?;
+    let (_, tx_hash) = send_prepared(&manager, prepared).await?;
 
     Ok(format!("{:#x}", tx_hash))
 }
 
-/// Get transaction receipt by hash
+/// Smallest fee increase nodes accept for a same-nonce replacement, in percent
+pub const MIN_BUMP_PERCENT: u64 = 10;
+
+/// Priority fee floor for replacements (0.01 Gwei)
+const MIN_PRIORITY_FEE: u64 = 10_000_000;
+
+/// How to replace a pending transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Replacement {
+    /// Same call with higher fees
+    SpeedUp,
+    /// 0-value transfer to the sender itself, using up the nonce
+    Cancel,
+}
+
+/// Raise a fee by a percentage, rounding up
+///
+/// `percent` comes from the command line and `fee` from the node, so a
+/// result that overflows is an error.
+pub fn bump_fee(fee: U256, percent: u64) -> Result<U256, Box<dyn std::error::Error>> {
+    let bumped = percent
+        .checked_add(100)
+        .and_then(|factor| fee.checked_mul(U256::from(factor)))
+        .and_then(|fee| fee.checked_add(U256::from(99)))
+        .ok_or_else(|| format!("Fee {} wei bumped by {}% overflows 256 bits", fee, percent))?;
+    Ok(bumped / 100)
+}
+
+/// Build a same-nonce replacement for a pending transaction
+///
+/// Fees are bumped by `bump_percent` and never set below `network_price`.
+/// EIP-1559 originals get both the max fee and the priority fee bumped, as
+/// replacement rules require. EIP-2930 originals keep their type and access
+/// list.
+///
+/// # Arguments
+/// * `original` - Pending transaction to replace
+/// * `kind` - Speed up or cancel
+/// * `bump_percent` - Fee increase, at least `MIN_BUMP_PERCENT`
+/// * `network_price` - Current gas price (base fee + 20% + tip)
+///
+/// # Returns
+/// * `Result<TypedTransaction, Box<dyn std::error::Error>>` - Replacement without a gas limit
+pub fn build_replacement(
+    original: &Transaction,
+    kind: Replacement,
+    bump_percent: u64,
+    network_price: U256,
+) -> Result<TypedTransaction, Box<dyn std::error::Error>> {
+    if bump_percent < MIN_BUMP_PERCENT {
+        return Err(format!("Fee bump must be at least {}% to replace a transaction", MIN_BUMP_PERCENT).into());
+    }
+    let (to, value, data) = match kind {
+        Replacement::SpeedUp => (
+            original.to.ok_or("Contract deployments cannot be sped up")?,
+            original.value,
+            original.input.clone(),
+        ),
+        Replacement::Cancel => (original.from, U256::zero(), Bytes::default()),
+    };
+    let chain_id = original.chain_id.unwrap_or_else(|| U256::from(421614u64)).as_u64();
+
+    let tx = if original.transaction_type == Some(U64::from(2)) {
+        let tip = bump_fee(original.max_priority_fee_per_gas.unwrap_or_default(), bump_percent)?
+            .max(U256::from(MIN_PRIORITY_FEE));
+        let max_fee = bump_fee(original.max_fee_per_gas.unwrap_or_default(), bump_percent)?
+            .max(network_price)
+            .max(tip);
+        Eip1559TransactionRequest::new()
+            .from(original.from)
+            .to(to)
+            .value(value)
+            .data(data)
+            .nonce(original.nonce)
+            .max_fee_per_gas(max_fee)
+            .max_priority_fee_per_gas(tip)
+            .chain_id(chain_id)
+            .into()
+    } else {
+        let gas_price = bump_fee(original.gas_price.unwrap_or_default(), bump_percent)?.max(network_price);
+        let request = TransactionRequest::new()
+            .from(original.from)
+            .to(to)
+            .value(value)
+            .data(data)
+            .nonce(original.nonce)
+            .gas_price(gas_price)
+            .chain_id(chain_id);
+        if original.transaction_type == Some(U64::from(1)) {
+            Eip2930TransactionRequest::new(request, original.access_list.clone().unwrap_or_default()).into()
+        } else {
+            request.into()
+        }
+    };
+    Ok(tx)
+}
+
+/// Prepare a speed-up or cancel for a pending transaction
 ///
 /// # Arguments
-/// * `tx_hash` - Transaction hash
+/// * `signer` - Signer for the account that sent the original
+/// * `tx_hash` - Pending transaction to replace
+/// * `kind` - Speed up or cancel
+/// * `bump_percent` - Fee increase, at least `MIN_BUMP_PERCENT`
 ///
 /// # Returns
-/// * `Result<Option<TransactionReceipt>, Box<dyn std::error::Error>>` - Transaction receipt
-pub async fn get_transaction_receipt(
-    tx_hash: &str,
-) -> Result<Option<TransactionReceipt>, Box<dyn std::error::Error>> {
+/// * `Result<PreparedTransfer, Box<dyn std::error::Error>>` - Replacement with the original's nonce
+pub async fn prepare_replacement(
+    signer: &AccountSigner,
+    tx_hash: H256,
+    kind: Replacement,
+    bump_percent: u64,
+) -> Result<PreparedTransfer, Box<dyn std::error::Error>> {
     let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
         .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
-
     let provider = Provider::<Http>::try_from(rpc_url)?;
 
-    // Parse transaction hash
-    let tx_hash = H256::from_str(tx_hash)?;
+    let chain_id = provider.get_chainid().await?;
+    if chain_id.as_u64() != 421614 {
+        return Err(format!("Not connected to Arbitrum Sepolia. Chain ID: {}", chain_id).into());
+    }
+    let signer = signer.clone().with_chain_id(chain_id.as_u64());
+
+    let original = provider
+        .get_transaction(tx_hash)
+        .await?
+        .ok_or_else(|| format!("Transaction {:#x} not found; it may have been dropped", tx_hash))?;
+    if let Some(block) = original.block_number {
+        return Err(format!("Transaction {:#x} is already mined in block {}", tx_hash, block).into());
+    }
+    if original.from != signer.address() {
+        return Err(format!(
+            "Transaction {:#x} was sent by {}, not by the selected signer {}",
+            tx_hash,
+            checksummed(original.from),
+            checksummed(signer.address())
+        )
+        .into());
+    }
+
+    let block = provider.get_block(BlockNumber::Latest).await?
+        .ok_or("Failed to get block")?;
+    let base_fee = block.base_fee_per_gas.unwrap_or_else(|| U256::from(10_000_000u64));
+    let network_price = base_fee + (base_fee / 5) + U256::from(MIN_PRIORITY_FEE);
+
+    let mut tx = build_replacement(&original, kind, bump_percent, network_price)?;
+    let gas_limit = match kind {
+        Replacement::SpeedUp => original.gas,
+        Replacement::Cancel => {
+            let estimate = provider.estimate_gas(&tx, None).await?;
+            estimate + estimate / 5
+        }
+    };
+    tx.set_gas(gas_limit);
+    let gas_price = match &tx {
+        TypedTransaction::Eip1559(inner) => inner.max_fee_per_gas.unwrap_or_default(),
+        other => other.gas_price().unwrap_or_default(),
+    };
+
+    Ok(PreparedTransfer {
+        signer,
+        value: tx.value().copied().unwrap_or_default(),
+        tx,
+        base_fee,
+        gas_price,
+        fee_basis: format!("original + {}%, at least base_fee + 20% + 0.01 Gwei", bump_percent),
+        gas_limit,
+        abi: None,
+    })
+}
 
-    let receipt = provider.get_transaction_receipt(tx_hash).await?;
+/// Broadcast a replacement as-is, keeping its nonce
+///
+/// # Arguments
+/// * `prepared` - Replacement from `prepare_replacement`
+///
+/// # Returns
+/// * `Result<H256, Box<dyn std::error::Error>>` - Replacement transaction hash
+pub async fn send_replacement(prepared: &PreparedTransfer) -> Result<H256, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARBITRUM_SEPOLIA_RPC")
+        .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string());
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let client = SignerMiddleware::new(provider, prepared.signer.clone());
 
-    Ok(receipt)
+    let result = client.send_transaction(prepared.tx.clone(), None).await.map(|pending| pending.tx_hash());
+    match result {
+        Ok(hash) => {
+            crate::policy::record_sent(prepared.signer.address(), &prepared.tx);
+            Ok(hash)
+        }
+        Err(e) => {
+            let message = e.to_string();
+            if message.contains("underpriced") {
+                Err(format!("Replacement rejected as underpriced; retry with a higher --bump ({})", message).into())
+            } else if message.contains("nonce too low") {
+                Err("The original transaction was mined before the replacement was accepted".into())
+            } else {
+                Err(message.into())
+            }
+        }
+    }
 }
 
 /// Estimate gas fee for a transfer
@@ -193,21 +826,22 @@ pub async fn estimate_transfer_fee() -> Result<String, Box<dyn std::error::Error
     let gas_limit = U256::from(21_000);
     let gas_fee = gas_price * gas_limit;
 
-    let gwei_value = format_units(gas_fee, "gwei")?;
-    let eth_value = format_units(gas_fee, "ether")?;
-
     Ok(format!(
         "Gas Price: {} Gwei\nGas Limit: {} units\nEstimated Fee: {} Gwei ({} ETH)",
-        format_units(gas_price, "gwei")?,
+        Amount::wei_as_gwei(gas_price),
         gas_limit,
-        gwei_value,
-        eth_value
+        Amount::wei_as_gwei(gas_fee),
+        Amount::from_wei(gas_fee)
     ))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{test_wallet, use_temp_audit_log};
+    use arb_common::address::AddressWarning;
+    use ethers::types::transaction::eip2930::{AccessList, AccessListItem};
+    use ethers::utils::parse_ether;
 
     #[test]
     fn test_validate_valid_addresses() {
@@ -220,6 +854,11 @@ mod tests {
         for addr in valid_addresses {
             assert!(validate_address(addr).is_ok(), "Should validate: {}", addr);
         }
+
+        // Unchecksummed input is accepted, but the caller is told
+        let parsed = validate_address("0xd78677efed3b87f8f421e68da3f984ad8ef76439").unwrap();
+        assert_eq!(parsed.warnings, vec![AddressWarning::NoChecksum]);
+        assert!(validate_address("0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439").unwrap().warnings.is_empty());
     }
 
     #[test]
@@ -228,6 +867,9 @@ mod tests {
             "0x123", // too short
             "0xGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGG", // invalid hex
             "not_an_address", // invalid format
+            "0xD78677EFed3b87f8f421E68dA3F984ad8Ef76439", // bad checksum
+            "0x0000000000000000000000000000000000000000", // zero address
+            "0x0000000000000000000000000000000000000064", // ArbSys precompile
         ];
 
         for addr in invalid_addresses {
@@ -245,4 +887,125 @@ mod tests {
         assert!(amount2 > U256::zero());
         assert!(amount1 > amount2);
     }
+
+    #[tokio::test]
+    async fn test_prepared_transfer_costs_and_signing() {
+        use_temp_audit_log();
+        let wallet = test_wallet();
+        let signer = AccountSigner::Local(wallet.clone()).with_chain_id(421614u64);
+        let value = parse_ether("0.0001").unwrap();
+        let prepared = PreparedTransfer {
+            signer,
+            tx: TransactionRequest::new()
+                .to(H160::repeat_byte(0x11))
+                .value(value)
+                .from(wallet.address())
+                .gas(100_000)
+                .gas_price(12_000_000)
+                .nonce(7)
+                .chain_id(421614u64)
+                .into(),
+            base_fee: U256::from(10_000_000),
+            gas_price: U256::from(12_000_000),
+            fee_basis: "base_fee + 20% + 0.01 Gwei".to_string(),
+            gas_limit: U256::from(100_000),
+            value,
+            abi: None,
+        };
+        assert_eq!(prepared.max_fee(), U256::from(1_200_000_000_000u64));
+        assert_eq!(prepared.total_cost(), value + U256::from(1_200_000_000_000u64));
+
+        let raw = sign_transfer(&prepared).await.unwrap();
+        let decoded: Transaction = ethers::utils::rlp::decode(&raw).unwrap();
+        assert_eq!(decoded.recover_from().unwrap(), wallet.address());
+        assert_eq!(decoded.nonce, U256::from(7));
+        assert_eq!(decoded.value, value);
+    }
+
+    #[test]
+    fn test_build_replacement() {
+        assert_eq!(bump_fee(U256::from(100), 10).unwrap(), U256::from(110));
+        assert_eq!(bump_fee(U256::from(101), 10).unwrap(), U256::from(112)); // 111.1 rounds up
+        assert!(bump_fee(U256::from(100), u64::MAX).is_err());
+        assert!(bump_fee(U256::MAX, 10).is_err());
+
+        let sender = H160::repeat_byte(0x11);
+        let original = Transaction {
+            from: sender,
+            to: Some(H160::repeat_byte(0x22)),
+            value: U256::from(1000),
+            nonce: U256::from(9),
+            gas: U256::from(100_000),
+            transaction_type: Some(U64::from(2)),
+            max_fee_per_gas: Some(U256::from(200_000_000u64)),
+            max_priority_fee_per_gas: Some(U256::from(20_000_000u64)),
+            chain_id: Some(U256::from(421614u64)),
+            ..Default::default()
+        };
+
+        let speedup = build_replacement(&original, Replacement::SpeedUp, 10, U256::from(100_000_000u64)).unwrap();
+        let TypedTransaction::Eip1559(speedup) = speedup else { panic!("expected an EIP-1559 replacement") };
+        assert_eq!(speedup.nonce, Some(U256::from(9)));
+        assert_eq!(speedup.value, Some(U256::from(1000)));
+        assert_eq!(speedup.max_fee_per_gas, Some(U256::from(220_000_000u64)));
+        assert_eq!(speedup.max_priority_fee_per_gas, Some(U256::from(22_000_000u64)));
+
+        // Fees never drop below the current network price
+        let cancel = build_replacement(&original, Replacement::Cancel, 10, U256::from(500_000_000u64)).unwrap();
+        assert_eq!(cancel.to_addr(), Some(&sender));
+        assert_eq!(cancel.value(), Some(&U256::zero()));
+        assert_eq!(cancel.nonce(), Some(&U256::from(9)));
+        let TypedTransaction::Eip1559(cancel) = cancel else { panic!("expected an EIP-1559 replacement") };
+        assert_eq!(cancel.max_fee_per_gas, Some(U256::from(500_000_000u64)));
+
+        assert!(build_replacement(&original, Replacement::SpeedUp, 5, U256::zero()).is_err());
+        assert!(build_replacement(&original, Replacement::SpeedUp, u64::MAX, U256::zero()).is_err());
+
+        // EIP-2930 originals are replaced with the same type and access list
+        let access_list = AccessList(vec![AccessListItem {
+            address: H160::repeat_byte(0x33),
+            storage_keys: vec![H256::repeat_byte(0x44)],
+        }]);
+        let typed_1 = Transaction {
+            transaction_type: Some(U64::from(1)),
+            gas_price: Some(U256::from(200_000_000u64)),
+            access_list: Some(access_list.clone()),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            ..original
+        };
+        let speedup = build_replacement(&typed_1, Replacement::SpeedUp, 10, U256::zero()).unwrap();
+        let TypedTransaction::Eip2930(speedup) = speedup else { panic!("expected an EIP-2930 replacement") };
+        assert_eq!(speedup.access_list, access_list);
+        assert_eq!(speedup.tx.gas_price, Some(U256::from(220_000_000u64)));
+    }
+
+    #[test]
+    fn test_sweep_amount() {
+        // 0.1 Gwei base fee, 21k L2 gas, 5k L1 gas, 10% headroom
+        let fees = SweepFees::new(U256::from(100_000_000u64), U256::from(21_000), U256::from(5_000), 10).unwrap();
+        assert_eq!(fees.gas_limit, U256::from(26_500));
+        assert_eq!(fees.max_fee_per_gas, U256::from(110_000_000u64));
+        assert_eq!(fees.max_fee_per_gas, bump_fee(U256::from(100_000_000u64), 10).unwrap());
+
+        let balance = parse_ether("0.01").unwrap();
+        let amount = sweep_amount(balance, &fees).unwrap();
+        assert_eq!(amount + fees.max_fee().unwrap(), balance);
+
+        // Without headroom the fee is exactly gas × base fee
+        let exact = SweepFees::new(U256::from(100_000_000u64), U256::from(21_000), U256::from(5_000), 0).unwrap();
+        assert_eq!(exact.max_fee().unwrap(), U256::from(26_000u64 * 100_000_000));
+
+        assert!(sweep_amount(fees.max_fee().unwrap(), &fees).is_err());
+        assert!(sweep_amount(U256::from(1), &fees).is_err());
+
+        // Estimates from a misbehaving node are refused rather than overflowing
+        assert!(SweepFees::new(U256::MAX, U256::from(21_000), U256::zero(), 10).is_err());
+        assert!(SweepFees::new(U256::one(), U256::MAX, U256::from(5_000), 10).is_err());
+        assert!(SweepFees::new(U256::from(1u64 << 40), U256::MAX / 2, U256::zero(), 0).unwrap_err().to_string().contains("overflow"));
+
+        assert_eq!(split_gas_estimate(26_000, 5_000), Some((U256::from(21_000), U256::from(5_000))));
+        // More L1 gas than the total is not trusted, so the plain estimate is used
+        assert_eq!(split_gas_estimate(5_000, 26_000), None);
+    }
 }
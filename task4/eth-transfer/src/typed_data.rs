@@ -0,0 +1,273 @@
+//! EIP-712 typed structured data
+//!
+//! Reads the standard `eth_signTypedData_v4` JSON (`types`, `primaryType`,
+//! `domain`, `message`), computes the domain separator, struct hash and
+//! digest, and renders the message for review before signing. Permits,
+//! orders and other off-chain approvals can move funds later, so the
+//! preview flags the fields that matter.
+
+use arb_common::address::checksummed;
+use ethers::types::transaction::eip712::{Eip712, TypedData};
+use ethers::types::{Signature, H160, H256, U256};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Hashes that make up an EIP-712 signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypedDataHashes {
+    pub domain_separator: H256,
+    /// `hashStruct(message)`
+    pub struct_hash: H256,
+    /// `keccak256(0x19 0x01 ‖ domainSeparator ‖ hashStruct(message))`, what is signed
+    pub digest: H256,
+}
+
+/// Read typed data JSON from a file
+///
+/// # Arguments
+/// * `path` - File with `types`, `primaryType`, `domain` and `message`
+///
+/// # Returns
+/// * `Result<TypedData, Box<dyn std::error::Error>>` - Parsed typed data, checked to hash
+pub fn load_typed_data(path: &Path) -> Result<TypedData, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path).map_err(|_| format!("Failed to read {}", path.display()))?;
+    let typed: TypedData =
+        serde_json::from_str(&text).map_err(|e| format!("{} is not EIP-712 typed data: {}", path.display(), e))?;
+    if typed.primary_type != "EIP712Domain" && !typed.types.contains_key(&typed.primary_type) {
+        return Err(format!("Primary type '{}' is not defined in types", typed.primary_type).into());
+    }
+    typed_data_hashes(&typed)?;
+    Ok(typed)
+}
+
+/// Domain separator, struct hash and digest
+pub fn typed_data_hashes(typed: &TypedData) -> Result<TypedDataHashes, Box<dyn std::error::Error>> {
+    let invalid = |e: ethers::types::transaction::eip712::Eip712Error| format!("Invalid typed data: {}", e);
+    Ok(TypedDataHashes {
+        domain_separator: H256::from(typed.domain_separator().map_err(invalid)?),
+        struct_hash: H256::from(typed.struct_hash().map_err(invalid)?),
+        digest: H256::from(typed.encode_eip712().map_err(invalid)?),
+    })
+}
+
+/// Recover the account that signed typed data
+pub fn recover_typed_data(typed: &TypedData, signature: &Signature) -> Result<H160, Box<dyn std::error::Error>> {
+    Ok(signature.recover(typed_data_hashes(typed)?.digest)?)
+}
+
+/// Human-readable lines describing the domain and message
+pub fn preview(typed: &TypedData) -> Vec<String> {
+    let domain = &typed.domain;
+    let mut lines = vec!["Domain:".to_string()];
+    if let Some(name) = &domain.name {
+        lines.push(format!("   name: {}", name));
+    }
+    if let Some(version) = &domain.version {
+        lines.push(format!("   version: {}", version));
+    }
+    if let Some(chain_id) = domain.chain_id {
+        lines.push(format!("   chainId: {}", chain_id));
+    }
+    if let Some(contract) = domain.verifying_contract {
+        lines.push(format!("   verifyingContract: {}", checksummed(contract)));
+    }
+    if let Some(salt) = domain.salt {
+        lines.push(format!("   salt: {:#x}", H256::from(salt)));
+    }
+    lines.push(format!("Message ({}):", typed.primary_type));
+    if let Some(fields) = typed.types.get(&typed.primary_type) {
+        for field in fields {
+            let value = typed.message.get(&field.name).unwrap_or(&Value::Null);
+            render(typed, &field.name, &field.r#type, value, 1, &mut lines);
+        }
+    }
+    lines
+}
+
+/// Render one field, recursing into structs and arrays
+fn render(typed: &TypedData, name: &str, kind: &str, value: &Value, depth: usize, lines: &mut Vec<String>) {
+    let indent = "   ".repeat(depth);
+    if let Some(element) = kind.strip_suffix(']').and_then(|k| k.rsplit_once('[')).map(|(element, _)| element) {
+        let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+        lines.push(format!("{}{} ({}, {} item(s)):", indent, name, kind, items.len()));
+        for (i, item) in items.iter().enumerate() {
+            render(typed, &format!("[{}]", i), element, item, depth + 1, lines);
+        }
+        return;
+    }
+    if let Some(fields) = typed.types.get(kind) {
+        lines.push(format!("{}{} ({}):", indent, name, kind));
+        for field in fields {
+            let inner = value.get(&field.name).unwrap_or(&Value::Null);
+            render(typed, &field.name, &field.r#type, inner, depth + 1, lines);
+        }
+        return;
+    }
+    let shown = match value {
+        Value::String(s) if kind == "address" => s.parse::<H160>().map(checksummed).unwrap_or_else(|_| s.clone()),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    lines.push(format!("{}{}: {}", indent, name, shown));
+}
+
+/// Numeric field of the message, accepting JSON numbers and decimal or hex strings
+fn message_uint(typed: &TypedData, field: &str) -> Option<U256> {
+    match typed.message.get(field)? {
+        Value::Number(n) => n.as_u64().map(U256::from),
+        Value::String(s) if s.starts_with("0x") => U256::from_str_radix(&s[2..], 16).ok(),
+        Value::String(s) => U256::from_dec_str(s).ok(),
+        _ => None,
+    }
+}
+
+/// Things worth a second look before signing
+///
+/// # Arguments
+/// * `typed` - Data about to be signed
+/// * `chain_id` - Chain the signer expects to be on
+///
+/// # Returns
+/// * `Vec<String>` - Warnings, empty if nothing stands out
+pub fn warnings(typed: &TypedData, chain_id: u64) -> Vec<String> {
+    let mut warnings = Vec::new();
+    match typed.domain.chain_id {
+        Some(domain_chain) if domain_chain != U256::from(chain_id) => warnings.push(format!(
+            "Domain is for chain {}, not {}",
+            domain_chain, chain_id
+        )),
+        None => warnings.push("Domain has no chainId: the signature is not tied to one chain".to_string()),
+        _ => {}
+    }
+    if typed.domain.verifying_contract.is_none() {
+        warnings.push("Domain has no verifyingContract: any contract accepting this domain can use it".to_string());
+    }
+    if typed.primary_type.starts_with("Permit") {
+        let spender = typed
+            .message
+            .get("spender")
+            .and_then(Value::as_str)
+            .and_then(|s| s.parse::<H160>().ok())
+            .map(checksummed)
+            .unwrap_or_else(|| "the spender".to_string());
+        warnings.push(format!("This is a permit: it lets {} move tokens without another approval", spender));
+        if message_uint(typed, "value") == Some(U256::MAX) {
+            warnings.push("The permit is for an unlimited amount".to_string());
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::utils::keccak256;
+
+    /// The example from the EIP-712 specification
+    const MAIL: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    fn mail() -> TypedData {
+        serde_json::from_str(MAIL).unwrap()
+    }
+
+    #[test]
+    fn test_hashes_match_spec() {
+        let hashes = typed_data_hashes(&mail()).unwrap();
+        assert_eq!(
+            hashes.domain_separator,
+            "0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f".parse().unwrap()
+        );
+        assert_eq!(
+            hashes.struct_hash,
+            "0xc52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e".parse().unwrap()
+        );
+        assert_eq!(
+            hashes.digest,
+            "0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2".parse().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_and_recover() {
+        // The spec's signer: keccak256("cow")
+        let wallet = LocalWallet::from_bytes(&keccak256("cow")).unwrap();
+        assert_eq!(checksummed(wallet.address()), "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826");
+
+        let typed = mail();
+        let signature = wallet.sign_typed_data(&typed).await.unwrap();
+        assert_eq!(signature.r, U256::from_str_radix("4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d", 16).unwrap());
+        assert_eq!(recover_typed_data(&typed, &signature).unwrap(), wallet.address());
+
+        let mut tampered = typed.clone();
+        tampered.message.insert("contents".to_string(), Value::String("Hello, Eve!".to_string()));
+        assert_ne!(recover_typed_data(&tampered, &signature).unwrap(), wallet.address());
+    }
+
+    #[test]
+    fn test_preview_and_warnings() {
+        let typed = mail();
+        let lines = preview(&typed);
+        assert!(lines.contains(&"   verifyingContract: 0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC".to_string()));
+        assert!(lines.contains(&"   from (Person):".to_string()));
+        assert!(lines.contains(&"      wallet: 0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826".to_string()));
+        assert!(lines.contains(&"   contents: Hello, Bob!".to_string()));
+
+        assert_eq!(warnings(&typed, 1), Vec::<String>::new());
+        assert_eq!(warnings(&typed, 421614), vec!["Domain is for chain 1, not 421614".to_string()]);
+
+        let permit: TypedData = serde_json::from_value(serde_json::json!({
+            "types": {
+                "EIP712Domain": [{ "name": "chainId", "type": "uint256" }],
+                "Permit": [
+                    { "name": "spender", "type": "address" },
+                    { "name": "value", "type": "uint256" },
+                    { "name": "tags", "type": "string[]" }
+                ]
+            },
+            "primaryType": "Permit",
+            "domain": { "chainId": 421614 },
+            "message": {
+                "spender": "0x7292dd72151dacfbbe76305db1c8ab1928e922e4",
+                "value": U256::MAX.to_string(),
+                "tags": ["a", "b"]
+            }
+        }))
+        .unwrap();
+        let warnings = warnings(&permit, 421614);
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[1].contains("0x7292dD72151DaCFBbE76305db1C8Ab1928E922E4"));
+        assert!(preview(&permit).contains(&"      [1]: b".to_string()));
+    }
+}
@@ -1,12 +1,13 @@
+//! ERC20 Token Contract interface
+//!
+//! This module provides functions to interact with ERC20 tokens
+//! on the Arbitrum Sepolia testnet using ethers-rs.
+
+use arb_common::address::{checksummed, parse_address};
+use arb_common::amount::Amount;
 use ethers::prelude::*;
 use std::env;
 use std::fs;
-use std::str::FromStr;
-
-/// ERC20 Token Contract interface
-///
-/// This module provides functions to interact with ERC20 tokens
-/// on the Arbitrum Sepolia testnet using ethers-rs.
 
 /// Load ERC20 ABI from file
 ///
@@ -28,16 +29,25 @@ pub fn get_rpc_url() -> String {
         .unwrap_or_else(|_| "https://sepolia-rollup.arbitrum.io/rpc".to_string())
 }
 
+/// Get the chain ID of the configured RPC endpoint
+///
+/// # Returns
+/// * `Result<u64, Box<dyn std::error::Error>>` - Chain ID
+pub async fn get_chain_id() -> Result<u64, Box<dyn std::error::Error>> {
+    let provider = Provider::<Http>::try_from(get_rpc_url())?;
+    Ok(provider.get_chainid().await?.as_u64())
+}
+
 /// Get contract address from environment variable or use default
 ///
 /// # Returns
 /// * `Result<H160, Box<dyn std::error::Error>>` - Contract address
 pub fn get_contract_address() -> Result<H160, Box<dyn std::error::Error>> {
     let address_str = env::var("CONTRACT_ADDRESS")
-        .unwrap_or_else(|_| "0x812dd1c3eb07bb1f5f93540350ef9af838ab0528".to_string());
-    let address = H160::from_str(&address_str)
-        .map_err(|_| format!("Invalid contract address: {}", address_str))?;
-    Ok(address)
+        .unwrap_or_else(|_| "0x812dD1c3EB07bb1F5f93540350EF9aF838AB0528".to_string());
+    let address = parse_address(&address_str)
+        .map_err(|e| format!("Invalid contract address: {}", e))?;
+    Ok(address.address)
 }
 
 /// Get contract instance
@@ -45,8 +55,21 @@ pub fn get_contract_address() -> Result<H160, Box<dyn std::error::Error>> {
 /// # Returns
 /// * Contract instance and address
 pub async fn get_contract() -> Result<(Contract<Provider<Http>>, H160), Box<dyn std::error::Error>> {
-    let rpc_url = get_rpc_url();
     let contract_address = get_contract_address()?;
+    let contract = get_contract_at(contract_address)?;
+
+    Ok((contract, contract_address))
+}
+
+/// Get contract instance for an arbitrary ERC20 token address
+///
+/// # Arguments
+/// * `contract_address` - The ERC20 token contract address
+///
+/// # Returns
+/// * `Result<Contract<Provider<Http>>, Box<dyn std::error::Error>>` - Contract instance
+pub fn get_contract_at(contract_address: H160) -> Result<Contract<Provider<Http>>, Box<dyn std::error::Error>> {
+    let rpc_url = get_rpc_url();
 
     // Create provider
     let provider = Provider::<Http>::try_from(rpc_url)?;
@@ -61,7 +84,7 @@ pub async fn get_contract() -> Result<(Contract<Provider<Http>>, H160), Box<dyn
     // Create contract instance
     let contract = Contract::new(contract_address, abi, provider.into());
 
-    Ok((contract, contract_address))
+    Ok(contract)
 }
 
 /// Query ERC20 token name
@@ -115,8 +138,8 @@ pub async fn get_token_decimals(contract: &Contract<Provider<Http>>) -> Result<u
 /// * `contract` - The contract instance
 ///
 /// # Returns
-/// * `Result<String, Box<dyn std::error::Error>>` - Total supply formatted with decimals
-pub async fn get_total_supply(contract: &Contract<Provider<Http>>) -> Result<String, Box<dyn std::error::Error>> {
+/// * `Result<Amount, Box<dyn std::error::Error>>` - Total supply scaled by the token decimals
+pub async fn get_total_supply(contract: &Contract<Provider<Http>>) -> Result<Amount, Box<dyn std::error::Error>> {
     let total_supply: U256 = contract
         .method("totalSupply", ())?
         .call()
@@ -124,47 +147,45 @@ pub async fn get_total_supply(contract: &Contract<Provider<Http>>) -> Result<Str
 
     let decimals = get_token_decimals(contract).await?;
 
-    // Calculate the divisor for decimals
-    let divisor = U256::from(10).pow(U256::from(decimals));
-    let whole_part = total_supply / divisor;
-    let fractional_part = total_supply % divisor;
-
-    Ok(format!("{}.{:0width$}",
-        whole_part,
-        fractional_part.as_u128(),
-        width = decimals as usize
-    ))
+    Ok(Amount::new(total_supply, decimals))
 }
 
-/// Query ERC20 token balance for an address
+/// Query raw ERC20 token balance (in the token's smallest unit) for an address
 ///
 /// # Arguments
 /// * `contract` - The contract instance
 /// * `address` - The address to query balance for
 ///
 /// # Returns
-/// * `Result<String, Box<dyn std::error::Error>>` - Balance formatted with token decimals
-pub async fn get_balance(
+/// * `Result<U256, Box<dyn std::error::Error>>` - Raw balance
+pub async fn get_raw_balance(
     contract: &Contract<Provider<Http>>,
     address: H160,
-) -> Result<String, Box<dyn std::error::Error>> {
+) -> Result<U256, Box<dyn std::error::Error>> {
     let balance: U256 = contract
         .method("balanceOf", address)?
         .call()
         .await?;
+    Ok(balance)
+}
 
-    let decimals = get_token_decimals(contract).await?;
+/// Query ERC20 token balance for an address
+///
+/// # Arguments
+/// * `contract` - The contract instance
+/// * `address` - The address to query balance for
+///
+/// # Returns
+/// * `Result<Amount, Box<dyn std::error::Error>>` - Balance scaled by the token decimals
+pub async fn get_balance(
+    contract: &Contract<Provider<Http>>,
+    address: H160,
+) -> Result<Amount, Box<dyn std::error::Error>> {
+    let balance = get_raw_balance(contract, address).await?;
 
-    // Manually format balance with decimals
-    let divisor = U256::from(10).pow(U256::from(decimals));
-    let whole_part = balance / divisor;
-    let fractional_part = balance % divisor;
+    let decimals = get_token_decimals(contract).await?;
 
-    Ok(format!("{}.{:0width$}",
-        whole_part,
-        fractional_part.as_u128(),
-        width = decimals as usize
-    ))
+    Ok(Amount::new(balance, decimals))
 }
 
 /// Display token information
@@ -194,11 +215,11 @@ pub async fn display_token_info(
          Decimals:          {}\n\
          Total Supply:      {} {}\n\
          =========================================================",
-        format!("{:#x}", contract_address),
+        checksummed(contract_address),
         name,
         symbol,
         decimals,
-        total_supply,
+        total_supply.to_grouped_string(),
         symbol
     );
 
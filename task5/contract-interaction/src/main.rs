@@ -1,19 +1,28 @@
 mod contract;
+mod portfolio;
 
 use contract::{
+    get_chain_id,
     get_contract,
+    get_contract_address,
     display_token_info,
     get_balance,
 };
-use ethers::prelude::*;
+use portfolio::{parse_address_list, resolve_address_list, resolve_tokens, get_portfolio, format_portfolio};
+use arb_common::address::checksummed;
+use arb_common::address_book::AddressBook;
 use std::env;
-use std::str::FromStr;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load environment variables
     dotenv::dotenv().ok();
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("portfolio") {
+        return run_portfolio(&args[1..]).await;
+    }
+
     println!("=========================================================");
     println!("    Arbitrum Sepolia ERC20 Contract Interaction");
     println!("=========================================================\n");
@@ -26,19 +35,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let token_info = display_token_info(&contract, contract_address).await?;
     println!("{}\n", token_info);
 
-    // Query balance for a specific address if provided
+    // Query balance for a specific address (or address book label) if provided
     let query_address_str = env::var("QUERY_ADDRESS").ok();
     if let Some(address_str) = query_address_str {
         println!("💰 Querying balance for address: {}\n", address_str);
 
-        match H160::from_str(&address_str) {
-            Ok(address) => {
+        let book = AddressBook::load_default()?;
+        match book.resolve(&address_str, get_chain_id().await?) {
+            Ok(parsed) => {
+                for warning in &parsed.warnings {
+                    println!("⚠️  {}\n", warning);
+                }
+                let address = parsed.address;
                 match get_balance(&contract, address).await {
                     Ok(balance) => {
                         println!("---------------------------------------------------------");
                         println!("Balance Query Result:");
                         println!("---------------------------------------------------------");
-                        println!("Address: {}", address_str);
+                        println!("Address: {}", book.display(address));
                         println!("Balance: {} tokens", balance);
                         println!("---------------------------------------------------------\n");
                     }
@@ -47,8 +61,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
             }
-            Err(_) => {
-                println!("⚠️  Invalid address format: {}\n", address_str);
+            Err(e) => {
+                println!("⚠️  {}\n", e);
             }
         }
     } else {
@@ -62,7 +76,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   ✓ Read token decimals");
     println!("   ✓ Read total supply");
     println!("   ✓ Query balance for any address");
-    println!("   ✓ View contract on Arbiscan: https://sepolia.arbiscan.io/token/{:#x}", contract_address);
+    println!("   ✓ View contract on Arbiscan: https://sepolia.arbiscan.io/token/{}", checksummed(contract_address));
     println!();
 
     println!("✨ Contract interaction completed successfully!");
@@ -70,3 +84,51 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Portfolio view: ETH plus a list of ERC20 tokens for a set of addresses
+///
+/// Usage: `contract-interaction portfolio [ADDRESS|LABEL...]`
+///
+/// Addresses default to `PORTFOLIO_ADDRESSES` (comma-separated) and the
+/// token list comes from `PORTFOLIO_TOKENS`, falling back to `CONTRACT_ADDRESS`.
+/// Addresses may be given as address book labels.
+async fn run_portfolio(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    println!("=========================================================");
+    println!("         Arbitrum Sepolia Portfolio");
+    println!("=========================================================\n");
+
+    let book = AddressBook::load_default()?;
+    let chain_id = get_chain_id().await?;
+
+    let parsed = if args.is_empty() {
+        let list = env::var("PORTFOLIO_ADDRESSES")
+            .map_err(|_| "No addresses given: pass them as arguments or set PORTFOLIO_ADDRESSES")?;
+        resolve_address_list(&list, &book, chain_id)?
+    } else {
+        resolve_address_list(&args.join(","), &book, chain_id)?
+    };
+    for address in &parsed {
+        for warning in &address.warnings {
+            println!("⚠️  {}: {}", checksummed(address.address), warning);
+        }
+    }
+    let addresses: Vec<_> = parsed.into_iter().map(|p| p.address).collect();
+
+    let tokens = match env::var("PORTFOLIO_TOKENS") {
+        Ok(list) => parse_address_list(&list)?,
+        Err(_) => vec![get_contract_address()?],
+    };
+
+    println!("🔎 Resolving {} token(s)...", tokens.len());
+    let tokens = resolve_tokens(&tokens).await?;
+    for token in &tokens {
+        println!("   {} ({} decimals)", token.symbol, token.decimals);
+    }
+    println!();
+
+    println!("💰 Querying balances for {} address(es)...\n", addresses.len());
+    let portfolios = get_portfolio(&addresses, &tokens).await?;
+    println!("{}\n", format_portfolio(&portfolios, &book)?);
+
+    Ok(())
+}
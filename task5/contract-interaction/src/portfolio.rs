@@ -0,0 +1,388 @@
+//! Multi-address, multi-token portfolio view
+//!
+//! Combines native ETH balances with a list of ERC20 tokens for a set of
+//! addresses, and aggregates per-address and grand totals. Holdings are
+//! valued in USD when a Chainlink feed is configured for the token's address.
+
+use crate::contract::{get_contract_at, get_raw_balance, get_rpc_url, get_token_decimals, get_token_symbol};
+use arb_common::address::{parse_address, ParsedAddress};
+use arb_common::address_book::AddressBook;
+use arb_common::amount::Amount;
+use arb_common::price::{format_usd, get_eth_usd_price, get_token_usd_price, to_usd_cents, PriceQuote};
+use ethers::prelude::*;
+
+/// Token metadata resolved from the token contract
+#[derive(Debug, Clone)]
+pub struct TokenInfo {
+    /// Token contract address (`None` for native ETH)
+    pub address: Option<H160>,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl TokenInfo {
+    /// Native ETH pseudo-token
+    pub fn eth() -> Self {
+        TokenInfo {
+            address: None,
+            symbol: "ETH".to_string(),
+            decimals: 18,
+        }
+    }
+}
+
+/// A single non-zero holding of one token
+#[derive(Debug, Clone)]
+pub struct Holding {
+    pub token: TokenInfo,
+    /// Raw balance in the token's smallest unit
+    pub raw: U256,
+    /// USD value in cents, when the token has a price feed
+    pub usd_cents: Option<U256>,
+}
+
+/// All non-zero holdings of one address
+#[derive(Debug, Clone)]
+pub struct AddressPortfolio {
+    pub address: H160,
+    pub holdings: Vec<Holding>,
+}
+
+/// Parse a comma-separated list of addresses
+///
+/// # Arguments
+/// * `list` - Addresses separated by commas, e.g. "0xabc...,0xdef..."
+///
+/// # Returns
+/// * `Result<Vec<H160>, Box<dyn std::error::Error>>` - Parsed addresses
+pub fn parse_address_list(list: &str) -> Result<Vec<H160>, Box<dyn std::error::Error>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| parse_address(s).map(|p| p.address).map_err(|e| e.into()))
+        .collect()
+}
+
+/// Resolve a comma-separated list of address book labels and addresses
+///
+/// # Arguments
+/// * `list` - Labels or addresses separated by commas
+/// * `book` - Address book to look labels up in
+/// * `chain_id` - Chain the addresses will be used on
+///
+/// # Returns
+/// * `Result<Vec<ParsedAddress>, Box<dyn std::error::Error>>` - Resolved addresses and their warnings
+pub fn resolve_address_list(
+    list: &str,
+    book: &AddressBook,
+    chain_id: u64,
+) -> Result<Vec<ParsedAddress>, Box<dyn std::error::Error>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| book.resolve(s, chain_id))
+        .collect()
+}
+
+/// Resolve symbol and decimals for each token contract
+///
+/// # Arguments
+/// * `tokens` - ERC20 token contract addresses
+///
+/// # Returns
+/// * `Result<Vec<TokenInfo>, Box<dyn std::error::Error>>` - Token metadata, in input order
+pub async fn resolve_tokens(tokens: &[H160]) -> Result<Vec<TokenInfo>, Box<dyn std::error::Error>> {
+    let mut infos = Vec::with_capacity(tokens.len());
+    for &token in tokens {
+        let contract = get_contract_at(token)?;
+        let symbol = get_token_symbol(&contract).await?;
+        let decimals = get_token_decimals(&contract).await?;
+        infos.push(TokenInfo {
+            address: Some(token),
+            symbol,
+            decimals,
+        });
+    }
+    Ok(infos)
+}
+
+/// Read the USD price of each token, skipping tokens without a usable feed
+///
+/// A stale or failing feed is reported and treated as "no price" so the
+/// rest of the portfolio can still be shown.
+///
+/// # Arguments
+/// * `provider` - Provider connected to the network
+/// * `tokens` - Tokens to price
+///
+/// # Returns
+/// * `Result<Vec<Option<PriceQuote>>, Box<dyn std::error::Error>>` - One entry per token, in input order
+pub async fn get_token_prices(
+    provider: &Provider<Http>,
+    tokens: &[TokenInfo],
+) -> Result<Vec<Option<PriceQuote>>, Box<dyn std::error::Error>> {
+    let mut prices = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let price = match token.address {
+            Some(address) => get_token_usd_price(provider, address).await,
+            None => get_eth_usd_price(provider).await,
+        };
+        match price {
+            Ok(quote) => prices.push(quote),
+            Err(e) => {
+                println!("⚠️  No USD price for {}: {}", token.symbol, e);
+                prices.push(None);
+            }
+        }
+    }
+    Ok(prices)
+}
+
+/// Build a holding, valuing it in USD when a price is available
+fn holding(token: &TokenInfo, raw: U256, price: Option<&PriceQuote>) -> Result<Holding, Box<dyn std::error::Error>> {
+    let usd_cents = match price {
+        Some(quote) => Some(to_usd_cents(raw, token.decimals, quote)?),
+        None => None,
+    };
+    Ok(Holding {
+        token: token.clone(),
+        raw,
+        usd_cents,
+    })
+}
+
+/// Query ETH and token balances for every address, keeping only non-zero holdings
+///
+/// # Arguments
+/// * `addresses` - Addresses to inspect
+/// * `tokens` - Resolved ERC20 tokens to include besides ETH
+///
+/// # Returns
+/// * `Result<Vec<AddressPortfolio>, Box<dyn std::error::Error>>` - One portfolio per address
+pub async fn get_portfolio(
+    addresses: &[H160],
+    tokens: &[TokenInfo],
+) -> Result<Vec<AddressPortfolio>, Box<dyn std::error::Error>> {
+    let provider = Provider::<Http>::try_from(get_rpc_url())?;
+
+    let mut contracts = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let address = token.address.ok_or("Token list must not contain native ETH")?;
+        contracts.push(get_contract_at(address)?);
+    }
+
+    let eth = TokenInfo::eth();
+    let eth_price = get_token_prices(&provider, std::slice::from_ref(&eth)).await?.remove(0);
+    let token_prices = get_token_prices(&provider, tokens).await?;
+
+    let mut portfolios = Vec::with_capacity(addresses.len());
+    for &address in addresses {
+        let mut holdings = Vec::new();
+
+        let eth_balance = provider.get_balance(address, None).await?;
+        if !eth_balance.is_zero() {
+            holdings.push(holding(&eth, eth_balance, eth_price.as_ref())?);
+        }
+
+        for ((token, contract), price) in tokens.iter().zip(&contracts).zip(&token_prices) {
+            let raw = get_raw_balance(contract, address).await?;
+            if !raw.is_zero() {
+                holdings.push(holding(token, raw, price.as_ref())?);
+            }
+        }
+
+        portfolios.push(AddressPortfolio { address, holdings });
+    }
+
+    Ok(portfolios)
+}
+
+/// Sum holdings per token across all addresses, in first-seen order
+///
+/// # Arguments
+/// * `portfolios` - Per-address portfolios
+///
+/// # Returns
+/// * `Result<Vec<Holding>, Box<dyn std::error::Error>>` - One aggregated holding per token,
+///   or an error if a sum overflows
+pub fn grand_totals(portfolios: &[AddressPortfolio]) -> Result<Vec<Holding>, Box<dyn std::error::Error>> {
+    let mut totals: Vec<Holding> = Vec::new();
+    for holding in portfolios.iter().flat_map(|p| &p.holdings) {
+        match totals.iter_mut().find(|t| t.token.address == holding.token.address) {
+            Some(total) => {
+                total.raw = total
+                    .raw
+                    .checked_add(holding.raw)
+                    .ok_or_else(|| format!("Total {} balance overflows 256 bits", total.token.symbol))?;
+                total.usd_cents = match (total.usd_cents, holding.usd_cents) {
+                    (Some(a), Some(b)) => Some(
+                        a.checked_add(b)
+                            .ok_or_else(|| format!("Total {} USD value overflows 256 bits", total.token.symbol))?,
+                    ),
+                    _ => None,
+                };
+            }
+            None => totals.push(holding.clone()),
+        }
+    }
+    Ok(totals)
+}
+
+/// Sum the USD value of holdings, returning `(total, all_priced)`
+///
+/// # Arguments
+/// * `holdings` - Holdings to sum
+///
+/// # Returns
+/// * `Result<(U256, bool), Box<dyn std::error::Error>>` - Total in cents and whether every
+///   holding had a price, or an error if the sum overflows
+pub fn usd_total(holdings: &[Holding]) -> Result<(U256, bool), Box<dyn std::error::Error>> {
+    let mut total = U256::zero();
+    let mut complete = true;
+    for holding in holdings {
+        match holding.usd_cents {
+            Some(cents) => total = total.checked_add(cents).ok_or("Total USD value overflows 256 bits")?,
+            None => complete = false,
+        }
+    }
+    Ok((total, complete))
+}
+
+/// Format a holding as "<amount> <symbol>" plus its USD value when known
+fn format_holding(holding: &Holding) -> String {
+    let amount = format!(
+        "{} {}",
+        Amount::new(holding.raw, holding.token.decimals).to_grouped_string(),
+        holding.token.symbol
+    );
+    match holding.usd_cents {
+        Some(cents) => format!("{} (≈ {})", amount, format_usd(cents)),
+        None => amount,
+    }
+}
+
+/// Format a USD total, flagging totals that leave out unpriced holdings
+fn format_usd_total(holdings: &[Holding]) -> Result<String, Box<dyn std::error::Error>> {
+    let (total, complete) = usd_total(holdings)?;
+    if complete {
+        Ok(format_usd(total))
+    } else {
+        Ok(format!("{} (excluding unpriced tokens)", format_usd(total)))
+    }
+}
+
+/// Render portfolios with per-address and grand totals
+///
+/// # Arguments
+/// * `portfolios` - Per-address portfolios
+/// * `book` - Address book used to label addresses
+///
+/// # Returns
+/// * `Result<String, Box<dyn std::error::Error>>` - Formatted report, or an error if a total overflows
+pub fn format_portfolio(portfolios: &[AddressPortfolio], book: &AddressBook) -> Result<String, Box<dyn std::error::Error>> {
+    let mut out = String::new();
+
+    for portfolio in portfolios {
+        out.push_str("---------------------------------------------------------\n");
+        out.push_str(&format!("Address: {}\n", book.display(portfolio.address)));
+        if portfolio.holdings.is_empty() {
+            out.push_str("   (no holdings)\n");
+        }
+        for holding in &portfolio.holdings {
+            out.push_str(&format!(
+                "   {:<8} {} (decimals: {})\n",
+                holding.token.symbol,
+                format_holding(holding),
+                holding.token.decimals
+            ));
+        }
+        out.push_str(&format!("   Holdings: {} non-zero\n", portfolio.holdings.len()));
+        out.push_str(&format!("   Total value: {}\n", format_usd_total(&portfolio.holdings)?));
+    }
+
+    out.push_str("=========================================================\n");
+    out.push_str(&format!("Grand Totals ({} address(es)):\n", portfolios.len()));
+    let totals = grand_totals(portfolios)?;
+    if totals.is_empty() {
+        out.push_str("   (no holdings)\n");
+    }
+    for total in &totals {
+        out.push_str(&format!("   {:<8} {}\n", total.token.symbol, format_holding(total)));
+    }
+    out.push_str(&format!("   Total value: {}\n", format_usd_total(&totals)?));
+    out.push_str("=========================================================");
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(byte: u8, symbol: &str, decimals: u8) -> TokenInfo {
+        TokenInfo {
+            address: Some(H160::repeat_byte(byte)),
+            symbol: symbol.to_string(),
+            decimals,
+        }
+    }
+
+    #[test]
+    fn test_parse_address_list() {
+        let list = "0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439, 0x7292dD72151DaCFBbE76305db1C8Ab1928E922E4,";
+        let addresses = parse_address_list(list).unwrap();
+        assert_eq!(addresses.len(), 2);
+        assert!(parse_address_list("0x123").is_err());
+        assert!(parse_address_list("0xD78677EFed3b87f8f421E68dA3F984ad8Ef76439").is_err());
+
+        let book: AddressBook = serde_json::from_str(
+            r#"{ "entries": [ { "label": "alice", "address": "0xd78677EFed3b87f8f421E68dA3F984ad8Ef76439" } ] }"#,
+        )
+        .unwrap();
+        let resolved = resolve_address_list("alice, 0x7292dD72151DaCFBbE76305db1C8Ab1928E922E4", &book, 421614).unwrap();
+        assert_eq!(resolved.iter().map(|p| p.address).collect::<Vec<_>>(), addresses);
+        assert!(resolve_address_list("bob", &book, 421614).is_err());
+    }
+
+    #[test]
+    fn test_grand_totals() {
+        let usdc = token(1, "USDC", 6);
+        let portfolios = vec![
+            AddressPortfolio {
+                address: H160::repeat_byte(0xaa),
+                holdings: vec![
+                    Holding { token: TokenInfo::eth(), raw: U256::from(5), usd_cents: None },
+                    Holding { token: usdc.clone(), raw: U256::from(1_000_000), usd_cents: Some(U256::from(100)) },
+                ],
+            },
+            AddressPortfolio {
+                address: H160::repeat_byte(0xbb),
+                holdings: vec![Holding { token: usdc, raw: U256::from(500_000), usd_cents: Some(U256::from(50)) }],
+            },
+        ];
+
+        let totals = grand_totals(&portfolios).unwrap();
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].token.symbol, "ETH");
+        assert_eq!(totals[0].raw, U256::from(5));
+        assert_eq!(totals[1].raw, U256::from(1_500_000));
+        assert_eq!(totals[1].usd_cents, Some(U256::from(150)));
+
+        assert_eq!(usd_total(&portfolios[1].holdings).unwrap(), (U256::from(50), true));
+        assert_eq!(usd_total(&totals).unwrap(), (U256::from(150), false));
+
+        let report = format_portfolio(&portfolios, &AddressBook::default()).unwrap();
+        assert!(report.contains("Holdings: 2 non-zero\n   Total value: $1.00 (excluding unpriced tokens)"));
+        assert!(report.contains("1.5 USDC (≈ $1.50)"));
+        assert!(report.contains("$1.50 (excluding unpriced tokens)"));
+
+        // Sums that overflow are errors, not silently capped
+        let mut overflowing = portfolios.clone();
+        overflowing[1].holdings[0].raw = U256::MAX;
+        assert!(grand_totals(&overflowing).is_err());
+        overflowing[1].holdings[0].raw = U256::one();
+        overflowing[1].holdings[0].usd_cents = Some(U256::MAX);
+        assert!(grand_totals(&overflowing).is_err());
+        assert!(format_portfolio(&overflowing, &AddressBook::default()).is_err());
+    }
+}